@@ -1,5 +1,9 @@
 use crate::eastmoney::StockData;
-use crate::indicators::calculate_macd;
+use crate::indicators::compute_macd_series;
+use crate::utils::time::TsMillis;
+
+/// Number of histogram values before a crossover used to normalise its strength.
+const CONFIDENCE_WINDOW: usize = 60;
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct TradeSignal {
@@ -10,8 +14,16 @@ pub struct TradeSignal {
 }
 
 pub fn analyze_signals(data: &[StockData]) -> Vec<TradeSignal> {
-    let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
-    let (_dif, _dea, macd) = calculate_macd(&closes);
+    let closes: Vec<(TsMillis, f64)> = data
+        .iter()
+        .enumerate()
+        // only the order matters, so the bar index stands in for a timestamp
+        .map(|(i, d)| (TsMillis(i as i64), d.close))
+        .collect();
+    let macd: Vec<f64> = compute_macd_series(&closes, 12, 26, 9)
+        .iter()
+        .map(|p| p.macd)
+        .collect();
     let mut signals = vec![];
 
     for i in 1..macd.len() {
@@ -20,7 +32,7 @@ pub fn analyze_signals(data: &[StockData]) -> Vec<TradeSignal> {
             signals.push(TradeSignal {
                 date: data[i].date.to_string(),
                 signal: "BUY".into(),
-                confidence: (macd[i].abs() * 10.0).min(100.0),
+                confidence: signal_confidence(&macd, i),
                 price: data[i].close,
             });
         }
@@ -29,10 +41,30 @@ pub fn analyze_signals(data: &[StockData]) -> Vec<TradeSignal> {
             signals.push(TradeSignal {
                 date: data[i].date.to_string(),
                 signal: "SELL".into(),
-                confidence: (macd[i].abs() * 10.0).min(100.0),
+                confidence: signal_confidence(&macd, i),
                 price: data[i].close,
             });
         }
     }
     signals
 }
+
+/// Confidence in [0, 100) of the crossover at `i`:
+///
+/// `100 * (1 - exp(-|h[i]| / σ))`
+///
+/// where `σ` is the standard deviation of the histogram over the
+/// `CONFIDENCE_WINDOW` points ending at `i`. Dividing by `σ` makes the score
+/// independent of the instrument's price scale; a crossover one σ strong scores
+/// ~63 and it only approaches 100 for outliers. A flat window scores 0.
+pub fn signal_confidence(histogram: &[f64], i: usize) -> f64 {
+    let window = &histogram[(i + 1).saturating_sub(CONFIDENCE_WINDOW)..=i];
+    let n = window.len() as f64;
+    let mean = window.iter().sum::<f64>() / n;
+    let variance = window.iter().map(|h| (h - mean).powi(2)).sum::<f64>() / n;
+    let sigma = variance.sqrt();
+    if sigma == 0.0 {
+        return 0.0;
+    }
+    100.0 * (1.0 - (-histogram[i].abs() / sigma).exp())
+}
@@ -1,17 +1,81 @@
 // src/app.rs
-use crate::config::AppConfig;
+use crate::backtest::{self, BacktestResult, MacdParams};
+use crate::config::{AppConfig, TradingConfig, clamp_days};
 use crate::error::{AppError, Result};
-use crate::indicators::{MACDPoint, compute_macd_series};
-use crate::storage::{Storage, Tick};
+use crate::feed::is_replayed;
+use crate::indicators::{
+    ADXCalc, CrossDirection, CrossEvent, LimitKind, MACDCalc, MACDPoint, OBVCalc, SMACalc,
+    SMAPoint, StrategyKind, VWAPCalc, apply_cooldown, apply_session_windows, compute_adx,
+    compute_macd_series, compute_macd_series_log, compute_obv, compute_sma_series, compute_vwap,
+    confirm_crosses, count_dif_zero_crossings, count_histogram_zero_crossings, detect_price_limits,
+    macd_cross_events, sma_cross_events,
+};
+use crate::models::Page;
+use crate::report::Report;
+use crate::storage::{Bar, BarBuilder, Storage, Tick, aggregate_bars, recent_days_range};
 
+use crate::utils::number_format;
+use crate::utils::time::{TsMillis, market_date, market_local_to_millis, market_today};
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use futures::stream::{self, StreamExt};
 use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tracing::{debug, instrument};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, info, instrument, warn};
+
+/// Entries kept in the per-day MACD cache before it is cleared.
+const DAY_MACD_CACHE_CAP: usize = 256;
+/// How long a computed market summary is served before it is rebuilt.
+const MARKET_SUMMARY_TTL: Duration = Duration::from_secs(5);
+/// Points `update_macd` remembers per symbol, so a stream client reading behind the
+/// others still gets the point of a tick that was already applied.
+const STREAMED_MACD_POINTS: usize = 1024;
 
 #[derive(Debug, Clone)]
 pub struct TradingApp {
     storage: Arc<Storage>,
     config: Arc<AppConfig>,
+    day_macd_cache: Arc<RwLock<HashMap<(String, NaiveDate), CachedDayMacd>>>,
+    day_macd_cache_hits: Arc<AtomicU64>,
+    day_macd_cache_misses: Arc<AtomicU64>,
+    /// set once `prime_indicator_cache` has finished
+    cache_primed: Arc<AtomicBool>,
+    market_summary_cache: Arc<RwLock<Option<(Instant, MarketSummary)>>>,
+    /// `update_macd` state per symbol, each behind its own lock so seeding one symbol
+    /// does not hold up the others
+    streaming_macd: Arc<RwLock<HashMap<String, StreamingSlot>>>,
+}
+
+/// Running MACD over one market day of a symbol's ticks, advanced one tick at a time.
+#[derive(Debug)]
+struct StreamingMacd {
+    date: NaiveDate,
+    calc: MACDCalc,
+    /// points of the newest ticks applied, oldest first, at most `STREAMED_MACD_POINTS`
+    recent: VecDeque<MACDPoint>,
+}
+
+/// A symbol's `StreamingMacd`, empty until its first `update_macd`.
+type StreamingSlot = Arc<Mutex<Option<StreamingMacd>>>;
+
+/// Outcome of `TradingApp::warm_range`, counted per (symbol, date).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct WarmSummary {
+    pub computed: usize,
+    /// already cached and still current
+    pub cached: usize,
+    pub failed: usize,
+}
+
+/// A day's MACD series, valid while the symbol's latest tick is unchanged.
+#[derive(Debug)]
+struct CachedDayMacd {
+    version: (usize, Option<TsMillis>),
+    points: Arc<Vec<MACDPoint>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -21,19 +85,260 @@ pub struct SymbolInfo {
     pub data_points: usize,
 }
 
+/// Market-wide tallies for the dashboard header.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketSummary {
+    pub symbol_count: usize,
+    /// ticks stored for the current market day, all symbols
+    pub ticks_today: usize,
+    /// symbols whose most recent MACD cross today was bullish / bearish
+    pub bullish_symbols: usize,
+    pub bearish_symbols: usize,
+}
+
 #[derive(Debug, Serialize)]
 pub struct MarketAnalysis {
     pub symbol: String,
+    /// false when no ticks exist in the window; all series/counts are then empty
+    pub has_data: bool,
+    /// false when the window has fewer than `trading.min_points` ticks; the MACD is then
+    /// still dominated by its seed, so no crossovers or signals are reported
+    pub sufficient_data: bool,
     pub macd_points: Vec<MACDPoint>,
+    /// rule that produced `cross_events` and the signal counts
+    pub strategy: StrategyKind,
+    pub cross_events: Vec<CrossEvent>,
     pub signal_count: usize,
     pub bullish_signals: usize,
     pub bearish_signals: usize,
+    /// leading points in which crossovers are not counted
+    pub warmup: usize,
+    /// short EMA crossing the long EMA (DIF changing sign): slower trend changes
+    pub dif_zero_crossings: usize,
+    /// DIF crossing DEA (histogram changing sign): faster momentum shifts
+    pub histogram_zero_crossings: usize,
+    /// (bar ts, kind) for one-minute bars at a daily price limit; the first day in the
+    /// window has no previous close and is never checked
+    pub limit_events: Vec<(TsMillis, LimitKind)>,
+    /// weighted by `trading.volume_basis`; `None` without any traded volume
+    #[serde(with = "number_format::option_float")]
+    pub vwap: Option<f64>,
+    /// on-balance volume at the last tick of the window
+    #[serde(with = "number_format::float")]
+    pub obv: f64,
+    /// latest ADX over one-minute bars (`trading.adx_period`), 0-100; `None` until
+    /// `2 * adx_period` bars exist
+    #[serde(with = "number_format::option_float")]
+    pub trend_strength: Option<f64>,
     pub analysis_period: String,
 }
 
+/// Per-request variations of `get_market_analysis_with`; the defaults give
+/// `get_market_analysis`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnalysisOptions {
+    /// signal rule; `trading.strategy` when unset
+    pub strategy: Option<StrategyKind>,
+    /// compute the MACD on `ln(price)`, see `compute_macd_series_log`
+    pub log_scale: bool,
+}
+
+/// Error for a tick `log_scale` cannot take the logarithm of.
+pub(crate) fn non_positive_price(ts: TsMillis) -> AppError {
+    AppError::invalid_parameter(format!(
+        "log_scale needs positive prices, the tick at {} is not",
+        ts
+    ))
+}
+
+/// Per-tick series and totals an analysis is derived from.
+struct AnalysisInputs {
+    tick_count: usize,
+    macd_points: Vec<MACDPoint>,
+    /// (short, long) SMA series, only for `StrategyKind::SmaCross`
+    sma: Option<(Vec<SMAPoint>, Vec<SMAPoint>)>,
+    limit_events: Vec<(TsMillis, LimitKind)>,
+    vwap: Option<f64>,
+    obv: f64,
+    trend_strength: Option<f64>,
+}
+
+/// Price limit hits found one tick at a time on one-minute bars that restart at each
+/// market day, each day judged against the previous day's last tick.
+struct LimitTracker {
+    tz: Tz,
+    limit_pct: f64,
+    /// market day of the ticks in `bar`
+    day: Option<Option<NaiveDate>>,
+    prev_close: Option<f64>,
+    day_close: Option<f64>,
+    bar: BarBuilder,
+    events: Vec<(TsMillis, LimitKind)>,
+}
+
+impl LimitTracker {
+    fn new(tz: Tz, limit_pct: f64) -> Self {
+        LimitTracker {
+            tz,
+            limit_pct,
+            day: None,
+            prev_close: None,
+            day_close: None,
+            bar: BarBuilder::new(60_000),
+            events: Vec::new(),
+        }
+    }
+
+    /// Feed a tick; returns the one-minute bar it closed, if any.
+    fn push(&mut self, t: &Tick) -> Option<Bar> {
+        let day = t.ts.to_datetime().map(|dt| market_date(self.tz, dt));
+        let mut closed = None;
+        if self.day != Some(day) {
+            closed = self.end_day();
+            self.day = Some(day);
+        }
+        if let Some(bar) = self.bar.push(t) {
+            closed = Some(self.close_bar(bar));
+        }
+        self.day_close = Some(t.price);
+        closed
+    }
+
+    fn end_day(&mut self) -> Option<Bar> {
+        let closed = std::mem::replace(&mut self.bar, BarBuilder::new(60_000))
+            .finish()
+            .map(|bar| self.close_bar(bar));
+        self.prev_close = self.day_close;
+        closed
+    }
+
+    fn close_bar(&mut self, bar: Bar) -> Bar {
+        if let Some(prev_close) = self.prev_close {
+            self.events.extend(detect_price_limits(
+                std::slice::from_ref(&bar),
+                prev_close,
+                self.limit_pct,
+            ));
+        }
+        bar
+    }
+
+    /// Close the open bar and return it with every hit found.
+    fn finish(mut self) -> (Option<Bar>, Vec<(TsMillis, LimitKind)>) {
+        let closed = self.end_day();
+        (closed, self.events)
+    }
+}
+
+/// Builds `AnalysisInputs` one tick at a time, holding only the output series plus the
+/// current one-minute bar, so long windows never load every tick. Bars restart at each
+/// market day, which matches whole-window bars as days begin on a minute boundary. The
+/// steps are the calculators behind the slice-based functions, so both paths give
+/// identical results.
+struct AnalysisFold {
+    log_scale: bool,
+    /// first tick `log_scale` could not take, reported by `finish`
+    non_positive_price: Option<TsMillis>,
+    macd: MACDCalc,
+    adx: ADXCalc,
+    sma: Option<(SMACalc, SMACalc)>,
+    vwap: VWAPCalc,
+    obv: OBVCalc,
+    limits: LimitTracker,
+    inputs: AnalysisInputs,
+}
+
+impl AnalysisFold {
+    fn new(
+        trading: &TradingConfig,
+        strategy: StrategyKind,
+        log_scale: bool,
+        limit_pct: f64,
+    ) -> Self {
+        let sma = match strategy {
+            StrategyKind::Macd => None,
+            StrategyKind::SmaCross => Some((
+                SMACalc::new(trading.sma_short),
+                SMACalc::new(trading.sma_long),
+            )),
+        };
+        AnalysisFold {
+            log_scale,
+            non_positive_price: None,
+            macd: MACDCalc::new(trading.macd_short, trading.macd_long, trading.macd_signal),
+            adx: ADXCalc::new(trading.adx_period),
+            inputs: AnalysisInputs {
+                tick_count: 0,
+                macd_points: Vec::new(),
+                sma: sma.as_ref().map(|_| (Vec::new(), Vec::new())),
+                limit_events: Vec::new(),
+                vwap: None,
+                obv: 0.0,
+                trend_strength: None,
+            },
+            sma,
+            vwap: VWAPCalc::new(trading.volume_basis),
+            obv: OBVCalc::new(trading.volume_basis),
+            limits: LimitTracker::new(trading.timezone, limit_pct),
+        }
+    }
+
+    fn push(&mut self, t: &Tick) {
+        self.inputs.tick_count += 1;
+
+        let point = if !self.log_scale {
+            self.macd.next_point(t.ts, t.price)
+        } else if t.price > 0.0 {
+            self.macd.next_log_point(t.ts, t.price)
+        } else {
+            self.non_positive_price.get_or_insert(t.ts);
+            self.macd.next_point(t.ts, t.price)
+        };
+        self.inputs.macd_points.push(point);
+        if let (Some((short, long)), Some((short_out, long_out))) =
+            (&mut self.sma, &mut self.inputs.sma)
+        {
+            for (calc, out) in [(short, short_out), (long, long_out)] {
+                out.push(SMAPoint {
+                    ts: t.ts,
+                    price: t.price,
+                    sma: calc.next(t.price),
+                });
+            }
+        }
+
+        self.inputs.vwap = self.vwap.next(t);
+        self.inputs.obv = self.obv.next(t);
+        if let Some(bar) = self.limits.push(t) {
+            self.inputs.trend_strength = self.adx.next(&bar).adx;
+        }
+    }
+
+    fn finish(mut self) -> Result<AnalysisInputs> {
+        if let Some(ts) = self.non_positive_price {
+            return Err(non_positive_price(ts));
+        }
+        let (bar, limit_events) = self.limits.finish();
+        if let Some(bar) = bar {
+            self.inputs.trend_strength = self.adx.next(&bar).adx;
+        }
+        self.inputs.limit_events = limit_events;
+        Ok(self.inputs)
+    }
+}
+
 impl TradingApp {
     pub fn new(storage: Arc<Storage>, config: Arc<AppConfig>) -> Self {
-        Self { storage, config }
+        Self {
+            storage,
+            config,
+            day_macd_cache: Arc::new(RwLock::new(HashMap::new())),
+            day_macd_cache_hits: Arc::new(AtomicU64::new(0)),
+            day_macd_cache_misses: Arc::new(AtomicU64::new(0)),
+            cache_primed: Arc::new(AtomicBool::new(false)),
+            market_summary_cache: Arc::new(RwLock::new(None)),
+            streaming_macd: Arc::new(RwLock::new(HashMap::new())),
+        }
     }
 
     #[instrument(skip(self))]
@@ -56,82 +361,553 @@ impl TradingApp {
         })
     }
 
-    #[instrument(skip(self))]
+    /// Market analysis using the configured `trading.strategy`.
     pub async fn get_market_analysis(
         &self,
         symbol: &str,
         days: Option<i64>,
     ) -> Result<MarketAnalysis> {
-        let analysis_days = days.unwrap_or(30);
+        self.get_market_analysis_with(symbol, days, AnalysisOptions::default())
+            .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_market_analysis_with(
+        &self,
+        symbol: &str,
+        days: Option<i64>,
+        options: AnalysisOptions,
+    ) -> Result<MarketAnalysis> {
+        let strategy = options.strategy.unwrap_or(self.config.trading.strategy);
+        let analysis_days = clamp_days(days.unwrap_or(30), self.config.trading.max_analysis_days);
         debug!(
             "Generating market analysis for {} over {} days",
             symbol, analysis_days
         );
 
-        let ticks = self
-            .storage
-            .get_ticks_recent_days(symbol, analysis_days)
-            .await?;
+        let trading = &self.config.trading;
+        let name = match self.storage.get_symbol_meta(symbol).await {
+            Ok(meta) => meta.map(|m| m.name),
+            Err(e) => {
+                warn!("Failed to load symbol meta for {}: {}", symbol, e);
+                None
+            }
+        };
+        let limit_pct = trading.price_limits.pct_for(symbol, name.as_deref());
+
+        let fold = AnalysisFold::new(trading, strategy, options.log_scale, limit_pct);
+        let inputs = if analysis_days > trading.streaming_analysis_days {
+            debug!(
+                "Streaming {} days of ticks for {} instead of loading them",
+                analysis_days, symbol
+            );
+            let (start, end) = recent_days_range(analysis_days);
+            self.storage
+                .fold_ticks_range(symbol, start, end, fold, |fold, tick| fold.push(&tick))
+                .await?
+                .finish()?
+        } else {
+            let ticks = self
+                .storage
+                .get_ticks_recent_days(symbol, analysis_days)
+                .await?;
+            Self::analysis_inputs(&ticks, trading, strategy, options.log_scale, limit_pct)?
+        };
+        let AnalysisInputs {
+            tick_count,
+            macd_points,
+            sma,
+            limit_events,
+            vwap,
+            obv,
+            trend_strength,
+        } = inputs;
 
-        if ticks.is_empty() {
-            return Err(AppError::DataNotFound(format!(
+        if tick_count == 0 {
+            debug!(
                 "No data found for symbol {} in the last {} days",
                 symbol, analysis_days
-            )));
+            );
         }
 
-        let price_points: Vec<(i64, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
-        let macd_points = compute_macd_series(&price_points);
-
-        let (bullish_signals, bearish_signals) = Self::count_macd_signals(&macd_points);
+        let macd_warmup = trading.macd_warmup.unwrap_or(trading.macd_long);
+        let (warmup, cross_events) = match sma {
+            None => (
+                macd_warmup,
+                confirm_crosses(
+                    macd_cross_events(&macd_points, macd_warmup),
+                    &macd_points,
+                    trading.min_histogram,
+                    trading.histogram_confirm_bars,
+                ),
+            ),
+            Some((short, long)) => (
+                trading.sma_long,
+                sma_cross_events(&short, &long, trading.sma_long),
+            ),
+        };
+        let min_points = trading.min_points.unwrap_or(trading.macd_long * 2);
+        let sufficient_data = tick_count >= min_points;
+        let (cross_events, dif_zero_crossings, histogram_zero_crossings) = if sufficient_data {
+            // masked first, so a suppressed cross does not start a cooldown
+            let cross_events =
+                apply_session_windows(cross_events, &trading.calendar(), &trading.signal_windows);
+            (
+                apply_cooldown(cross_events, trading.cooldown_secs as i64 * 1000),
+                count_dif_zero_crossings(&macd_points, macd_warmup),
+                count_histogram_zero_crossings(&macd_points, macd_warmup),
+            )
+        } else {
+            debug!(
+                "{} ticks for {} is below min_points {}, reporting no signals",
+                tick_count, symbol, min_points
+            );
+            (Vec::new(), 0, 0)
+        };
+        let (bullish_signals, bearish_signals) = Self::count_cross_events(&cross_events);
 
         Ok(MarketAnalysis {
             symbol: symbol.to_string(),
+            has_data: tick_count > 0,
+            sufficient_data,
             macd_points,
+            strategy,
+            cross_events,
             signal_count: bullish_signals + bearish_signals,
             bullish_signals,
             bearish_signals,
+            warmup,
+            dif_zero_crossings,
+            histogram_zero_crossings,
+            limit_events,
+            vwap,
+            obv,
+            trend_strength,
             analysis_period: format!("{} days", analysis_days),
         })
     }
 
+    /// MACD series over one day of ticks. Cached per (symbol, date) until a tick for that
+    /// day is stored.
     #[instrument(skip(self))]
-    pub async fn get_all_symbols_info(&self) -> Result<Vec<SymbolInfo>> {
-        let symbols = self.storage.get_symbols().await?;
-        let mut symbols_info = Vec::new();
-
-        for symbol in symbols {
-            match self.get_symbol_info(&symbol).await {
-                Ok(info) => symbols_info.push(info),
-                Err(e) => {
-                    debug!("Failed to get info for symbol {}: {}", symbol, e);
-                    // Continue with other symbols
+    pub async fn get_day_macd(&self, symbol: &str, date: NaiveDate) -> Result<Arc<Vec<MACDPoint>>> {
+        Ok(self.day_macd(symbol, date).await?.0)
+    }
+
+    /// `get_day_macd`, also telling whether the series came from the cache.
+    async fn day_macd(&self, symbol: &str, date: NaiveDate) -> Result<(Arc<Vec<MACDPoint>>, bool)> {
+        let version = self.storage.day_tick_version(symbol, date).await?;
+        let key = (symbol.to_string(), date);
+
+        if let Some(cached) = self.day_macd_cache.read().await.get(&key)
+            && cached.version == version
+        {
+            self.day_macd_cache_hits.fetch_add(1, Ordering::Relaxed);
+            debug!("Day MACD cache hit for {} on {}", symbol, date);
+            return Ok((cached.points.clone(), true));
+        }
+        self.day_macd_cache_misses.fetch_add(1, Ordering::Relaxed);
+
+        let ticks = self
+            .storage
+            .get_ticks_for_date(symbol, &date.format("%Y-%m-%d").to_string())
+            .await?;
+        let price_points: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+        let trading = &self.config.trading;
+        let points = Arc::new(compute_macd_series(
+            &price_points,
+            trading.macd_short,
+            trading.macd_long,
+            trading.macd_signal,
+        ));
+
+        let mut cache = self.day_macd_cache.write().await;
+        if cache.len() >= DAY_MACD_CACHE_CAP {
+            cache.clear();
+        }
+        cache.insert(
+            key,
+            CachedDayMacd {
+                version,
+                points: points.clone(),
+            },
+        );
+        Ok((points, false))
+    }
+
+    /// The day MACD point for `tick`, advancing per-symbol state by that one tick instead
+    /// of recomputing the day. The first tick of a symbol (or of a new market day) seeds
+    /// the state from the day's stored ticks before it, so the result matches the last
+    /// point of `get_day_macd` once `tick` is stored. A tick that was already applied (one
+    /// tick seen by several stream clients) returns its point again while it is among
+    /// the last `STREAMED_MACD_POINTS`; other ticks not newer than the last are rejected,
+    /// as are replayed ones.
+    #[instrument(skip(self, tick), fields(symbol = %tick.symbol))]
+    pub async fn update_macd(&self, tick: &Tick) -> Result<MACDPoint> {
+        if is_replayed(tick) {
+            return Err(AppError::invalid_parameter(format!(
+                "Replayed tick for {} at {} does not update the live MACD",
+                tick.symbol, tick.ts
+            )));
+        }
+        let slot = {
+            let states = self.streaming_macd.read().await;
+            states.get(&tick.symbol).cloned()
+        };
+        let slot = match slot {
+            Some(slot) => slot,
+            None => self
+                .streaming_macd
+                .write()
+                .await
+                .entry(tick.symbol.clone())
+                .or_default()
+                .clone(),
+        };
+        let mut state = slot.lock().await;
+
+        let trading = &self.config.trading;
+        let date = market_date(trading.timezone, tick.ts.to_datetime().unwrap_or_default());
+        match state.as_ref() {
+            Some(current) if current.date == date => {
+                if let Some(last) = current.recent.back()
+                    && tick.ts <= last.ts
+                {
+                    return match current.recent.binary_search_by_key(&tick.ts, |p| p.ts) {
+                        Ok(i) => Ok(current.recent[i].clone()),
+                        Err(_) => Err(AppError::invalid_parameter(format!(
+                            "Tick at {} is not after the last streamed tick at {}",
+                            tick.ts, last.ts
+                        ))),
+                    };
                 }
             }
+            _ => {
+                let mut calc =
+                    MACDCalc::new(trading.macd_short, trading.macd_long, trading.macd_signal);
+                let stored = self
+                    .storage
+                    .get_ticks_for_date(&tick.symbol, &date.format("%Y-%m-%d").to_string())
+                    .await?;
+                let mut seeded = 0;
+                for earlier in stored.iter().take_while(|t| t.ts < tick.ts) {
+                    calc.next(earlier.price);
+                    seeded += 1;
+                }
+                debug!(
+                    "Seeded streaming MACD for {} from {} ticks",
+                    tick.symbol, seeded
+                );
+                *state = Some(StreamingMacd {
+                    date,
+                    calc,
+                    recent: VecDeque::new(),
+                });
+            }
         }
 
-        Ok(symbols_info)
+        let current = state.as_mut().expect("seeded above");
+        let point = current.calc.next_point(tick.ts, tick.price);
+        if current.recent.len() == STREAMED_MACD_POINTS {
+            current.recent.pop_front();
+        }
+        current.recent.push_back(point.clone());
+        Ok(point)
     }
 
-    fn count_macd_signals(macd_points: &[MACDPoint]) -> (usize, usize) {
-        let mut bullish_signals = 0;
-        let mut bearish_signals = 0;
+    /// Number of `get_day_macd` calls answered from the cache.
+    pub fn day_macd_cache_hits(&self) -> u64 {
+        self.day_macd_cache_hits.load(Ordering::Relaxed)
+    }
 
-        for i in 1..macd_points.len() {
-            let prev = &macd_points[i - 1];
-            let current = &macd_points[i];
+    /// Number of `get_day_macd` calls that had to compute the series.
+    pub fn day_macd_cache_misses(&self) -> u64 {
+        self.day_macd_cache_misses.load(Ordering::Relaxed)
+    }
 
-            // Bullish signal: MACD crosses above zero
-            if prev.macd <= 0.0 && current.macd > 0.0 {
-                bullish_signals += 1;
-            }
-            // Bearish signal: MACD crosses below zero
-            else if prev.macd >= 0.0 && current.macd < 0.0 {
-                bearish_signals += 1;
-            }
+    /// Precompute today's MACD series for `symbols` so the first request for each is
+    /// served from the cache. Failures are logged and skipped.
+    #[instrument(skip(self))]
+    pub async fn prime_indicator_cache(&self, symbols: &[String]) {
+        let today = market_today(self.config.trading.timezone);
+        let concurrency = self.config.trading.symbol_info_concurrency.max(1);
+        info!(
+            "Priming MACD cache for {} symbols ({})",
+            symbols.len(),
+            today
+        );
+
+        let primed: usize = stream::iter(symbols.to_vec())
+            .map(|symbol| async move {
+                match self.get_day_macd(&symbol, today).await {
+                    Ok(points) => {
+                        debug!("Primed {} ({} points)", symbol, points.len());
+                        1
+                    }
+                    Err(e) => {
+                        warn!("Failed to prime MACD cache for {}: {}", symbol, e);
+                        0
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .fold(0, |acc, n| async move { acc + n })
+            .await;
+
+        info!("Primed MACD cache for {}/{} symbols", primed, symbols.len());
+        self.cache_primed.store(true, Ordering::Relaxed);
+    }
+
+    /// Compute and cache the day MACD series of every symbol for every trading day from
+    /// `from` to `to` (inclusive), `trading.symbol_info_concurrency` at a time. The
+    /// cache holds `DAY_MACD_CACHE_CAP` series, so larger ranges evict their own start.
+    #[instrument(skip(self))]
+    pub async fn warm_range(
+        &self,
+        symbols: &[String],
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> WarmSummary {
+        let concurrency = self.config.trading.symbol_info_concurrency.max(1);
+        let calendar = self.config.trading.calendar();
+        let dates: Vec<NaiveDate> = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| calendar.is_trading_day(*date))
+            .collect();
+        let pairs: Vec<(String, NaiveDate)> = symbols
+            .iter()
+            .flat_map(|symbol| dates.iter().map(move |date| (symbol.clone(), *date)))
+            .collect();
+
+        let summary = stream::iter(pairs)
+            .map(|(symbol, date)| async move {
+                let result = self.day_macd(&symbol, date).await;
+                if let Err(e) = &result {
+                    warn!(
+                        "Failed to warm MACD cache for {} on {}: {}",
+                        symbol, date, e
+                    );
+                }
+                result.map(|(_, cached)| cached)
+            })
+            .buffer_unordered(concurrency)
+            .fold(WarmSummary::default(), |mut summary, result| async move {
+                match result {
+                    Ok(true) => summary.cached += 1,
+                    Ok(false) => summary.computed += 1,
+                    Err(_) => summary.failed += 1,
+                }
+                summary
+            })
+            .await;
+        info!(
+            "Warmed MACD cache for {} symbols from {} to {}: {:?}",
+            symbols.len(),
+            from,
+            to,
+            summary
+        );
+        summary
+    }
+
+    /// Whether `prime_indicator_cache` has completed (even if some symbols failed).
+    pub fn cache_primed(&self) -> bool {
+        self.cache_primed.load(Ordering::Relaxed)
+    }
+
+    /// Backtest each MACD parameter combination over the ticks from `from` to `to`
+    /// (inclusive market dates), best total return first.
+    #[instrument(skip(self, combos))]
+    pub async fn backtest_sweep(
+        &self,
+        symbol: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        combos: Vec<MacdParams>,
+    ) -> Result<Vec<BacktestResult>> {
+        let tz = self.config.trading.timezone;
+        let start = market_local_to_millis(tz, from.and_time(NaiveTime::MIN));
+        let end = market_local_to_millis(
+            tz,
+            (to + chrono::Duration::days(1)).and_time(NaiveTime::MIN),
+        );
+        let ticks = self.storage.get_ticks_range(symbol, start, end).await?;
+        info!(
+            "Sweeping {} MACD parameter sets for {} over {} ticks",
+            combos.len(),
+            symbol,
+            ticks.len()
+        );
+
+        let points: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+        Ok(backtest::sweep(Arc::new(points), combos).await)
+    }
+
+    /// Latest tick plus the market analysis, bundled for `/api/report`.
+    #[instrument(skip(self))]
+    pub async fn build_report(&self, symbol: &str, days: Option<i64>) -> Result<Report> {
+        let latest_tick = self.storage.get_latest_tick(symbol).await?;
+        let analysis = self.get_market_analysis(symbol, days).await?;
+        Ok(Report::new(
+            analysis,
+            latest_tick,
+            self.config.trading.timezone,
+        ))
+    }
+
+    /// One page of symbol infos. Pages are cut in symbol order and `items` is always
+    /// sorted by ascending symbol, even though infos are fetched concurrently
+    /// (`trading.symbol_info_concurrency`). Symbols whose lookup fails are left out.
+    #[instrument(skip(self))]
+    pub async fn get_all_symbols_info(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Page<SymbolInfo>> {
+        let (symbols, total) = self.storage.get_symbols_page(limit, offset).await?;
+        let concurrency = self.config.trading.symbol_info_concurrency.max(1);
+
+        let symbols_info = collect_sorted_by_symbol(symbols, concurrency, |symbol| async move {
+            self.get_symbol_info(&symbol).await
+        })
+        .await;
+
+        Ok(Page {
+            items: symbols_info,
+            total,
+            limit,
+            offset,
+        })
+    }
+
+    /// Symbol and tick counts plus each symbol's latest MACD cross direction today.
+    /// Results are reused for `MARKET_SUMMARY_TTL` so dashboard polling stays cheap.
+    #[instrument(skip(self))]
+    pub async fn market_summary(&self) -> Result<MarketSummary> {
+        if let Some((computed_at, summary)) = self.market_summary_cache.read().await.as_ref()
+            && computed_at.elapsed() < MARKET_SUMMARY_TTL
+        {
+            return Ok(summary.clone());
         }
 
-        (bullish_signals, bearish_signals)
+        let trading = &self.config.trading;
+        let today = market_today(trading.timezone);
+        let start = market_local_to_millis(trading.timezone, today.and_time(NaiveTime::MIN));
+        let end = market_local_to_millis(
+            trading.timezone,
+            (today + chrono::Duration::days(1)).and_time(NaiveTime::MIN),
+        );
+        let symbols = self.storage.get_symbols().await?;
+        let ticks_today = self.storage.count_ticks_range(start, end).await?;
+
+        let warmup = trading.macd_warmup.unwrap_or(trading.macd_long);
+        let calendar = &trading.calendar();
+        let concurrency = trading.symbol_info_concurrency.max(1);
+        let (bullish_symbols, bearish_symbols) = stream::iter(symbols.clone())
+            .map(|symbol| async move {
+                match self.storage.get_ticks_range(&symbol, start, end).await {
+                    Ok(ticks) => {
+                        let points: Vec<(TsMillis, f64)> =
+                            ticks.iter().map(|t| (t.ts, t.price)).collect();
+                        let macd = compute_macd_series(
+                            &points,
+                            trading.macd_short,
+                            trading.macd_long,
+                            trading.macd_signal,
+                        );
+                        let events = confirm_crosses(
+                            macd_cross_events(&macd, warmup),
+                            &macd,
+                            trading.min_histogram,
+                            trading.histogram_confirm_bars,
+                        );
+                        apply_session_windows(events, calendar, &trading.signal_windows)
+                            .last()
+                            .map(|event| event.direction)
+                    }
+                    Err(e) => {
+                        debug!("Skipping {} in market summary: {}", symbol, e);
+                        None
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .fold((0, 0), |(bull, bear), direction| async move {
+                match direction {
+                    Some(CrossDirection::Bullish) => (bull + 1, bear),
+                    Some(CrossDirection::Bearish) => (bull, bear + 1),
+                    None => (bull, bear),
+                }
+            })
+            .await;
+
+        let summary = MarketSummary {
+            symbol_count: symbols.len(),
+            ticks_today,
+            bullish_symbols,
+            bearish_symbols,
+        };
+        *self.market_summary_cache.write().await = Some((Instant::now(), summary.clone()));
+        Ok(summary)
+    }
+
+    /// (bullish, bearish) totals of a cross event list.
+    pub fn count_cross_events(events: &[CrossEvent]) -> (usize, usize) {
+        events
+            .iter()
+            .fold((0, 0), |(bull, bear), event| match event.direction {
+                CrossDirection::Bullish => (bull + 1, bear),
+                CrossDirection::Bearish => (bull, bear + 1),
+            })
+    }
+
+    /// `AnalysisInputs` from ticks already in memory.
+    fn analysis_inputs(
+        ticks: &[Tick],
+        trading: &TradingConfig,
+        strategy: StrategyKind,
+        log_scale: bool,
+        limit_pct: f64,
+    ) -> Result<AnalysisInputs> {
+        let price_points: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+        let sma = match strategy {
+            StrategyKind::Macd => None,
+            StrategyKind::SmaCross => Some((
+                compute_sma_series(&price_points, trading.sma_short),
+                compute_sma_series(&price_points, trading.sma_long),
+            )),
+        };
+        let (short, long, signal) = (trading.macd_short, trading.macd_long, trading.macd_signal);
+        let macd_points = if log_scale {
+            compute_macd_series_log(&price_points, short, long, signal)
+                .map_err(|i| non_positive_price(price_points[i].0))?
+        } else {
+            compute_macd_series(&price_points, short, long, signal)
+        };
+        let bars = aggregate_bars(ticks, 60_000);
+        Ok(AnalysisInputs {
+            tick_count: ticks.len(),
+            macd_points,
+            sma,
+            limit_events: Self::limit_events(ticks, trading.timezone, limit_pct),
+            vwap: compute_vwap(ticks, trading.volume_basis),
+            obv: compute_obv(ticks, trading.volume_basis)
+                .last()
+                .map_or(0.0, |(_, obv)| *obv),
+            trend_strength: compute_adx(&bars, trading.adx_period)
+                .last()
+                .and_then(|p| p.adx),
+        })
+    }
+
+    /// Price limit hits per market day, each day judged against the previous day's last tick.
+    fn limit_events(ticks: &[Tick], tz: Tz, limit_pct: f64) -> Vec<(TsMillis, LimitKind)> {
+        let mut limits = LimitTracker::new(tz, limit_pct);
+        for t in ticks {
+            limits.push(t);
+        }
+        limits.finish().1
     }
 
     pub fn get_config(&self) -> &AppConfig {
@@ -142,3 +918,32 @@ impl TradingApp {
         &self.storage
     }
 }
+
+/// Run `fetch` for every symbol with at most `concurrency` in flight and return the
+/// successful results sorted by symbol, independent of completion order. Failures are
+/// logged and dropped.
+pub(crate) async fn collect_sorted_by_symbol<F, Fut>(
+    symbols: Vec<String>,
+    concurrency: usize,
+    fetch: F,
+) -> Vec<SymbolInfo>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<SymbolInfo>>,
+{
+    let mut infos: Vec<SymbolInfo> = stream::iter(symbols)
+        .map(|symbol| {
+            let info = fetch(symbol.clone());
+            async move {
+                info.await
+                    .inspect_err(|e| debug!("Failed to get info for symbol {}: {}", symbol, e))
+                    .ok()
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|info| async move { info })
+        .collect()
+        .await;
+    infos.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    infos
+}
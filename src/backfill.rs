@@ -0,0 +1,48 @@
+// src/backfill.rs
+use crate::data_fetch::KlineSource;
+use crate::storage::{Storage, Tick};
+use crate::utils::tick_size::TickSizeTable;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use tracing::{info, instrument};
+
+/// Pull historical klines for `symbol` and persist each one as a `Tick`
+/// (ts = kline timestamp, price = close, vol = volume) so the MACD pipeline can
+/// run on them offline, closes snapped to `tick_sizes`. Ticks already in storage are
+/// left untouched.
+/// Returns the number of newly inserted ticks.
+#[instrument(skip(source, storage, tick_sizes))]
+pub async fn backfill_klines<S: KlineSource>(
+    source: &S,
+    storage: &Storage,
+    tick_sizes: &TickSizeTable,
+    symbol: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    period: &str,
+) -> Result<usize> {
+    let klines = source
+        .fetch_klines(symbol, from, to, period)
+        .await
+        .with_context(|| format!("Failed to fetch klines for {}", symbol))?;
+
+    let ticks: Vec<Tick> = klines
+        .iter()
+        .map(|k| Tick {
+            source: Some("backfill".to_string()),
+            ..Tick::from_kline(k, tick_sizes)
+        })
+        .collect();
+
+    let inserted = storage.insert_ticks_ignore(&ticks).await?;
+    info!(
+        "Backfilled {} of {} klines for {} ({} to {}, {})",
+        inserted,
+        ticks.len(),
+        symbol,
+        from,
+        to,
+        period
+    );
+    Ok(inserted)
+}
@@ -0,0 +1,120 @@
+// src/backtest.rs
+use crate::indicators::{CrossDirection, compute_macd_series, macd_cross_events};
+use crate::utils::number_format;
+use crate::utils::time::TsMillis;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Upper bound on parameter combinations evaluated by one sweep request.
+pub const MAX_SWEEP_COMBINATIONS: usize = 256;
+/// Backtests run at once during a sweep (each one is CPU-bound).
+const SWEEP_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacdParams {
+    pub short: usize,
+    pub long: usize,
+    pub signal: usize,
+}
+
+/// Candidate values per MACD period; a sweep tries every combination.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParamGrid {
+    pub short: Vec<usize>,
+    pub long: Vec<usize>,
+    pub signal: Vec<usize>,
+}
+
+impl ParamGrid {
+    /// Size of the full cartesian product, before invalid combinations are dropped.
+    pub fn size(&self) -> usize {
+        self.short.len() * self.long.len() * self.signal.len()
+    }
+
+    /// Every combination with `0 < short < long` and a non-zero signal period.
+    pub fn combinations(&self) -> Vec<MacdParams> {
+        let mut out = Vec::new();
+        for &short in &self.short {
+            for &long in &self.long {
+                if short == 0 || short >= long {
+                    continue;
+                }
+                for &signal in self.signal.iter().filter(|s| **s > 0) {
+                    out.push(MacdParams {
+                        short,
+                        long,
+                        signal,
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestResult {
+    pub params: MacdParams,
+    /// completed round trips, including a position closed at the last price
+    pub trades: usize,
+    /// compounded return of all trades, 0.05 = +5%
+    #[serde(with = "number_format::float")]
+    pub total_return: f64,
+}
+
+/// Long-only MACD backtest: buy on a bullish histogram cross, sell on the next bearish
+/// one, and close any open position at the final price. Crosses within the first
+/// `long` points are ignored while the EMAs warm up.
+pub fn run_backtest(points: &[(TsMillis, f64)], params: MacdParams) -> BacktestResult {
+    let macd = compute_macd_series(points, params.short, params.long, params.signal);
+    let mut equity = 1.0;
+    let mut trades = 0;
+    let mut entry: Option<f64> = None;
+
+    for event in macd_cross_events(&macd, params.long) {
+        match (event.direction, entry) {
+            (CrossDirection::Bullish, None) => entry = Some(event.price),
+            (CrossDirection::Bearish, Some(price)) => {
+                equity *= event.price / price;
+                trades += 1;
+                entry = None;
+            }
+            _ => {}
+        }
+    }
+    if let (Some(price), Some(last)) = (entry, points.last()) {
+        equity *= last.1 / price;
+        trades += 1;
+    }
+
+    BacktestResult {
+        params,
+        trades,
+        total_return: equity - 1.0,
+    }
+}
+
+/// Backtest every combination over `points`, best total return first.
+pub async fn sweep(
+    points: Arc<Vec<(TsMillis, f64)>>,
+    combos: Vec<MacdParams>,
+) -> Vec<BacktestResult> {
+    let mut results: Vec<BacktestResult> = stream::iter(combos)
+        .map(|params| {
+            let points = points.clone();
+            tokio::task::spawn_blocking(move || run_backtest(&points, params))
+        })
+        .buffer_unordered(SWEEP_CONCURRENCY)
+        .filter_map(|joined| async move {
+            joined
+                .inspect_err(|e| warn!("Backtest task failed: {}", e))
+                .ok()
+        })
+        .collect()
+        .await;
+
+    results.sort_by(|a, b| b.total_return.total_cmp(&a.total_return));
+    results
+}
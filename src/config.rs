@@ -1,18 +1,120 @@
 // src/config.rs
+use crate::indicators::{PriceSource, StrategyKind, VolumeBasis};
+use crate::models::RunMode;
+use crate::storage::{BarTimestamp, DEFAULT_SQLITE_WORKERS};
+use crate::utils::calendar::{SessionWindow, TradingCalendar};
+use crate::utils::number_format::NumberFormat;
+use crate::utils::tick_size::TickSizeTable;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
 use config::{Config, ConfigError, File, FileFormat};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
+use std::path::Path;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseConfig {
     pub sqlite_path: String,
     pub redis_url: String,
+    /// skip same-price ticks arriving within the same second as the last stored one
+    pub dedupe_ticks: bool,
+    /// a tick priced more than this many percent away from the median of the symbol's
+    /// last few stored ticks is an outlier; unset disables the check
+    #[serde(default)]
+    pub outlier_pct: Option<f64>,
+    #[serde(default)]
+    pub outlier_action: OutlierAction,
+    #[serde(default)]
+    pub on_duplicate: DuplicateTickAction,
+    /// threads running SQLite queries; a burst beyond this queues for them
+    #[serde(default = "default_worker_threads")]
+    pub worker_threads: usize,
+    /// mode the service starts in; tick writes from the other mode's writer (the
+    /// poller is `real`, the simulator `sim`) are dropped until `/api/set_mode`
+    #[serde(default = "default_run_mode")]
+    pub run_mode: RunMode,
+}
+
+fn default_worker_threads() -> usize {
+    DEFAULT_SQLITE_WORKERS
+}
+
+fn default_run_mode() -> RunMode {
+    RunMode::Real
+}
+
+/// What `save_tick` does with a tick whose (symbol, ts) is already stored.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateTickAction {
+    /// overwrite the stored tick: late corrections win, but history can change
+    #[default]
+    Replace,
+    /// keep the stored tick and skip the new one: append-only, corrections are lost
+    Ignore,
+    /// fail the write with a 409 conflict and roll back the rest of its batch:
+    /// append-only, and the caller hears about every conflict
+    Error,
+}
+
+/// What `save_tick` does with an outlier tick.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlierAction {
+    /// drop it
+    #[default]
+    Reject,
+    /// store it with `outlier = 1` for inspection, but leave it out of tick reads,
+    /// the Redis cache and the outlier median, and log a warning
+    Flag,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// seconds a client gets to send its request head; unrelated to upstream fetches
+    pub timeout: u64,
+    /// compute today's MACD for the watchlist in the background at startup
+    #[serde(default)]
+    pub prime_cache: bool,
+    /// ticks a single history/ohlc response may be built from; larger ranges get a 413
+    #[serde(default = "default_max_response_points")]
+    pub max_response_points: usize,
+    /// decimals kept for price/dif/dea/macd in MACD responses; computation is unaffected
+    #[serde(default = "default_response_decimals")]
+    pub response_decimals: u32,
+    /// bearer token for admin endpoints (`/api/gen_sim`); they are disabled when unset
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// frontend files; unknown non-API paths fall back to its `index.html`
+    #[serde(default = "default_static_dir")]
+    pub static_dir: String,
+    /// largest JSON request body accepted; bigger ones get a 413
+    #[serde(default = "default_max_payload_bytes")]
+    pub max_payload_bytes: usize,
+    /// ticks buffered per `/api/stream` client before it counts as too slow
+    #[serde(default = "default_stream_buffer")]
+    pub stream_buffer: usize,
+    #[serde(default)]
+    pub slow_client_policy: SlowClientPolicy,
+    /// JSON form of prices, volumes and MACD values: numbers or exact strings
+    #[serde(default)]
+    pub number_format: NumberFormat,
+}
+
+/// What a `/api/stream` connection does once its client falls `stream_buffer` ticks
+/// behind. Either way the buffer stays bounded and other clients are unaffected.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SlowClientPolicy {
+    /// skip the overwritten ticks and carry on from the oldest one still buffered
+    #[default]
+    DropOldest,
+    /// end the stream; the client has to reconnect
+    Disconnect,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -21,6 +123,355 @@ pub struct TradingConfig {
     pub macd_short: usize,
     pub macd_long: usize,
     pub macd_signal: usize,
+    /// crossovers within the first N MACD points are ignored; defaults to `macd_long`
+    #[serde(default)]
+    pub macd_warmup: Option<usize>,
+    /// analyses over fewer ticks than this report no signals; defaults to `macd_long * 2`
+    #[serde(default)]
+    pub min_points: Option<usize>,
+    /// symbols primed and polled at startup; falls back to `default_symbol` when empty
+    #[serde(default)]
+    pub watchlist: Vec<WatchlistEntry>,
+    /// poll the watchlist for live quotes in the background
+    #[serde(default)]
+    pub poll_enabled: bool,
+    /// default seconds between quote polls, per symbol
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// keep polling outside trading sessions (24h markets, or any market not on China
+    /// A-share hours); by default polls are skipped before the open, over lunch, after
+    /// the close and on non-trading days
+    #[serde(default)]
+    pub poll_outside_sessions: bool,
+    /// upper bound for any `days` parameter; see `clamp_days`
+    #[serde(default = "default_max_analysis_days")]
+    pub max_analysis_days: i64,
+    /// analyses over more days than this stream ticks from SQLite instead of loading
+    /// the whole window into memory
+    #[serde(default = "default_streaming_analysis_days")]
+    pub streaming_analysis_days: i64,
+    /// signal rule used by `get_market_analysis`
+    #[serde(default)]
+    pub strategy: StrategyKind,
+    /// a signal repeating the previous one's direction within this many seconds is
+    /// suppressed; 0 disables the cooldown
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    /// crosses are only reported inside these windows, counted from the day's open or
+    /// close (or each session's, per window); empty reports them all day
+    #[serde(default)]
+    pub signal_windows: Vec<SessionWindow>,
+    /// a MACD cross only counts once the histogram exceeds this magnitude (in its new
+    /// direction) within `histogram_confirm_bars` points of it; 0 accepts every cross
+    #[serde(default)]
+    pub min_histogram: f64,
+    #[serde(default = "default_histogram_confirm_bars")]
+    pub histogram_confirm_bars: usize,
+    /// whether VWAP and OBV weight ticks by shares or by turnover
+    #[serde(default)]
+    pub volume_basis: VolumeBasis,
+    /// whether resampled bars and points carry their first tick's time or the bucket
+    /// boundary
+    #[serde(default)]
+    pub bar_timestamp: BarTimestamp,
+    /// bar price the MACD is computed from when ticks are resampled into bars; raw
+    /// ticks only have one price
+    #[serde(default)]
+    pub price_source: PriceSource,
+    #[serde(default = "default_sma_short")]
+    pub sma_short: usize,
+    #[serde(default = "default_sma_long")]
+    pub sma_long: usize,
+    /// bars (one minute each) per ADX smoothing step for `trend_strength`
+    #[serde(default = "default_adx_period")]
+    pub adx_period: usize,
+    /// max number of `get_symbol_info` lookups in flight for `/api/symbols`
+    pub symbol_info_concurrency: usize,
+    /// exchange timezone used to decide what "today" is
+    pub timezone: Tz,
+    /// exchange holidays on weekdays; weekends are never trading days
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+    #[serde(default)]
+    pub price_limits: PriceLimitConfig,
+    /// minimum price increments applied to stored ticks and simulated fills
+    #[serde(default)]
+    pub tick_sizes: TickSizeTable,
+}
+
+/// Daily price limit percentages by board.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PriceLimitConfig {
+    pub default_pct: f64,
+    /// risk-warned (ST / *ST) names
+    pub st_pct: f64,
+    /// STAR Market (688xxx)
+    pub star_pct: f64,
+}
+
+impl Default for PriceLimitConfig {
+    fn default() -> Self {
+        Self {
+            default_pct: 10.0,
+            st_pct: 5.0,
+            star_pct: 20.0,
+        }
+    }
+}
+
+impl PriceLimitConfig {
+    /// Largest legitimate move within one day, in percent: from limit-down to limit-up
+    /// on the widest board (20% limits allow +50%).
+    pub fn max_swing_pct(&self) -> f64 {
+        let widest = self.default_pct.max(self.st_pct).max(self.star_pct) / 100.0;
+        if widest >= 1.0 {
+            return f64::INFINITY;
+        }
+        ((1.0 + widest) / (1.0 - widest) - 1.0) * 100.0
+    }
+
+    /// Limit percentage for `symbol`; `name` (when known) identifies ST stocks.
+    pub fn pct_for(&self, symbol: &str, name: Option<&str>) -> f64 {
+        if symbol.starts_with("688") {
+            self.star_pct
+        } else if name.is_some_and(|n| n.to_uppercase().contains("ST")) {
+            self.st_pct
+        } else {
+            self.default_pct
+        }
+    }
+}
+
+/// A watchlist item: either a bare symbol or a table with per-symbol overrides, e.g.
+/// `{ symbol = "600733.SH", poll_interval_secs = 30 }`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum WatchlistEntry {
+    Symbol(String),
+    Detailed {
+        symbol: String,
+        poll_interval_secs: Option<u64>,
+    },
+}
+
+impl WatchlistEntry {
+    pub fn symbol(&self) -> &str {
+        match self {
+            WatchlistEntry::Symbol(symbol) | WatchlistEntry::Detailed { symbol, .. } => symbol,
+        }
+    }
+}
+
+fn default_max_response_points() -> usize {
+    500_000
+}
+
+fn default_max_payload_bytes() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_stream_buffer() -> usize {
+    1024
+}
+
+fn default_static_dir() -> String {
+    "./static".to_string()
+}
+
+fn default_response_decimals() -> u32 {
+    4
+}
+
+fn default_max_analysis_days() -> i64 {
+    365
+}
+
+/// The one rule for a `days` value on any endpoint: clamped to `1..=max_days`, so zero
+/// or a negative count means one day and an oversized one `max_days`.
+pub fn clamp_days(days: i64, max_days: i64) -> i64 {
+    days.clamp(1, max_days.max(1))
+}
+
+fn default_streaming_analysis_days() -> i64 {
+    90
+}
+
+fn default_poll_interval_secs() -> u64 {
+    5
+}
+
+fn default_sma_short() -> usize {
+    5
+}
+
+fn default_sma_long() -> usize {
+    20
+}
+
+fn default_histogram_confirm_bars() -> usize {
+    3
+}
+
+fn default_adx_period() -> usize {
+    14
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SourceConfig {
+    pub enabled: bool,
+    pub base_url: String,
+    pub user_agent: String,
+    /// whole-request timeout for calls to this source
+    #[serde(default = "default_source_timeout_secs")]
+    pub timeout_secs: u64,
+    /// overrides for the upstream field codes read by the JSON parsers
+    #[serde(default)]
+    pub fields: FieldMap,
+}
+
+/// Upstream field code for each logical attribute a JSON source is parsed into.
+/// Quote attributes use the `Quote` field names (`price`, `bid_price`, ...); depth
+/// levels are `bid1_price` .. `ask5_volume`; `name` is the display name.
+const DEFAULT_FIELD_CODES: &[(&str, &str)] = &[
+    ("name", "f58"),
+    ("price", "f43"),
+    ("high", "f44"),
+    ("low", "f45"),
+    ("open", "f46"),
+    ("prev_close", "f47"),
+    ("volume", "f48"),
+    ("amount", "f49"),
+    ("change", "f134"),
+    ("change_pct", "f135"),
+    ("bid_price", "f18"),
+    ("ask_price", "f19"),
+    ("bid_volume", "f10"),
+    ("ask_volume", "f11"),
+    ("bid1_price", "f1"),
+    ("bid2_price", "f2"),
+    ("bid3_price", "f3"),
+    ("bid4_price", "f4"),
+    ("bid5_price", "f5"),
+    ("bid1_volume", "f6"),
+    ("bid2_volume", "f7"),
+    ("bid3_volume", "f8"),
+    ("bid4_volume", "f9"),
+    ("bid5_volume", "f10"),
+    ("ask1_price", "f11"),
+    ("ask2_price", "f12"),
+    ("ask3_price", "f13"),
+    ("ask4_price", "f14"),
+    ("ask5_price", "f15"),
+    ("ask1_volume", "f16"),
+    ("ask2_volume", "f17"),
+    ("ask3_volume", "f18"),
+    ("ask4_volume", "f19"),
+    ("ask5_volume", "f20"),
+];
+
+/// Logical attribute -> field code, e.g. `price = "f43"`. Only the codes that
+/// differ from `DEFAULT_FIELD_CODES` need configuring.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct FieldMap(HashMap<String, String>);
+
+impl FieldMap {
+    pub fn code<'a>(&'a self, name: &str) -> &'a str {
+        if let Some(code) = self.0.get(name) {
+            return code;
+        }
+        DEFAULT_FIELD_CODES
+            .iter()
+            .find(|(logical, _)| *logical == name)
+            .map(|(_, code)| *code)
+            .unwrap_or_else(|| panic!("no default field code for `{}`", name))
+    }
+}
+
+fn default_source_timeout_secs() -> u64 {
+    10
+}
+
+/// Max decimal places kept when parsing upstream numbers.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PrecisionConfig {
+    pub price: u32,
+    pub volume: u32,
+    pub amount: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DataSourceConfig {
+    pub eastmoney: SourceConfig,
+    pub baidu: SourceConfig,
+    pub sina: SourceConfig,
+    #[serde(default)]
+    pub cache: CacheTtlConfig,
+    /// most responses the fetcher keeps cached; the least recently used goes first
+    #[serde(default = "default_cache_max_entries")]
+    pub cache_max_entries: usize,
+    /// serve generated quotes/klines when every real source fails (demo only)
+    pub allow_simulated_fallback: bool,
+    /// upstream requests in flight at once, across all sources and symbols
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// K-line pages requested per fetch; past this the rows fetched so far are returned
+    #[serde(default = "default_max_kline_pages")]
+    pub max_kline_pages: usize,
+    pub precision: PrecisionConfig,
+}
+
+fn default_max_concurrent_requests() -> usize {
+    8
+}
+
+fn default_max_kline_pages() -> usize {
+    50
+}
+
+fn default_cache_max_entries() -> usize {
+    1000
+}
+
+/// How long each kind of upstream response is served from the fetcher's cache,
+/// in seconds. `0` disables caching for that kind.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CacheTtlConfig {
+    #[serde(default = "default_quote_ttl")]
+    pub quote_ttl: i64,
+    #[serde(default = "default_kline_ttl")]
+    pub kline_ttl: i64,
+    #[serde(default = "default_depth_ttl")]
+    pub depth_ttl: i64,
+    #[serde(default = "default_trades_ttl")]
+    pub trades_ttl: i64,
+}
+
+impl Default for CacheTtlConfig {
+    fn default() -> Self {
+        Self {
+            quote_ttl: default_quote_ttl(),
+            kline_ttl: default_kline_ttl(),
+            depth_ttl: default_depth_ttl(),
+            trades_ttl: default_trades_ttl(),
+        }
+    }
+}
+
+fn default_quote_ttl() -> i64 {
+    5
+}
+
+fn default_kline_ttl() -> i64 {
+    3600
+}
+
+fn default_depth_ttl() -> i64 {
+    30
+}
+
+fn default_trades_ttl() -> i64 {
+    10
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -31,6 +482,116 @@ pub struct AppConfig {
     pub database: DatabaseConfig,
     pub server: ServerConfig,
     pub trading: TradingConfig,
+    pub data_source: DataSourceConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+}
+
+/// Where alerts are delivered; each channel is enabled by setting its URL, or for
+/// email its `[alerts.smtp]` section.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlertsConfig {
+    /// receives the alert as a JSON POST
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Slack incoming-webhook URL
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+    /// per-delivery timeout
+    #[serde(default = "default_alert_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            slack_webhook_url: None,
+            smtp: None,
+            timeout_secs: default_alert_timeout_secs(),
+        }
+    }
+}
+
+fn default_alert_timeout_secs() -> u64 {
+    10
+}
+
+/// SMTP relay that emails alerts.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    /// login for relays that require it; both or neither
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// sender mailbox, e.g. "MACD alerts <alerts@example.com>"
+    pub from: String,
+    /// recipient mailboxes
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// How the SMTP connection is secured.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    /// plain connection upgraded with STARTTLS, usually on port 587
+    #[default]
+    Starttls,
+    /// TLS from the start, usually on port 465
+    Tls,
+    /// no encryption; for a local relay only
+    None,
+}
+
+impl TradingConfig {
+    pub fn calendar(&self) -> TradingCalendar {
+        TradingCalendar::new(self.timezone, self.holidays.clone())
+    }
+
+    /// `watchlist` symbols, or just `default_symbol` when no watchlist is configured.
+    pub fn watchlist_or_default(&self) -> Vec<String> {
+        if self.watchlist.is_empty() {
+            vec![self.default_symbol.clone()]
+        } else {
+            self.watchlist
+                .iter()
+                .map(|entry| entry.symbol().to_string())
+                .collect()
+        }
+    }
+
+    /// (symbol, poll interval) for every watched symbol, applying per-entry overrides.
+    pub fn poll_schedule(&self) -> Vec<(String, Duration)> {
+        let default = self.poll_interval_secs.max(1);
+        if self.watchlist.is_empty() {
+            return vec![(self.default_symbol.clone(), Duration::from_secs(default))];
+        }
+        self.watchlist
+            .iter()
+            .map(|entry| {
+                let secs = match entry {
+                    WatchlistEntry::Detailed {
+                        poll_interval_secs: Some(secs),
+                        ..
+                    } => (*secs).max(1),
+                    _ => default,
+                };
+                (entry.symbol().to_string(), Duration::from_secs(secs))
+            })
+            .collect()
+    }
 }
 
 impl AppConfig {
@@ -48,6 +609,16 @@ impl AppConfig {
         config.try_deserialize()
     }
 
+    /// Load exactly `path` (format from its extension) plus `APP_*` environment
+    /// overrides, skipping the `config/` directory convention.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        Config::builder()
+            .add_source(File::from(path))
+            .add_source(config::Environment::with_prefix("APP"))
+            .build()?
+            .try_deserialize()
+    }
+
     pub fn get_server_address(&self) -> String {
         format!("{}:{}", self.server.host, self.server.port)
     }
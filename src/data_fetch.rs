@@ -1,20 +1,67 @@
-use crate::config::AppConfig;
+use crate::config::{AppConfig, SourceConfig};
 use crate::error::{AppError, ErrorCode, ResultExt};
 use crate::models::{Kline, MarketDepth, Quote, Trade};
+use crate::storage::SymbolMeta;
 use crate::utils::http_client::HttpClient;
-use anyhow::{Result, anyhow};
-use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use crate::utils::time::{market_local_to_millis, market_today};
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use reqwest::{RequestBuilder, Response};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use rust_decimal::prelude::FromPrimitive;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone)]
 pub struct DataFetcher {
     config: Arc<AppConfig>,
     http_client: HttpClient,
-    cache: Arc<RwLock<HashMap<String, CachedData>>>,
+    cache: Arc<RwLock<ResponseCache>>,
+    source_health: Arc<RwLock<HashMap<&'static str, SourceHealth>>>,
+    cache_hits: Arc<AtomicU64>,
+    cache_misses: Arc<AtomicU64>,
+    /// bounds upstream requests in flight (`data_source.max_concurrent_requests`)
+    request_slots: Arc<Semaphore>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SourceHealth {
+    last_success_ts: Option<i64>,
+    last_error: Option<String>,
+    successes: u64,
+    failures: u64,
+}
+
+/// Per-source status reported by `/api/sources`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub last_success_ts: Option<i64>,
+    pub last_error: Option<String>,
+    /// requests answered / failed since startup
+    pub successes: u64,
+    pub failures: u64,
+}
+
+/// What a parsed upstream field holds; selects its precision and which values are
+/// "no data" placeholders. Sources send `"-"` or `""` for any unavailable field (e.g.
+/// pre-open), and a literal `0` for unavailable prices (f43-f47, bid/ask levels).
+/// Volumes, amounts and changes can genuinely be zero, so `0` is kept for those.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FieldKind {
+    /// last/open/high/low/prev close/bid/ask; `0` means unavailable
+    Price,
+    /// absolute or percent change (f134/f135); `0` is a real value
+    Change,
+    Volume,
+    Amount,
 }
 
 #[derive(Debug, Clone)]
@@ -22,89 +69,304 @@ struct CachedData {
     data: serde_json::Value,
     timestamp: i64,
     ttl: i64,
+    /// `ResponseCache::clock` at the last read or write
+    last_used: u64,
+}
+
+/// Upstream responses by key. Entries expire after their TTL, and once `max_entries`
+/// are held the least recently used one makes room for a new key.
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    entries: HashMap<String, CachedData>,
+    max_entries: usize,
+    /// bumped on every access to order entries by recency
+    clock: u64,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries: max_entries.max(1),
+            clock: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The unexpired value at `key` as of `now` (ms), marking it recently used.
+    pub(crate) fn get(&mut self, key: &str, now: i64) -> Option<serde_json::Value> {
+        self.entries.retain(|_, v| now - v.timestamp < v.ttl);
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.data.clone())
+    }
+
+    /// Store `data` for `ttl` ms from `now`, evicting the least recently used entry
+    /// when a new key would exceed the cap. A `ttl` of 0 or less is not stored.
+    pub(crate) fn insert(&mut self, key: &str, data: serde_json::Value, ttl: i64, now: i64) {
+        if ttl <= 0 {
+            return;
+        }
+        if !self.entries.contains_key(key) && self.entries.len() >= self.max_entries {
+            self.entries.retain(|_, v| now - v.timestamp < v.ttl);
+            if self.entries.len() >= self.max_entries
+                && let Some(oldest) = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, v)| v.last_used)
+                    .map(|(k, _)| k.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(
+            key.to_string(),
+            CachedData {
+                data,
+                timestamp: now,
+                ttl,
+                last_used: self.clock,
+            },
+        );
+    }
 }
 
 impl DataFetcher {
     pub fn new(config: Arc<AppConfig>) -> Self {
         Self {
             config: config.clone(),
-            http_client: HttpClient::new(config.server.timeout),
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            http_client: HttpClient::new(),
+            cache: Arc::new(RwLock::new(ResponseCache::new(
+                config.data_source.cache_max_entries,
+            ))),
+            source_health: Arc::new(RwLock::new(HashMap::new())),
+            cache_hits: Arc::new(AtomicU64::new(0)),
+            cache_misses: Arc::new(AtomicU64::new(0)),
+            request_slots: Arc::new(Semaphore::new(
+                config.data_source.max_concurrent_requests.max(1),
+            )),
         }
     }
 
     /// Get real-time quote for a symbol
     pub async fn get_quote(&self, symbol: &str) -> Result<Quote, AppError> {
+        self.get_quote_with(symbol, false).await
+    }
+
+    /// Like `get_quote`; with `force_refresh` the cache is not read, so every call goes
+    /// upstream and counts against the sources' rate limits. The fresh quote is still
+    /// written back to the cache.
+    pub async fn get_quote_with(
+        &self,
+        symbol: &str,
+        force_refresh: bool,
+    ) -> Result<Quote, AppError> {
         let normalized_symbol = self.normalize_symbol(symbol);
+        info!(
+            "Getting quote for symbol: {} (force_refresh: {})",
+            normalized_symbol, force_refresh
+        );
 
         // Try to get from cache first
-        if let Some(cached) = self
-            .get_from_cache(&format!("quote:{}", normalized_symbol))
-            .await?
+        if !force_refresh
+            && let Some(cached) = self
+                .get_from_cache(&format!("quote:{}", normalized_symbol))
+                .await?
         {
+            debug!("Returning cached quote for {}", normalized_symbol);
             return Ok(serde_json::from_value(cached)?);
         }
 
-        // Try multiple data sources
+        // Try multiple data sources with detailed error logging
         let mut errors = Vec::new();
 
         // Try EastMoney first
         if self.config.data_source.eastmoney.enabled {
+            debug!(
+                "Trying to get quote from EastMoney for {}",
+                normalized_symbol
+            );
             match self.get_quote_from_eastmoney(&normalized_symbol).await {
                 Ok(quote) => {
+                    self.mark_source_ok("EastMoney").await;
+                    info!(
+                        "Successfully got quote from EastMoney for {}",
+                        normalized_symbol
+                    );
                     self.cache_data(
                         &format!("quote:{}", normalized_symbol),
                         serde_json::to_value(&quote)?,
-                        self.config.data_source.cache_duration * 1000,
+                        self.config.data_source.cache.quote_ttl * 1000,
                     )
                     .await?;
                     return Ok(quote);
                 }
-                Err(e) => errors.push(("EastMoney", e)),
+                Err(e) => {
+                    error!("EastMoney failed for {}: {}", normalized_symbol, e);
+                    self.mark_source_failed("EastMoney", &e).await;
+                    errors.push(("EastMoney", e.to_string()));
+                }
             }
         }
 
         // Try Baidu Finance
         if self.config.data_source.baidu.enabled {
+            debug!(
+                "Trying to get quote from Baidu Finance for {}",
+                normalized_symbol
+            );
             match self.get_quote_from_baidu(&normalized_symbol).await {
                 Ok(quote) => {
+                    self.mark_source_ok("Baidu Finance").await;
+                    info!(
+                        "Successfully got quote from Baidu Finance for {}",
+                        normalized_symbol
+                    );
                     self.cache_data(
                         &format!("quote:{}", normalized_symbol),
                         serde_json::to_value(&quote)?,
-                        self.config.data_source.cache_duration * 1000,
+                        self.config.data_source.cache.quote_ttl * 1000,
                     )
                     .await?;
                     return Ok(quote);
                 }
-                Err(e) => errors.push(("Baidu Finance", e)),
+                Err(e) => {
+                    error!("Baidu Finance failed for {}: {}", normalized_symbol, e);
+                    self.mark_source_failed("Baidu Finance", &e).await;
+                    errors.push(("Baidu Finance", e.to_string()));
+                }
             }
         }
 
         // Try Sina Finance
         if self.config.data_source.sina.enabled {
+            debug!(
+                "Trying to get quote from Sina Finance for {}",
+                normalized_symbol
+            );
             match self.get_quote_from_sina(&normalized_symbol).await {
                 Ok(quote) => {
+                    self.mark_source_ok("Sina Finance").await;
+                    info!(
+                        "Successfully got quote from Sina Finance for {}",
+                        normalized_symbol
+                    );
                     self.cache_data(
                         &format!("quote:{}", normalized_symbol),
                         serde_json::to_value(&quote)?,
-                        self.config.data_source.cache_duration * 1000,
+                        self.config.data_source.cache.quote_ttl * 1000,
                     )
                     .await?;
                     return Ok(quote);
                 }
-                Err(e) => errors.push(("Sina Finance", e)),
+                Err(e) => {
+                    error!("Sina Finance failed for {}: {}", normalized_symbol, e);
+                    self.mark_source_failed("Sina Finance", &e).await;
+                    errors.push(("Sina Finance", e.to_string()));
+                }
             }
         }
 
-        // If all sources failed
-        let error_msg = format!(
-            "Failed to get quote for {} from all sources: {:?}",
-            symbol, errors
+        // If all sources failed, try to get from storage or return simulated data
+        warn!(
+            "All data sources failed for {}, trying fallback strategies",
+            normalized_symbol
         );
-        Err(AppError::api(ErrorCode::DataSourceError, error_msg))
+
+        // Try to get from storage
+        if let Ok(Some(quote)) = self.get_quote_from_storage(&normalized_symbol).await {
+            info!(
+                "Returning historical quote from storage for {}",
+                normalized_symbol
+            );
+            return Ok(quote);
+        }
+
+        // As a last resort, return simulated data - but only when explicitly allowed,
+        // fake prices must never reach real trading silently
+        if !self.config.data_source.allow_simulated_fallback {
+            return Err(AppError::api(
+                ErrorCode::DataSourceError,
+                format!(
+                    "Failed to get quote for {} from all sources: {:?}",
+                    symbol, errors
+                ),
+            ));
+        }
+
+        error!(
+            "SIMULATED DATA: no real quote available for {}, serving a simulated quote",
+            normalized_symbol
+        );
+        Ok(self.generate_simulated_quote(&normalized_symbol))
+    }
+
+    /// Get quote from storage as fallback
+    async fn get_quote_from_storage(&self, _symbol: &str) -> Result<Option<Quote>, AppError> {
+        // In a real implementation, this would query the database
+        // For now, return None
+        Ok(None)
+    }
+
+    /// Generate simulated quote for fallback
+    fn generate_simulated_quote(&self, symbol: &str) -> Quote {
+        let base_price = if symbol.starts_with("600733") {
+            15.50 // Simulated price for 600733
+        } else if symbol.starts_with("000001") {
+            10.50 // Simulated price for 000001
+        } else {
+            8.0 + rand::random::<f64>() * 4.0 // Random price between 8-12
+        };
+
+        let change = (rand::random::<f64>() - 0.5) * 0.2; // Random change between -10% and +10%
+        let price = base_price * (1.0 + change);
+
+        Quote {
+            symbol: symbol.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            price: Decimal::from_f64(price).unwrap().round_dp(2),
+            open: Some(Decimal::from_f64(base_price).unwrap().round_dp(2)),
+            high: Some(
+                Decimal::from_f64(base_price * (1.0 + change.abs() * 1.5))
+                    .unwrap()
+                    .round_dp(2),
+            ),
+            low: Some(
+                Decimal::from_f64(base_price * (1.0 - change.abs() * 1.5))
+                    .unwrap()
+                    .round_dp(2),
+            ),
+            prev_close: Some(Decimal::from_f64(base_price).unwrap().round_dp(2)),
+            volume: Some(
+                Decimal::from_f64(rand::random::<f64>() * 1000000.0 + 500000.0)
+                    .unwrap()
+                    .round_dp(0),
+            ),
+            amount: Some(Decimal::from_f64(price * 1000000.0).unwrap().round_dp(0)),
+            change: Some(Decimal::from_f64(price - base_price).unwrap().round_dp(2)),
+            change_pct: Some(Decimal::from_f64(change * 100.0).unwrap().round_dp(2)),
+            bid_price: Some(Decimal::from_f64(price - 0.01).unwrap().round_dp(2)),
+            ask_price: Some(Decimal::from_f64(price).unwrap().round_dp(2)),
+            bid_volume: Some(
+                Decimal::from_f64(rand::random::<f64>() * 10000.0 + 5000.0)
+                    .unwrap()
+                    .round_dp(0),
+            ),
+            ask_volume: Some(
+                Decimal::from_f64(rand::random::<f64>() * 10000.0 + 5000.0)
+                    .unwrap()
+                    .round_dp(0),
+            ),
+            source: Some("simulated".to_string()),
+        }
     }
 
-    /// Get historical K-line data
+    /// Get historical K-line data with fallback
     pub async fn get_kline_data(
         &self,
         symbol: &str,
@@ -121,25 +383,150 @@ impl DataFetcher {
             period
         );
 
+        info!(
+            "Getting K-line data for {} ({} to {})",
+            normalized_symbol, start_date, end_date
+        );
+
         // Try cache
         if let Some(cached) = self.get_from_cache(&cache_key).await? {
+            debug!("Returning cached K-line data for {}", normalized_symbol);
             return Ok(serde_json::from_value(cached)?);
         }
 
-        let klines = self
+        // Try to get from data source
+        match self
             .get_kline_from_eastmoney(&normalized_symbol, start_date, end_date, period)
-            .await?;
+            .await
+        {
+            Ok(klines) => {
+                self.mark_source_ok("EastMoney").await;
+                info!("Successfully got K-line data for {}", normalized_symbol);
+                self.cache_data(
+                    &cache_key,
+                    serde_json::to_value(&klines)?,
+                    self.config.data_source.cache.kline_ttl * 1000,
+                )
+                .await?;
+                Ok(klines)
+            }
+            Err(e) => {
+                error!("Failed to get K-line data from EastMoney: {}", e);
+                self.mark_source_failed("EastMoney", &e).await;
+
+                // Try to get from storage
+                if let Ok(Some(klines)) = self
+                    .get_klines_from_storage(&normalized_symbol, start_date, end_date, period)
+                    .await
+                {
+                    info!(
+                        "Returning K-line data from storage for {}",
+                        normalized_symbol
+                    );
+                    return Ok(klines);
+                }
 
-        self.cache_data(
-            &cache_key,
-            serde_json::to_value(&klines)?,
-            3600 * 1000, // Cache for 1 hour
-        )
-        .await?;
+                if !self.config.data_source.allow_simulated_fallback {
+                    return Err(AppError::api(
+                        ErrorCode::DataSourceError,
+                        format!("Failed to get K-line data for {}: {}", symbol, e),
+                    ));
+                }
 
-        Ok(klines)
+                // Generate simulated K-line data
+                error!(
+                    "SIMULATED DATA: no real K-line data available for {}, serving simulated klines",
+                    normalized_symbol
+                );
+                Ok(
+                    self.generate_simulated_klines(
+                        &normalized_symbol,
+                        start_date,
+                        end_date,
+                        period,
+                    ),
+                )
+            }
+        }
+    }
+
+    /// Get K-lines from storage as fallback
+    async fn get_klines_from_storage(
+        &self,
+        _symbol: &str,
+        _start_date: NaiveDate,
+        _end_date: NaiveDate,
+        _period: &str,
+    ) -> Result<Option<Vec<Kline>>, AppError> {
+        // In a real implementation, this would query the database
+        Ok(None)
     }
 
+    /// Generate simulated K-line data
+    fn generate_simulated_klines(
+        &self,
+        symbol: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        period: &str,
+    ) -> Vec<Kline> {
+        let mut klines = Vec::new();
+        let days = (end_date - start_date).num_days() as usize;
+
+        // Base price based on symbol
+        let base_price = if symbol.starts_with("600733") {
+            15.50
+        } else if symbol.starts_with("000001") {
+            10.50
+        } else {
+            8.0 + rand::random::<f64>() * 4.0
+        };
+
+        let mut current_price = base_price;
+
+        for i in 0..days {
+            let date = start_date + chrono::Duration::days(i as i64);
+
+            // Skip weekends
+            let weekday = date.weekday();
+            if weekday == chrono::Weekday::Sat || weekday == chrono::Weekday::Sun {
+                continue;
+            }
+
+            // Generate daily price movement
+            let change = (rand::random::<f64>() - 0.5) * 0.03; // ±3% daily change
+            current_price *= 1.0 + change;
+
+            let open = current_price * (0.995 + rand::random::<f64>() * 0.01); // Open within ±0.5% of current price
+            let high = open * (1.0 + rand::random::<f64>() * 0.02); // High up to +2%
+            let low = open * (0.98 + rand::random::<f64>() * 0.02); // Low down to -2%
+            let close = if rand::random::<f64>() > 0.5 {
+                (open + high + low + current_price) / 4.0
+            } else {
+                (open + high + low + current_price * 0.99) / 4.0
+            };
+
+            let volume = rand::random::<f64>() * 1000000.0 + 500000.0;
+
+            klines.push(Kline {
+                symbol: symbol.to_string(),
+                timestamp: date
+                    .and_hms_opt(15, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp_millis(),
+                open: Decimal::from_f64(open).unwrap().round_dp(2),
+                high: Decimal::from_f64(high).unwrap().round_dp(2),
+                low: Decimal::from_f64(low).unwrap().round_dp(2),
+                close: Decimal::from_f64(close).unwrap().round_dp(2),
+                volume: Decimal::from_f64(volume).unwrap().round_dp(0),
+                amount: Some(Decimal::from_f64(close * volume).unwrap().round_dp(0)),
+                period: period.to_string(),
+            });
+        }
+
+        klines
+    }
     /// Get market depth data
     pub async fn get_market_depth(&self, symbol: &str) -> Result<MarketDepth, AppError> {
         let normalized_symbol = self.normalize_symbol(symbol);
@@ -154,7 +541,7 @@ impl DataFetcher {
         self.cache_data(
             &cache_key,
             serde_json::to_value(&depth)?,
-            30 * 1000, // Cache for 30 seconds
+            self.config.data_source.cache.depth_ttl * 1000,
         )
         .await?;
 
@@ -181,7 +568,7 @@ impl DataFetcher {
         self.cache_data(
             &cache_key,
             serde_json::to_value(&trades)?,
-            10 * 1000, // Cache for 10 seconds
+            self.config.data_source.cache.trades_ttl * 1000,
         )
         .await?;
 
@@ -189,28 +576,75 @@ impl DataFetcher {
     }
 
     /// Normalize stock symbol to standard format
-    fn normalize_symbol(&self, symbol: &str) -> String {
+    /// Append the exchange suffix to a bare 6-digit code: `.SZ` for Shenzhen stocks,
+    /// funds and bonds (0/2/3, 12/15/16/18), `.SH` for Shanghai stocks, funds and bonds
+    /// (5/6/9, 10/11) and `.BJ` for the Beijing Stock Exchange (4/8 and the newer 92).
+    /// Suffixed or unrecognised symbols are returned uppercased but otherwise unchanged.
+    pub(crate) fn normalize_symbol(&self, symbol: &str) -> String {
         let symbol = symbol.trim().to_uppercase();
 
-        // Convert to standard format: 000001.SZ, 600733.SH
-        if symbol.ends_with(".SZ") || symbol.ends_with(".SH") {
+        // Convert to standard format: 000001.SZ, 600733.SH, 830799.BJ
+        if symbol.ends_with(".SZ") || symbol.ends_with(".SH") || symbol.ends_with(".BJ") {
             return symbol;
         }
 
-        if symbol.len() == 6 {
-            if symbol.starts_with(|c: char| c.is_ascii_digit()) {
-                let prefix = &symbol[0..1];
-                if prefix == "0" || prefix == "3" {
-                    return format!("{}.SZ", symbol);
-                } else if prefix == "6" {
-                    return format!("{}.SH", symbol);
-                }
+        if symbol.len() == 6 && symbol.chars().all(|c| c.is_ascii_digit()) {
+            let suffix = match &symbol[0..2] {
+                "92" => Some("BJ"),
+                "10" | "11" => Some("SH"),
+                "12" | "15" | "16" | "18" => Some("SZ"),
+                p if p.starts_with(['0', '2', '3']) => Some("SZ"),
+                p if p.starts_with(['5', '6', '9']) => Some("SH"),
+                p if p.starts_with(['4', '8']) => Some("BJ"),
+                _ => None,
+            };
+            if let Some(suffix) = suffix {
+                return format!("{}.{}", symbol, suffix);
             }
         }
 
         symbol
     }
 
+    /// Look up the display name (EastMoney field `name`, `f58` by default) and exchange
+    /// of a symbol.
+    pub async fn get_symbol_meta(&self, symbol: &str) -> Result<SymbolMeta, AppError> {
+        let symbol = self.normalize_symbol(symbol);
+        let (market, code) = self.parse_symbol(&symbol)?;
+        let exchange = if symbol.ends_with(".BJ") {
+            "BSE"
+        } else if market == 1 {
+            "SSE"
+        } else {
+            "SZSE"
+        };
+
+        let name_field = self.config.data_source.eastmoney.fields.code("name");
+        let url = format!(
+            "{}/api/qt/stock/get?secid={}.{}&fields=f57,{}",
+            self.config.data_source.eastmoney.base_url, market, code, name_field
+        );
+        let json: serde_json::Value = self
+            .source_send(&self.config.data_source.eastmoney, &url)
+            .await
+            .with_context("Failed to fetch symbol name from EastMoney")?
+            .json()
+            .await
+            .with_context("Failed to parse EastMoney response")?;
+
+        let name = json["data"][name_field]
+            .as_str()
+            .filter(|name| !name.is_empty() && *name != "-")
+            .ok_or_else(|| AppError::data_not_found(format!("No name found for {}", symbol)))?;
+
+        Ok(SymbolMeta {
+            symbol: symbol.clone(),
+            name: name.to_string(),
+            exchange: exchange.to_string(),
+            sector: None,
+        })
+    }
+
     /// Get quote from EastMoney
     async fn get_quote_from_eastmoney(&self, symbol: &str) -> Result<Quote, AppError> {
         let (market, code) = self.parse_symbol(symbol)?;
@@ -221,10 +655,7 @@ impl DataFetcher {
         );
 
         let response = self
-            .http_client
-            .get(&url)
-            .header("User-Agent", &self.config.data_source.eastmoney.user_agent)
-            .send()
+            .source_send(&self.config.data_source.eastmoney, &url)
             .await
             .with_context("Failed to fetch data from EastMoney")?;
 
@@ -237,19 +668,20 @@ impl DataFetcher {
             .as_object()
             .ok_or_else(|| AppError::data_not_found("No data found for symbol"))?;
 
-        let price = self.get_decimal(data, "f43")?;
-        let open = self.get_decimal_opt(data, "f46");
-        let high = self.get_decimal_opt(data, "f44");
-        let low = self.get_decimal_opt(data, "f45");
-        let prev_close = self.get_decimal_opt(data, "f47");
-        let volume = self.get_decimal_opt(data, "f48");
-        let amount = self.get_decimal_opt(data, "f49");
-        let change = self.get_decimal_opt(data, "f134");
-        let change_pct = self.get_decimal_opt(data, "f135");
-        let bid_price = self.get_decimal_opt(data, "f18");
-        let ask_price = self.get_decimal_opt(data, "f19");
-        let bid_volume = self.get_decimal_opt(data, "f10");
-        let ask_volume = self.get_decimal_opt(data, "f11");
+        let fields = &self.config.data_source.eastmoney.fields;
+        let price = self.get_decimal(data, fields.code("price"), FieldKind::Price)?;
+        let open = self.get_decimal_opt(data, fields.code("open"), FieldKind::Price);
+        let high = self.get_decimal_opt(data, fields.code("high"), FieldKind::Price);
+        let low = self.get_decimal_opt(data, fields.code("low"), FieldKind::Price);
+        let prev_close = self.get_decimal_opt(data, fields.code("prev_close"), FieldKind::Price);
+        let volume = self.get_decimal_opt(data, fields.code("volume"), FieldKind::Volume);
+        let amount = self.get_decimal_opt(data, fields.code("amount"), FieldKind::Amount);
+        let change = self.get_decimal_opt(data, fields.code("change"), FieldKind::Change);
+        let change_pct = self.get_decimal_opt(data, fields.code("change_pct"), FieldKind::Change);
+        let bid_price = self.get_decimal_opt(data, fields.code("bid_price"), FieldKind::Price);
+        let ask_price = self.get_decimal_opt(data, fields.code("ask_price"), FieldKind::Price);
+        let bid_volume = self.get_decimal_opt(data, fields.code("bid_volume"), FieldKind::Volume);
+        let ask_volume = self.get_decimal_opt(data, fields.code("ask_volume"), FieldKind::Volume);
 
         Ok(Quote {
             symbol: symbol.to_string(),
@@ -267,6 +699,7 @@ impl DataFetcher {
             ask_price,
             bid_volume,
             ask_volume,
+            source: Some("eastmoney".to_string()),
         })
     }
 
@@ -281,19 +714,36 @@ impl DataFetcher {
         let (market, code) = self.parse_symbol(symbol)?;
         let ktype = self.convert_period_to_ktype(period)?;
 
-        let start_ts = start_date
-            .and_hms_opt(0, 0, 0)
-            .ok_or_else(|| AppError::invalid_date_range())?
-            .timestamp_millis();
-        let end_ts = end_date
-            .and_hms_opt(23, 59, 59)
-            .ok_or_else(|| AppError::invalid_date_range())?
-            .timestamp_millis();
+        let tz = self.config.trading.timezone;
+        let start_ts = market_local_to_millis(
+            tz,
+            start_date
+                .and_hms_opt(0, 0, 0)
+                .ok_or_else(AppError::invalid_date_range)?,
+        );
+        let end_ts = market_local_to_millis(
+            tz,
+            end_date
+                .and_hms_opt(23, 59, 59)
+                .ok_or_else(AppError::invalid_date_range)?,
+        );
 
         let mut klines = Vec::new();
         let mut current_end = end_ts;
+        let mut pages = 0;
 
         while current_end >= start_ts {
+            if pages == self.config.data_source.max_kline_pages {
+                warn!(
+                    "Stopping K-line fetch for {} after {} pages with {} rows",
+                    symbol,
+                    pages,
+                    klines.len()
+                );
+                break;
+            }
+            pages += 1;
+
             let url = format!(
                 "{}/api/qt/stock/kline/get?secid={}.{}&klt={}&fqt=0&beg={}&end={}&smplmt=1000",
                 self.config.data_source.eastmoney.base_url,
@@ -305,10 +755,7 @@ impl DataFetcher {
             );
 
             let response = self
-                .http_client
-                .get(&url)
-                .header("User-Agent", &self.config.data_source.eastmoney.user_agent)
-                .send()
+                .source_send(&self.config.data_source.eastmoney, &url)
                 .await
                 .with_context("Failed to fetch K-line data from EastMoney")?;
 
@@ -329,6 +776,7 @@ impl DataFetcher {
                 break;
             }
 
+            let mut page = Vec::with_capacity(klines_str.len());
             for kline_str in klines_str {
                 let kline_data: Vec<&str> = kline_str.as_str().unwrap().split(',').collect();
                 if kline_data.len() < 6 {
@@ -347,13 +795,17 @@ impl DataFetcher {
                 let volume = Decimal::from_str_radix(kline_data[5], 10)
                     .with_context(format!("Invalid volume: {}", kline_data[5]))?;
 
-                let datetime = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M")
-                    .or_else(|_| NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d"))
-                    .with_context(format!("Invalid date format: {}", date_str))?;
+                let timestamp = parse_kline_timestamp(date_str, tz).ok_or_else(|| {
+                    AppError::invalid_data(format!("Invalid date format: {}", date_str))
+                })?;
 
-                klines.push(Kline {
+                // rows past the cursor came with an earlier page
+                if timestamp > current_end {
+                    continue;
+                }
+                page.push(Kline {
                     symbol: symbol.to_string(),
-                    timestamp: datetime.timestamp_millis(),
+                    timestamp,
                     open,
                     high,
                     low,
@@ -364,12 +816,13 @@ impl DataFetcher {
                 });
             }
 
-            // Update current_end to get previous page
-            if let Some(first_kline) = klines.first() {
-                current_end = first_kline.timestamp - 1;
-            } else {
+            // the previous page ends just before this one's earliest row; a page with
+            // nothing new would request the same page again
+            let Some(page_start) = page.iter().map(|k| k.timestamp).min() else {
                 break;
-            }
+            };
+            klines.append(&mut page);
+            current_end = page_start - 1;
 
             // Add delay to avoid rate limiting
             tokio::time::sleep(Duration::from_millis(100)).await;
@@ -391,10 +844,7 @@ impl DataFetcher {
         );
 
         let response = self
-            .http_client
-            .get(&url)
-            .header("User-Agent", &self.config.data_source.eastmoney.user_agent)
-            .send()
+            .source_send(&self.config.data_source.eastmoney, &url)
             .await
             .with_context("Failed to fetch market depth from EastMoney")?;
 
@@ -410,33 +860,32 @@ impl DataFetcher {
         let mut bids = Vec::new();
         let mut asks = Vec::new();
 
-        // Parse bid orders (f1-f5: price, f6-f10: volume)
-        for i in 0..5 {
-            let price_key = format!("f{}", i + 1);
-            let volume_key = format!("f{}", i + 6);
+        let fields = &self.config.data_source.eastmoney.fields;
+        for level in 1..=5 {
+            let price_key = fields.code(&format!("bid{}_price", level));
+            let volume_key = fields.code(&format!("bid{}_volume", level));
 
             if let (Some(price), Some(volume)) = (
-                self.get_decimal_opt(data, &price_key),
-                self.get_decimal_opt(data, &volume_key),
-            ) {
-                if price > Decimal::ZERO && volume > Decimal::ZERO {
-                    bids.push((price, volume));
-                }
+                self.get_decimal_opt(data, price_key, FieldKind::Price),
+                self.get_decimal_opt(data, volume_key, FieldKind::Volume),
+            ) && price > Decimal::ZERO
+                && volume > Decimal::ZERO
+            {
+                bids.push((price, volume));
             }
         }
 
-        // Parse ask orders (f11-f15: price, f16-f20: volume)
-        for i in 0..5 {
-            let price_key = format!("f{}", i + 11);
-            let volume_key = format!("f{}", i + 16);
+        for level in 1..=5 {
+            let price_key = fields.code(&format!("ask{}_price", level));
+            let volume_key = fields.code(&format!("ask{}_volume", level));
 
             if let (Some(price), Some(volume)) = (
-                self.get_decimal_opt(data, &price_key),
-                self.get_decimal_opt(data, &volume_key),
-            ) {
-                if price > Decimal::ZERO && volume > Decimal::ZERO {
-                    asks.push((price, volume));
-                }
+                self.get_decimal_opt(data, price_key, FieldKind::Price),
+                self.get_decimal_opt(data, volume_key, FieldKind::Volume),
+            ) && price > Decimal::ZERO
+                && volume > Decimal::ZERO
+            {
+                asks.push((price, volume));
             }
         }
 
@@ -462,10 +911,7 @@ impl DataFetcher {
         );
 
         let response = self
-            .http_client
-            .get(&url)
-            .header("User-Agent", &self.config.data_source.eastmoney.user_agent)
-            .send()
+            .source_send(&self.config.data_source.eastmoney, &url)
             .await
             .with_context("Failed to fetch trades from EastMoney")?;
 
@@ -504,9 +950,9 @@ impl DataFetcher {
 
             let time = NaiveTime::parse_from_str(time_str, "%H:%M:%S")
                 .with_context(format!("Invalid time format: {}", time_str))?;
-            let today = NaiveDate::today();
-            let datetime = NaiveDateTime::new(today, time);
-            let timestamp = datetime.timestamp_millis();
+            let tz = self.config.trading.timezone;
+            let datetime = NaiveDateTime::new(market_today(tz), time);
+            let timestamp = market_local_to_millis(tz, datetime);
 
             trades.push(Trade {
                 trade_id,
@@ -532,10 +978,7 @@ impl DataFetcher {
         );
 
         let response = self
-            .http_client
-            .get(&url)
-            .header("User-Agent", &self.config.data_source.baidu.user_agent)
-            .send()
+            .source_send(&self.config.data_source.baidu, &url)
             .await
             .with_context("Failed to fetch data from Baidu Finance")?;
 
@@ -554,15 +997,16 @@ impl DataFetcher {
 
         let data = result[0].as_object().unwrap();
 
-        let price = self.get_decimal(data, "f43")?;
-        let open = self.get_decimal_opt(data, "f46");
-        let high = self.get_decimal_opt(data, "f44");
-        let low = self.get_decimal_opt(data, "f45");
-        let prev_close = self.get_decimal_opt(data, "f47");
-        let volume = self.get_decimal_opt(data, "f48");
-        let amount = self.get_decimal_opt(data, "f49");
-        let change = self.get_decimal_opt(data, "f134");
-        let change_pct = self.get_decimal_opt(data, "f135");
+        let fields = &self.config.data_source.baidu.fields;
+        let price = self.get_decimal(data, fields.code("price"), FieldKind::Price)?;
+        let open = self.get_decimal_opt(data, fields.code("open"), FieldKind::Price);
+        let high = self.get_decimal_opt(data, fields.code("high"), FieldKind::Price);
+        let low = self.get_decimal_opt(data, fields.code("low"), FieldKind::Price);
+        let prev_close = self.get_decimal_opt(data, fields.code("prev_close"), FieldKind::Price);
+        let volume = self.get_decimal_opt(data, fields.code("volume"), FieldKind::Volume);
+        let amount = self.get_decimal_opt(data, fields.code("amount"), FieldKind::Amount);
+        let change = self.get_decimal_opt(data, fields.code("change"), FieldKind::Change);
+        let change_pct = self.get_decimal_opt(data, fields.code("change_pct"), FieldKind::Change);
 
         Ok(Quote {
             symbol: symbol.to_string(),
@@ -580,6 +1024,7 @@ impl DataFetcher {
             ask_price: None,
             bid_volume: None,
             ask_volume: None,
+            source: Some("baidu".to_string()),
         })
     }
 
@@ -593,10 +1038,7 @@ impl DataFetcher {
         );
 
         let response = self
-            .http_client
-            .get(&url)
-            .header("User-Agent", &self.config.data_source.sina.user_agent)
-            .send()
+            .source_send(&self.config.data_source.sina, &url)
             .await
             .with_context("Failed to fetch data from Sina Finance")?;
 
@@ -607,9 +1049,8 @@ impl DataFetcher {
 
         // Parse the JavaScript data
         let json_str = text
-            .splitn(2, '=')
-            .nth(1)
-            .and_then(|s| s.strip_suffix(';'))
+            .split_once('=')
+            .and_then(|(_, s)| s.trim_end().strip_suffix(';'))
             .ok_or_else(|| AppError::invalid_data("Invalid Sina Finance response format"))?;
 
         let json: serde_json::Value =
@@ -625,15 +1066,15 @@ impl DataFetcher {
 
         let quote_data = data[0].as_object().unwrap();
 
-        let price = self.get_decimal(quote_data, "price")?;
-        let open = self.get_decimal_opt(quote_data, "open");
-        let high = self.get_decimal_opt(quote_data, "high");
-        let low = self.get_decimal_opt(quote_data, "low");
-        let prev_close = self.get_decimal_opt(quote_data, "preclose");
-        let volume = self.get_decimal_opt(quote_data, "volume");
-        let amount = self.get_decimal_opt(quote_data, "amount");
-        let change = self.get_decimal_opt(quote_data, "change");
-        let change_pct = self.get_decimal_opt(quote_data, "changepercent");
+        let price = self.get_decimal(quote_data, "price", FieldKind::Price)?;
+        let open = self.get_decimal_opt(quote_data, "open", FieldKind::Price);
+        let high = self.get_decimal_opt(quote_data, "high", FieldKind::Price);
+        let low = self.get_decimal_opt(quote_data, "low", FieldKind::Price);
+        let prev_close = self.get_decimal_opt(quote_data, "preclose", FieldKind::Price);
+        let volume = self.get_decimal_opt(quote_data, "volume", FieldKind::Volume);
+        let amount = self.get_decimal_opt(quote_data, "amount", FieldKind::Amount);
+        let change = self.get_decimal_opt(quote_data, "change", FieldKind::Change);
+        let change_pct = self.get_decimal_opt(quote_data, "changepercent", FieldKind::Change);
 
         Ok(Quote {
             symbol: symbol.to_string(),
@@ -651,16 +1092,19 @@ impl DataFetcher {
             ask_price: None,
             bid_volume: None,
             ask_volume: None,
+            source: Some("sina".to_string()),
         })
     }
 
-    /// Parse symbol into market and code
-    fn parse_symbol(&self, symbol: &str) -> Result<(i32, &str), AppError> {
-        if symbol.ends_with(".SZ") {
-            let code = &symbol[0..6];
+    /// EastMoney market code and bare code of a normalized symbol: 1 for Shanghai,
+    /// 0 for Shenzhen and Beijing (EastMoney lists BSE under market 0).
+    pub(crate) fn parse_symbol<'a>(&self, symbol: &'a str) -> Result<(i32, &'a str), AppError> {
+        let code = symbol
+            .get(0..6)
+            .ok_or_else(|| AppError::invalid_symbol(symbol))?;
+        if symbol.ends_with(".SZ") || symbol.ends_with(".BJ") {
             Ok((0, code))
         } else if symbol.ends_with(".SH") {
-            let code = &symbol[0..6];
             Ok((1, code))
         } else {
             Err(AppError::invalid_symbol(symbol))
@@ -686,10 +1130,8 @@ impl DataFetcher {
     }
 
     /// Get Baidu finance code format
-    fn get_baidu_code(&self, symbol: &str) -> Result<&str, AppError> {
-        if symbol.ends_with(".SZ") {
-            Ok(&symbol[0..6])
-        } else if symbol.ends_with(".SH") {
+    fn get_baidu_code<'a>(&self, symbol: &'a str) -> Result<&'a str, AppError> {
+        if symbol.ends_with(".SZ") || symbol.ends_with(".SH") || symbol.ends_with(".BJ") {
             Ok(&symbol[0..6])
         } else {
             Err(AppError::invalid_symbol(symbol))
@@ -702,52 +1144,55 @@ impl DataFetcher {
             Ok(format!("sz{}", &symbol[0..6]))
         } else if symbol.ends_with(".SH") {
             Ok(format!("sh{}", &symbol[0..6]))
+        } else if symbol.ends_with(".BJ") {
+            Ok(format!("bj{}", &symbol[0..6]))
         } else {
             Err(AppError::invalid_symbol(symbol))
         }
     }
 
-    /// Helper to get Decimal from JSON object
+    /// Helper to get Decimal from JSON object. Placeholders are an error here since
+    /// the caller needs a real value.
     fn get_decimal(
         &self,
         data: &serde_json::Map<String, serde_json::Value>,
         key: &str,
+        kind: FieldKind,
     ) -> Result<Decimal, AppError> {
         let value = data
             .get(key)
             .ok_or_else(|| AppError::data_not_found(format!("Missing key: {}", key)))?;
 
-        self.parse_decimal(value, key)
+        self.parse_decimal(value, key, kind)?.ok_or_else(|| {
+            AppError::invalid_data(format!("{} is unavailable (placeholder {})", key, value))
+        })
     }
 
-    /// Helper to get optional Decimal from JSON object
+    /// Helper to get optional Decimal from JSON object; placeholders become `None`
     fn get_decimal_opt(
         &self,
         data: &serde_json::Map<String, serde_json::Value>,
         key: &str,
+        kind: FieldKind,
     ) -> Option<Decimal> {
-        data.get(key).and_then(|v| self.parse_decimal(v, key).ok())
+        data.get(key)
+            .and_then(|v| self.parse_decimal(v, key, kind).ok().flatten())
     }
 
     /// Helper to parse Decimal from JSON value
-    fn parse_decimal(&self, value: &serde_json::Value, key: &str) -> Result<Decimal, AppError> {
-        if let Some(s) = value.as_str() {
-            Decimal::from_str_radix(s, 10)
-                .with_context(format!("Invalid decimal value for {}: {}", key, s))
-        } else if let Some(n) = value.as_f64() {
-            Ok(Decimal::from_f64(n)
-                .ok_or_else(|| {
-                    AppError::invalid_data(format!("Invalid number for {}: {}", key, n))
-                })?
-                .round_dp(2))
-        } else if let Some(n) = value.as_i64() {
-            Ok(Decimal::from(n))
-        } else {
-            Err(AppError::invalid_data(format!(
-                "Unsupported type for {}: {:?}",
-                key, value
-            )))
-        }
+    fn parse_decimal(
+        &self,
+        value: &serde_json::Value,
+        key: &str,
+        kind: FieldKind,
+    ) -> Result<Option<Decimal>, AppError> {
+        let precision = &self.config.data_source.precision;
+        let dp = match kind {
+            FieldKind::Price | FieldKind::Change => precision.price,
+            FieldKind::Volume => precision.volume,
+            FieldKind::Amount => precision.amount,
+        };
+        parse_field(value, key, kind, dp)
     }
 
     /// Cache data
@@ -758,26 +1203,29 @@ impl DataFetcher {
         ttl: i64,
     ) -> Result<(), AppError> {
         let mut cache = self.cache.write().await;
-        cache.insert(
-            key.to_string(),
-            CachedData {
-                data,
-                timestamp: Utc::now().timestamp_millis(),
-                ttl,
-            },
-        );
+        cache.insert(key, data, ttl, Utc::now().timestamp_millis());
         Ok(())
     }
 
     /// Get data from cache
     async fn get_from_cache(&self, key: &str) -> Result<Option<serde_json::Value>, AppError> {
         let mut cache = self.cache.write().await;
-        let now = Utc::now().timestamp_millis();
-
-        // Clean up expired cache entries
-        cache.retain(|_, v| now - v.timestamp < v.ttl);
+        let hit = cache.get(key, Utc::now().timestamp_millis());
+        let counter = if hit.is_some() {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(hit)
+    }
 
-        Ok(cache.get(key).map(|v| v.data.clone()))
+    /// (hits, misses) of the response cache since startup.
+    pub fn cache_stats(&self) -> (u64, u64) {
+        (
+            self.cache_hits.load(Ordering::Relaxed),
+            self.cache_misses.load(Ordering::Relaxed),
+        )
     }
 }
 
@@ -788,6 +1236,64 @@ impl std::fmt::Display for DataFetcher {
 }
 
 impl DataFetcher {
+    /// GET request to `source` with its own user agent and `timeout_secs`.
+    pub(crate) fn source_get(&self, source: &SourceConfig, url: &str) -> RequestBuilder {
+        self.http_client
+            .get(url)
+            .timeout(Duration::from_secs(source.timeout_secs))
+            .header("User-Agent", &source.user_agent)
+    }
+
+    /// Send a `source_get` request once a slot is free, so the total number of upstream
+    /// requests in flight stays within `data_source.max_concurrent_requests`. The slot is
+    /// held until the response headers arrive.
+    async fn source_send(&self, source: &SourceConfig, url: &str) -> reqwest::Result<Response> {
+        let _slot = self
+            .request_slots
+            .acquire()
+            .await
+            .expect("request semaphore is never closed");
+        self.source_get(source, url).send().await
+    }
+
+    /// Every configured source with its enabled flag and last observed outcome.
+    pub async fn source_statuses(&self) -> Vec<SourceStatus> {
+        let health = self.source_health.read().await;
+        let sources = &self.config.data_source;
+        [
+            ("EastMoney", sources.eastmoney.enabled),
+            ("Baidu Finance", sources.baidu.enabled),
+            ("Sina Finance", sources.sina.enabled),
+        ]
+        .into_iter()
+        .map(|(name, enabled)| {
+            let entry = health.get(name).cloned().unwrap_or_default();
+            SourceStatus {
+                name,
+                enabled,
+                last_success_ts: entry.last_success_ts,
+                last_error: entry.last_error,
+                successes: entry.successes,
+                failures: entry.failures,
+            }
+        })
+        .collect()
+    }
+
+    async fn mark_source_ok(&self, name: &'static str) {
+        let mut health = self.source_health.write().await;
+        let entry = health.entry(name).or_default();
+        entry.last_success_ts = Some(Utc::now().timestamp_millis());
+        entry.successes += 1;
+    }
+
+    async fn mark_source_failed(&self, name: &'static str, error: &AppError) {
+        let mut health = self.source_health.write().await;
+        let entry = health.entry(name).or_default();
+        entry.last_error = Some(error.to_string());
+        entry.failures += 1;
+    }
+
     fn get_enabled_sources(&self) -> Vec<&str> {
         let mut sources = Vec::new();
         if self.config.data_source.eastmoney.enabled {
@@ -802,323 +1308,104 @@ impl DataFetcher {
         sources
     }
 }
-/// Get real-time quote for a symbol
-pub async fn get_quote(&self, symbol: &str) -> Result<Quote, AppError> {
-    let normalized_symbol = self.normalize_symbol(symbol);
-    log::info!("Getting quote for symbol: {}", normalized_symbol);
-
-    // Try to get from cache first
-    if let Some(cached) = self
-        .get_from_cache(&format!("quote:{}", normalized_symbol))
-        .await?
-    {
-        log::debug!("Returning cached quote for {}", normalized_symbol);
-        return Ok(serde_json::from_value(cached)?);
-    }
-
-    // Try multiple data sources with detailed error logging
-    let mut errors = Vec::new();
-
-    // Try EastMoney first
-    if self.config.data_source.eastmoney.enabled {
-        log::debug!(
-            "Trying to get quote from EastMoney for {}",
-            normalized_symbol
-        );
-        match self.get_quote_from_eastmoney(&normalized_symbol).await {
-            Ok(quote) => {
-                log::info!(
-                    "Successfully got quote from EastMoney for {}",
-                    normalized_symbol
-                );
-                self.cache_data(
-                    &format!("quote:{}", normalized_symbol),
-                    serde_json::to_value(&quote)?,
-                    self.config.data_source.cache_duration * 1000,
-                )
-                .await?;
-                return Ok(quote);
-            }
-            Err(e) => {
-                log::error!("EastMoney failed for {}: {}", normalized_symbol, e);
-                errors.push(("EastMoney", e.to_string()));
-            }
-        }
-    }
 
-    // Try Baidu Finance
-    if self.config.data_source.baidu.enabled {
-        log::debug!(
-            "Trying to get quote from Baidu Finance for {}",
-            normalized_symbol
-        );
-        match self.get_quote_from_baidu(&normalized_symbol).await {
-            Ok(quote) => {
-                log::info!(
-                    "Successfully got quote from Baidu Finance for {}",
-                    normalized_symbol
-                );
-                self.cache_data(
-                    &format!("quote:{}", normalized_symbol),
-                    serde_json::to_value(&quote)?,
-                    self.config.data_source.cache_duration * 1000,
-                )
-                .await?;
-                return Ok(quote);
-            }
-            Err(e) => {
-                log::error!("Baidu Finance failed for {}: {}", normalized_symbol, e);
-                errors.push(("Baidu Finance", e.to_string()));
-            }
-        }
-    }
-
-    // Try Sina Finance
-    if self.config.data_source.sina.enabled {
-        log::debug!(
-            "Trying to get quote from Sina Finance for {}",
-            normalized_symbol
-        );
-        match self.get_quote_from_sina(&normalized_symbol).await {
-            Ok(quote) => {
-                log::info!(
-                    "Successfully got quote from Sina Finance for {}",
-                    normalized_symbol
-                );
-                self.cache_data(
-                    &format!("quote:{}", normalized_symbol),
-                    serde_json::to_value(&quote)?,
-                    self.config.data_source.cache_duration * 1000,
-                )
-                .await?;
-                return Ok(quote);
-            }
-            Err(e) => {
-                log::error!("Sina Finance failed for {}: {}", normalized_symbol, e);
-                errors.push(("Sina Finance", e.to_string()));
-            }
-        }
-    }
-
-    // If all sources failed, try to get from storage or return simulated data
-    log::warn!(
-        "All data sources failed for {}, trying fallback strategies",
-        normalized_symbol
-    );
+/// Anything that can produce historical K-lines. `DataFetcher` is the real
+/// implementation; the backfill job only depends on this so it can be driven by a mock.
+pub trait KlineSource {
+    fn fetch_klines(
+        &self,
+        symbol: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        period: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<Kline>, AppError>> + Send;
+}
 
-    // Try to get from storage
-    if let Ok(Some(quote)) = self.get_quote_from_storage(&normalized_symbol).await {
-        log::info!(
-            "Returning historical quote from storage for {}",
-            normalized_symbol
-        );
-        return Ok(quote);
+impl KlineSource for DataFetcher {
+    async fn fetch_klines(
+        &self,
+        symbol: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        period: &str,
+    ) -> Result<Vec<Kline>, AppError> {
+        self.get_kline_data(symbol, start_date, end_date, period)
+            .await
     }
-
-    // As a last resort, return simulated data with warning
-    log::warn!(
-        "No data available for {}, returning simulated data",
-        normalized_symbol
-    );
-    Ok(self.generate_simulated_quote(&normalized_symbol))
 }
 
-/// Get quote from storage as fallback
-async fn get_quote_from_storage(&self, symbol: &str) -> Result<Option<Quote>, AppError> {
-    // In a real implementation, this would query the database
-    // For now, return None
-    Ok(None)
+/// Anything that can produce a real-time quote; the live poller depends on this.
+pub trait QuoteSource {
+    fn fetch_quote(
+        &self,
+        symbol: &str,
+    ) -> impl std::future::Future<Output = Result<Quote, AppError>> + Send;
 }
 
-/// Generate simulated quote for fallback
-fn generate_simulated_quote(&self, symbol: &str) -> Quote {
-    let base_price = if symbol.starts_with("600733") {
-        15.50 // Simulated price for 600733
-    } else if symbol.starts_with("000001") {
-        10.50 // Simulated price for 000001
-    } else {
-        8.0 + rand::random::<f64>() * 4.0 // Random price between 8-12
-    };
-
-    let change = (rand::random::<f64>() - 0.5) * 0.2; // Random change between -10% and +10%
-    let price = base_price * (1.0 + change);
-
-    Quote {
-        symbol: symbol.to_string(),
-        timestamp: chrono::Utc::now().timestamp_millis(),
-        price: Decimal::from_f64(price).unwrap().round_dp(2),
-        open: Some(Decimal::from_f64(base_price).unwrap().round_dp(2)),
-        high: Some(
-            Decimal::from_f64(base_price * (1.0 + change.abs() * 1.5))
-                .unwrap()
-                .round_dp(2),
-        ),
-        low: Some(
-            Decimal::from_f64(base_price * (1.0 - change.abs() * 1.5))
-                .unwrap()
-                .round_dp(2),
-        ),
-        prev_close: Some(Decimal::from_f64(base_price).unwrap().round_dp(2)),
-        volume: Some(
-            Decimal::from_f64(rand::random::<f64>() * 1000000.0 + 500000.0)
-                .unwrap()
-                .round_dp(0),
-        ),
-        amount: Some(Decimal::from_f64(price * 1000000.0).unwrap().round_dp(0)),
-        change: Some(Decimal::from_f64(price - base_price).unwrap().round_dp(2)),
-        change_pct: Some(Decimal::from_f64(change * 100.0).unwrap().round_dp(2)),
-        bid_price: Some(Decimal::from_f64(price - 0.01).unwrap().round_dp(2)),
-        ask_price: Some(Decimal::from_f64(price).unwrap().round_dp(2)),
-        bid_volume: Some(
-            Decimal::from_f64(rand::random::<f64>() * 10000.0 + 5000.0)
-                .unwrap()
-                .round_dp(0),
-        ),
-        ask_volume: Some(
-            Decimal::from_f64(rand::random::<f64>() * 10000.0 + 5000.0)
-                .unwrap()
-                .round_dp(0),
-        ),
+impl QuoteSource for DataFetcher {
+    async fn fetch_quote(&self, symbol: &str) -> Result<Quote, AppError> {
+        self.get_quote(symbol).await
     }
 }
 
-/// Get historical K-line data with fallback
-pub async fn get_kline_data(
-    &self,
-    symbol: &str,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-    period: &str,
-) -> Result<Vec<Kline>, AppError> {
-    let normalized_symbol = self.normalize_symbol(symbol);
-    let cache_key = format!(
-        "kline:{}:{}:{}:{}",
-        normalized_symbol,
-        start_date.format("%Y%m%d"),
-        end_date.format("%Y%m%d"),
-        period
-    );
-
-    log::info!(
-        "Getting K-line data for {} ({} to {})",
-        normalized_symbol,
-        start_date,
-        end_date
-    );
-
-    // Try cache
-    if let Some(cached) = self.get_from_cache(&cache_key).await? {
-        log::debug!("Returning cached K-line data for {}", normalized_symbol);
-        return Ok(serde_json::from_value(cached)?);
-    }
+/// Epoch millis of a kline's `"%Y-%m-%d %H:%M"` (intraday) or `"%Y-%m-%d"` (daily)
+/// datetime, read as wall-clock time in `tz`. Daily bars are stamped at the 15:00
+/// session close.
+pub(crate) fn parse_kline_timestamp(date_str: &str, tz: Tz) -> Option<i64> {
+    let local = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(15, 0, 0))
+        })?;
+    Some(market_local_to_millis(tz, local))
+}
 
-    // Try to get from data source
-    match self
-        .get_kline_from_eastmoney(&normalized_symbol, start_date, end_date, period)
-        .await
+/// Parse an upstream field, mapping the source's "no data" placeholders (see
+/// `FieldKind`) to `None`.
+pub(crate) fn parse_field(
+    value: &serde_json::Value,
+    key: &str,
+    kind: FieldKind,
+    dp: u32,
+) -> Result<Option<Decimal>, AppError> {
+    if let Some(s) = value.as_str()
+        && matches!(s.trim(), "" | "-")
     {
-        Ok(klines) => {
-            log::info!("Successfully got K-line data for {}", normalized_symbol);
-            self.cache_data(
-                &cache_key,
-                serde_json::to_value(&klines)?,
-                3600 * 1000, // Cache for 1 hour
-            )
-            .await?;
-            Ok(klines)
-        }
-        Err(e) => {
-            log::error!("Failed to get K-line data from EastMoney: {}", e);
-
-            // Try to get from storage
-            if let Ok(Some(klines)) = self
-                .get_klines_from_storage(&normalized_symbol, start_date, end_date, period)
-                .await
-            {
-                log::info!(
-                    "Returning K-line data from storage for {}",
-                    normalized_symbol
-                );
-                return Ok(klines);
-            }
-
-            // Generate simulated K-line data
-            log::warn!("Generating simulated K-line data for {}", normalized_symbol);
-            Ok(self.generate_simulated_klines(&normalized_symbol, start_date, end_date, period))
-        }
+        return Ok(None);
     }
+    let parsed = parse_json_decimal(value, key, dp)?;
+    if kind == FieldKind::Price && parsed.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(parsed))
 }
 
-/// Get K-lines from storage as fallback
-async fn get_klines_from_storage(
-    &self,
-    symbol: &str,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-    period: &str,
-) -> Result<Option<Vec<Kline>>, AppError> {
-    // In a real implementation, this would query the database
-    Ok(None)
-}
-
-/// Generate simulated K-line data
-fn generate_simulated_klines(
-    &self,
-    symbol: &str,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-    period: &str,
-) -> Vec<Kline> {
-    let mut klines = Vec::new();
-    let days = (end_date - start_date).num_days() as usize;
-
-    // Base price based on symbol
-    let base_price = if symbol.starts_with("600733") {
-        15.50
-    } else if symbol.starts_with("000001") {
-        10.50
-    } else {
-        8.0 + rand::random::<f64>() * 4.0
-    };
-
-    let mut current_price = base_price;
-
-    for i in 0..days {
-        let date = start_date + chrono::Duration::days(i as i64);
-
-        // Skip weekends
-        let weekday = date.weekday();
-        if weekday == chrono::Weekday::Sat || weekday == chrono::Weekday::Sun {
-            continue;
+/// Parse a JSON string or number into a Decimal rounded to at most `dp` places.
+///
+/// Numbers go through their textual form rather than `f64`, so `12.345` stays
+/// `12.345` and large integer volumes keep every digit.
+pub(crate) fn parse_json_decimal(
+    value: &serde_json::Value,
+    key: &str,
+    dp: u32,
+) -> Result<Decimal, AppError> {
+    let parsed = match value {
+        serde_json::Value::String(s) => Decimal::from_str_radix(s.trim(), 10)
+            .with_context(format!("Invalid decimal value for {}: {}", key, s))?,
+        serde_json::Value::Number(n) => {
+            let text = n.to_string();
+            Decimal::from_str_exact(&text)
+                .or_else(|_| Decimal::from_scientific(&text))
+                .with_context(format!("Invalid number for {}: {}", key, text))?
         }
-
-        // Generate daily price movement
-        let change = (rand::random::<f64>() - 0.5) * 0.03; // ±3% daily change
-        current_price *= (1.0 + change);
-
-        let open = current_price * (0.995 + rand::random::<f64>() * 0.01); // Open within ±0.5% of current price
-        let high = open * (1.0 + rand::random::<f64>() * 0.02); // High up to +2%
-        let low = open * (0.98 + rand::random::<f64>() * 0.02); // Low down to -2%
-        let close = if rand::random::<f64>() > 0.5 {
-            (open + high + low + current_price) / 4.0
-        } else {
-            (open + high + low + current_price * 0.99) / 4.0
-        };
-
-        let volume = rand::random::<f64>() * 1000000.0 + 500000.0;
-
-        klines.push(Kline {
-            symbol: symbol.to_string(),
-            timestamp: date.and_hms_opt(15, 0, 0).unwrap().timestamp_millis(),
-            open: Decimal::from_f64(open).unwrap().round_dp(2),
-            high: Decimal::from_f64(high).unwrap().round_dp(2),
-            low: Decimal::from_f64(low).unwrap().round_dp(2),
-            close: Decimal::from_f64(close).unwrap().round_dp(2),
-            volume: Decimal::from_f64(volume).unwrap().round_dp(0),
-            amount: Some(Decimal::from_f64(close * volume).unwrap().round_dp(0)),
-            period: period.to_string(),
-        });
-    }
-
-    klines
+        _ => {
+            return Err(AppError::invalid_data(format!(
+                "Unsupported type for {}: {:?}",
+                key, value
+            )));
+        }
+    };
+    Ok(parsed.round_dp(dp))
 }
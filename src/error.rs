@@ -10,11 +10,19 @@ pub struct ApiErrorResponse {
     pub code: u16,
 }
 
+/// Machine-readable category for errors raised by the upstream data sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorCode {
+    DataSourceError,
+    InvalidData,
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
     Database(String),
 
+    #[cfg(feature = "redis")]
     #[error("Redis error: {0}")]
     Redis(String),
 
@@ -33,6 +41,12 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("{code:?}: {message}")]
+    Api { code: ErrorCode, message: String },
+
     #[error("Internal server error")]
     Internal,
 }
@@ -41,17 +55,70 @@ impl AppError {
     pub fn status_code(&self) -> u16 {
         match self {
             AppError::Database(_) => 500,
+            #[cfg(feature = "redis")]
             AppError::Redis(_) => 500,
             AppError::Reqwest(_) => 502,
             AppError::Serialization(_) => 500,
             AppError::Config(_) => 500,
             AppError::DataNotFound(_) => 404,
             AppError::Validation(_) => 400,
+            AppError::Conflict(_) => 409,
+            AppError::Api { .. } => 502,
             AppError::Internal => 500,
         }
     }
 }
 
+impl AppError {
+    pub fn api(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError::Api {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn data_not_found(message: impl Into<String>) -> Self {
+        AppError::DataNotFound(message.into())
+    }
+
+    pub fn invalid_data(message: impl Into<String>) -> Self {
+        AppError::api(ErrorCode::InvalidData, message)
+    }
+
+    pub fn invalid_parameter(message: impl Into<String>) -> Self {
+        AppError::Validation(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict(message.into())
+    }
+
+    pub fn invalid_symbol(symbol: &str) -> Self {
+        AppError::Validation(format!("Invalid symbol: {}", symbol))
+    }
+
+    pub fn invalid_date_range() -> Self {
+        AppError::Validation("Invalid date range".to_string())
+    }
+}
+
+/// Attach a human-readable context to any displayable error, turning it into an
+/// upstream `InvalidData`/`DataSourceError` style `AppError`.
+pub trait ResultExt<T> {
+    fn with_context(self, context: impl Into<String>) -> Result<T>;
+}
+
+impl<T, E: std::fmt::Display> ResultExt<T> for std::result::Result<T, E> {
+    fn with_context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|e| {
+            AppError::api(
+                ErrorCode::DataSourceError,
+                format!("{}: {}", context.into(), e),
+            )
+        })
+    }
+}
+
 impl ResponseError for AppError {
     fn error_response(&self) -> HttpResponse {
         let status_code = self.status_code();
@@ -70,8 +137,8 @@ impl ResponseError for AppError {
 }
 
 impl From<anyhow::Error> for AppError {
-    fn from(_err: anyhow::Error) -> Self {
-        AppError::Internal
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast::<AppError>().unwrap_or(AppError::Internal)
     }
 }
 
@@ -93,6 +160,7 @@ impl From<rusqlite::Error> for AppError {
     }
 }
 
+#[cfg(feature = "redis")]
 impl From<redis::RedisError> for AppError {
     fn from(err: redis::RedisError) -> Self {
         AppError::Redis(err.to_string())
@@ -1,36 +1,157 @@
 // src/executor.rs
-use anyhow::Result;
-use std::sync::Arc;
+use crate::utils::tick_size::TickSizeTable;
+use anyhow::{Result, bail};
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// `client_order_id`s remembered per executor; the oldest is forgotten past this, long
+/// after any retry of it could still arrive.
+const SIM_ORDER_KEYS: usize = 10_000;
+
+/// A simulated order fill; `price` is the requested price rounded to the symbol's tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimFill {
+    pub id: String,
+    pub price: f64,
+}
+
+/// What a `client_order_id` was first used for, to tell a retry from a reused key.
+struct SimOrder {
+    symbol: String,
+    price: f64,
+    amount: f64,
+    fill: SimFill,
+}
+
+#[derive(Default)]
+struct SimBook {
+    /// orders by `client_order_id`, so a retried order is answered instead of re-filled
+    orders: HashMap<String, SimOrder>,
+    /// keys of `orders`, oldest first, bounded by `SIM_ORDER_KEYS`
+    order_keys: VecDeque<String>,
+    /// net filled amount per symbol
+    positions: HashMap<String, f64>,
+}
 
 #[derive(Clone)]
 pub struct SimExecutor {
     counter: Arc<AtomicUsize>,
+    tick_sizes: TickSizeTable,
+    book: Arc<Mutex<SimBook>>,
 }
 
 impl SimExecutor {
-    pub fn new() -> Self {
+    pub fn new(tick_sizes: TickSizeTable) -> Self {
         Self {
             counter: Arc::new(AtomicUsize::new(0)),
+            tick_sizes,
+            book: Arc::new(Mutex::new(SimBook::default())),
         }
     }
 
-    pub async fn buy(&self, symbol: &str, price: f64, amount: f64) -> Result<String> {
-        let id = self.counter.fetch_add(1, Ordering::SeqCst);
-        println!(
-            "[SIM BUY] {} @ {:.2} x {} -> id={}",
-            symbol, price, amount, id
-        );
-        Ok(format!("sim-{}", id))
+    /// Buy `amount` of `symbol`. Repeating a `client_order_id` returns the original
+    /// fill without trading again, so a timed-out order can be retried safely; reusing
+    /// one for a different order is an error.
+    pub async fn buy(
+        &self,
+        symbol: &str,
+        price: f64,
+        amount: f64,
+        client_order_id: Option<&str>,
+    ) -> Result<SimFill> {
+        let (fill, repeated) = self.execute(symbol, price, amount, client_order_id)?;
+        if repeated {
+            info!("[SIM BUY] repeated order -> id={}", fill.id);
+        } else {
+            info!(
+                "[SIM BUY] {} @ {} x {} -> id={}",
+                symbol, fill.price, amount, fill.id
+            );
+        }
+        Ok(fill)
     }
 
-    pub async fn sell(&self, symbol: &str, price: f64, amount: f64) -> Result<String> {
+    /// Sell `amount` of `symbol`; `client_order_id` as for `buy`.
+    pub async fn sell(
+        &self,
+        symbol: &str,
+        price: f64,
+        amount: f64,
+        client_order_id: Option<&str>,
+    ) -> Result<SimFill> {
+        let (fill, repeated) = self.execute(symbol, price, -amount, client_order_id)?;
+        if repeated {
+            info!("[SIM SELL] repeated order -> id={}", fill.id);
+        } else {
+            info!(
+                "[SIM SELL] {} @ {} x {} -> id={}",
+                symbol, fill.price, amount, fill.id
+            );
+        }
+        Ok(fill)
+    }
+
+    /// Net amount bought minus sold for `symbol`.
+    pub fn position(&self, symbol: &str) -> f64 {
+        let book = self.book.lock().unwrap();
+        book.positions.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Fill a signed `amount` (negative sells) unless `client_order_id` was filled
+    /// before; true when the earlier fill is returned. A known key with a different
+    /// symbol, side, amount or price is refused.
+    fn execute(
+        &self,
+        symbol: &str,
+        price: f64,
+        amount: f64,
+        client_order_id: Option<&str>,
+    ) -> Result<(SimFill, bool)> {
+        let mut book = self.book.lock().unwrap();
+        if let Some((key, order)) =
+            client_order_id.and_then(|key| book.orders.get(key).map(|order| (key, order)))
+        {
+            if order.symbol != symbol || order.price != price || order.amount != amount {
+                bail!(
+                    "client_order_id {} was already used for {} {} @ {}",
+                    key,
+                    order.symbol,
+                    order.amount,
+                    order.price
+                );
+            }
+            return Ok((order.fill.clone(), true));
+        }
+        let fill = self.fill(symbol, price);
+        *book.positions.entry(symbol.to_string()).or_default() += amount;
+        if let Some(key) = client_order_id {
+            if book.order_keys.len() >= SIM_ORDER_KEYS
+                && let Some(oldest) = book.order_keys.pop_front()
+            {
+                book.orders.remove(&oldest);
+            }
+            book.order_keys.push_back(key.to_string());
+            book.orders.insert(
+                key.to_string(),
+                SimOrder {
+                    symbol: symbol.to_string(),
+                    price,
+                    amount,
+                    fill: fill.clone(),
+                },
+            );
+        }
+        Ok((fill, false))
+    }
+
+    fn fill(&self, symbol: &str, price: f64) -> SimFill {
         let id = self.counter.fetch_add(1, Ordering::SeqCst);
-        println!(
-            "[SIM SELL] {} @ {:.2} x {} -> id={}",
-            symbol, price, amount, id
-        );
-        Ok(format!("sim-{}", id))
+        SimFill {
+            id: format!("sim-{}", id),
+            price: self.tick_sizes.round_to_tick(price, symbol),
+        }
     }
 }
 
@@ -64,13 +185,20 @@ impl GuosenExecutor {
         Ok(resp)
     }
 
-    /// 买入下单
-    pub async fn buy(&self, symbol: &str, price: f64, amount: f64) -> Result<serde_json::Value> {
+    /// 买入下单；`client_order_id` 原样转发，券商据此对重试的订单去重
+    pub async fn buy(
+        &self,
+        symbol: &str,
+        price: f64,
+        amount: f64,
+        client_order_id: Option<&str>,
+    ) -> Result<serde_json::Value> {
         let url = format!("{}/trade/buy", self.base_url);
         let body = serde_json::json!({
             "symbol": symbol,
             "price": price,
             "amount": amount,
+            "client_order_id": client_order_id,
             "api_key": self.api_key,
             "sign": self.sign(symbol, price, amount),
         });
@@ -84,13 +212,20 @@ impl GuosenExecutor {
         Ok(resp)
     }
 
-    /// 卖出下单
-    pub async fn sell(&self, symbol: &str, price: f64, amount: f64) -> Result<serde_json::Value> {
+    /// 卖出下单；`client_order_id` 同买入
+    pub async fn sell(
+        &self,
+        symbol: &str,
+        price: f64,
+        amount: f64,
+        client_order_id: Option<&str>,
+    ) -> Result<serde_json::Value> {
         let url = format!("{}/trade/sell", self.base_url);
         let body = serde_json::json!({
             "symbol": symbol,
             "price": price,
             "amount": amount,
+            "client_order_id": client_order_id,
             "api_key": self.api_key,
             "sign": self.sign(symbol, price, amount),
         });
@@ -0,0 +1,101 @@
+// src/feed.rs
+use crate::storage::{Storage, Tick};
+use crate::utils::time::TsMillis;
+use anyhow::{Context, Result, bail};
+use chrono::NaiveDate;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, info, instrument};
+
+/// Ticks buffered per subscriber by default before a slow client falls behind.
+const FEED_CAPACITY: usize = 1024;
+/// `source` of the ticks `replay_day` publishes.
+pub const REPLAY_SOURCE: &str = "replay";
+
+/// Whether `tick` was re-emitted by `replay_day` rather than received live. Such ticks
+/// are for watching only: alerts and the streamed MACD leave them out.
+pub fn is_replayed(tick: &Tick) -> bool {
+    tick.source.as_deref() == Some(REPLAY_SOURCE)
+}
+
+/// Fan-out of live ticks to streaming clients (`/api/stream/{symbol}`).
+#[derive(Debug, Clone)]
+pub struct TickFeed {
+    tx: broadcast::Sender<Tick>,
+}
+
+impl TickFeed {
+    pub fn new() -> Self {
+        Self::with_capacity(FEED_CAPACITY)
+    }
+
+    /// Each subscriber buffers at most `capacity` ticks; once a client falls further
+    /// behind, the oldest ones are overwritten and its next receive reports the lag.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { tx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Tick> {
+        self.tx.subscribe()
+    }
+
+    /// Send a tick to every current subscriber; returns how many received it.
+    pub fn publish(&self, tick: Tick) -> usize {
+        self.tx.send(tick).unwrap_or(0)
+    }
+}
+
+impl Default for TickFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Re-emit one stored day of `symbol` through `feed` as if it were live, waiting the
+/// original gap between ticks divided by `speed`. Each tick is published with
+/// `source = REPLAY_SOURCE`; nothing is written back to storage. Returns the number of
+/// ticks replayed.
+#[instrument(skip(storage, feed))]
+pub async fn replay_day(
+    storage: &Storage,
+    feed: &TickFeed,
+    symbol: &str,
+    date: NaiveDate,
+    speed: f64,
+) -> Result<usize> {
+    if !(speed.is_finite() && speed > 0.0) {
+        bail!("Replay speed must be a positive number, got {}", speed);
+    }
+
+    let ticks = storage
+        .get_ticks_for_date(symbol, &date.format("%Y-%m-%d").to_string())
+        .await
+        .with_context(|| format!("Failed to load ticks to replay for {}", symbol))?;
+    info!(
+        "Replaying {} ticks for {} on {} at {}x",
+        ticks.len(),
+        symbol,
+        date,
+        speed
+    );
+
+    let mut prev_ts: Option<TsMillis> = None;
+    for tick in &ticks {
+        if let Some(prev) = prev_ts {
+            let gap_ms = (tick.ts - prev).max(0) as f64 / speed;
+            if gap_ms >= 1.0 {
+                tokio::time::sleep(Duration::from_secs_f64(gap_ms / 1000.0)).await;
+            }
+        }
+        prev_ts = Some(tick.ts);
+        let receivers = feed.publish(Tick {
+            source: Some(REPLAY_SOURCE.to_string()),
+            ..tick.clone()
+        });
+        debug!("Replayed tick {} to {} subscribers", tick.ts, receivers);
+    }
+
+    info!("Replay for {} on {} finished", symbol, date);
+    Ok(ticks.len())
+}
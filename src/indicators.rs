@@ -1,9 +1,18 @@
 // src/indicators.rs
-use serde::Serialize;
+use crate::storage::{Bar, Tick};
+use crate::utils::calendar::{SessionWindow, TradingCalendar};
+use crate::utils::csv::{CsvRecord, optional};
+use crate::utils::number_format;
+use crate::utils::time::TsMillis;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 
 /// Simple EMA and MACD implementation used to build DIF/DEA/MACD series.
 /// Deterministic, streaming-friendly.
-
+///
+/// Two smoothings are available: `new` uses the standard `2 / (period + 1)` multiplier
+/// (MACD, PPO) and `new_wilder` uses Wilder's `1 / period` (RSI, ATR).
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub struct EMA {
     mult: f64,
@@ -11,14 +20,24 @@ pub struct EMA {
 }
 
 impl EMA {
+    /// `period` must be >= 1; 0 would give a multiplier of 2 and an EMA that overshoots
+    /// every step, so it is treated as 1 (the EMA then just tracks the latest value).
     pub fn new(period: usize) -> Self {
-        let mult = 2.0 / (period as f64 + 1.0);
+        let mult = 2.0 / (period.max(1) as f64 + 1.0);
         EMA {
             mult,
             current: None,
         }
     }
 
+    /// Wilder's smoothing, multiplier `1 / period`; a period of 0 is treated as 1.
+    pub fn new_wilder(period: usize) -> Self {
+        EMA {
+            mult: 1.0 / period.max(1) as f64,
+            current: None,
+        }
+    }
+
     pub fn next(&mut self, value: f64) -> f64 {
         match self.current {
             None => {
@@ -34,6 +53,10 @@ impl EMA {
     }
 }
 
+/// MACD histogram scale: `macd = MACD_HISTOGRAM_MULTIPLIER * (dif - dea)`, the
+/// convention of Chinese charting software.
+pub const MACD_HISTOGRAM_MULTIPLIER: f64 = 2.0;
+
 #[derive(Debug)]
 pub struct MACDCalc {
     ema_short: EMA,
@@ -43,11 +66,64 @@ pub struct MACDCalc {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct MACDPoint {
-    pub ts: i64,
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
     pub price: f64,
+    #[serde(with = "number_format::float")]
     pub dif: f64,
+    #[serde(with = "number_format::float")]
     pub dea: f64,
+    #[serde(with = "number_format::float")]
     pub macd: f64,
+    /// short and long EMA of the price, for drawing alongside it; omitted when unknown
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "number_format::option_float"
+    )]
+    pub ema_short: Option<f64>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "number_format::option_float"
+    )]
+    pub ema_long: Option<f64>,
+    /// within the first `long` points, where the EMAs are still mostly their seed; only
+    /// set by `compute_macd_series_flagged` and omitted from JSON when false
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub warming_up: bool,
+}
+
+impl MACDPoint {
+    /// Copy with price and MACD values rounded to `decimals` places, for responses.
+    pub fn rounded(&self, decimals: u32) -> Self {
+        let scale = 10f64.powi(decimals as i32);
+        let round = |v: f64| (v * scale).round() / scale;
+        MACDPoint {
+            ts: self.ts,
+            price: round(self.price),
+            dif: round(self.dif),
+            dea: round(self.dea),
+            macd: round(self.macd),
+            ema_short: self.ema_short.map(round),
+            ema_long: self.ema_long.map(round),
+            warming_up: self.warming_up,
+        }
+    }
+}
+
+impl CsvRecord for MACDPoint {
+    const HEADER: &'static str = "ts,price,dif,dea,macd,ema_short,ema_long";
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.ts.to_string(),
+            self.price.to_string(),
+            self.dif.to_string(),
+            self.dea.to_string(),
+            self.macd.to_string(),
+            optional(self.ema_short),
+            optional(self.ema_long),
+        ]
+    }
 }
 
 impl MACDCalc {
@@ -65,25 +141,943 @@ impl MACDCalc {
         let l = self.ema_long.next(close);
         let dif = s - l;
         let dea = self.dea_ema.next(dif);
-        let macd = 2.0 * (dif - dea);
+        let macd = MACD_HISTOGRAM_MULTIPLIER * (dif - dea);
         (dif, dea, macd)
     }
+
+    /// `next`, returned as a point that also carries both EMAs
+    pub fn next_point(&mut self, ts: TsMillis, close: f64) -> MACDPoint {
+        let (dif, dea, macd) = self.next(close);
+        MACDPoint {
+            ts,
+            price: close,
+            dif,
+            dea,
+            macd,
+            ema_short: self.ema_short.current,
+            ema_long: self.ema_long.current,
+            warming_up: false,
+        }
+    }
+
+    /// `next_point` fed `ln(close)`, with `price` and both EMAs mapped back to price
+    /// scale; `close` must be positive
+    pub fn next_log_point(&mut self, ts: TsMillis, close: f64) -> MACDPoint {
+        let mut point = self.next_point(ts, close.ln());
+        point.price = close;
+        point.ema_short = point.ema_short.map(f64::exp);
+        point.ema_long = point.ema_long.map(f64::exp);
+        point
+    }
 }
 
 /// Given a vector of (ts, price) returns vector of MACDPoint (with dif/dea/macd).
-/// The input must be time-ordered ascending.
-pub fn compute_macd_series(points: &[(i64, f64)]) -> Vec<MACDPoint> {
-    let mut macd = MACDCalc::new(12, 26, 9);
+/// The input must be time-ordered ascending. Periods usually come from `TradingConfig`
+/// (12/26/9 by default).
+pub fn compute_macd_series(
+    points: &[(TsMillis, f64)],
+    short: usize,
+    long: usize,
+    signal: usize,
+) -> Vec<MACDPoint> {
+    let mut macd = MACDCalc::new(short, long, signal);
+    points
+        .iter()
+        .map(|(ts, price)| macd.next_point(*ts, *price))
+        .collect()
+}
+
+/// Which price of a bar feeds the MACD EMAs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    #[default]
+    Close,
+    /// `(high + low + close) / 3`
+    Typical,
+    /// `(high + low + 2 * close) / 4`
+    Weighted,
+}
+
+impl PriceSource {
+    pub fn of(self, bar: &Bar) -> f64 {
+        match self {
+            PriceSource::Close => bar.close,
+            PriceSource::Typical => (bar.high + bar.low + bar.close) / 3.0,
+            PriceSource::Weighted => (bar.high + bar.low + 2.0 * bar.close) / 4.0,
+        }
+    }
+}
+
+/// `(ts, price)` input for `compute_macd_series` taking each bar's `source` price.
+pub fn bar_prices(bars: &[Bar], source: PriceSource) -> Vec<(TsMillis, f64)> {
+    bars.iter().map(|b| (b.ts, source.of(b))).collect()
+}
+
+/// `compute_macd_series` with the first `long` points marked `warming_up`, so charts
+/// can set the seed-dominated start apart from the usable signal.
+pub fn compute_macd_series_flagged(
+    points: &[(TsMillis, f64)],
+    short: usize,
+    long: usize,
+    signal: usize,
+) -> Vec<MACDPoint> {
+    let mut series = compute_macd_series(points, short, long, signal);
+    mark_warmup(&mut series, long);
+    series
+}
+
+/// Set `warming_up` on the first `long` points of a MACD series.
+pub fn mark_warmup(series: &mut [MACDPoint], long: usize) {
+    for point in series.iter_mut().take(long) {
+        point.warming_up = true;
+    }
+}
+
+/// `compute_macd_series` fed with `ln(price)`, so equal percentage moves weigh the
+/// same at any price level. `price` stays the raw price and the EMAs are mapped back
+/// to price scale; DIF/DEA/MACD are in log units. Fails with the index of the first
+/// non-positive price.
+pub fn compute_macd_series_log(
+    points: &[(TsMillis, f64)],
+    short: usize,
+    long: usize,
+    signal: usize,
+) -> Result<Vec<MACDPoint>, usize> {
+    if let Some(i) = points.iter().position(|(_, price)| *price <= 0.0) {
+        return Err(i);
+    }
+    let mut macd = MACDCalc::new(short, long, signal);
+    Ok(points
+        .iter()
+        .map(|(ts, price)| macd.next_log_point(*ts, *price))
+        .collect())
+}
+
+/// Percentage Price Oscillator: the MACD line expressed as a percentage of the long EMA,
+/// so it can be compared across symbols at different price levels.
+#[allow(dead_code)] // for cross-symbol comparison, not served by an endpoint yet
+#[derive(Debug, Clone, Serialize)]
+pub struct PPOPoint {
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
+    pub price: f64,
+    /// `100 * (ema_short - ema_long) / ema_long`
+    #[serde(with = "number_format::float")]
+    pub ppo: f64,
+    /// EMA of `ppo` over the signal period
+    #[serde(with = "number_format::float")]
+    pub signal: f64,
+    /// `ppo - signal`
+    #[serde(with = "number_format::float")]
+    pub histogram: f64,
+}
+
+/// PPO series for time-ordered (ts, price) points, one output per input. A zero long
+/// EMA (all-zero prices) yields a PPO of 0 instead of dividing by zero.
+#[allow(dead_code)]
+pub fn compute_ppo_series(
+    points: &[(TsMillis, f64)],
+    short: usize,
+    long: usize,
+    signal: usize,
+) -> Vec<PPOPoint> {
+    let mut ema_short = EMA::new(short);
+    let mut ema_long = EMA::new(long);
+    let mut signal_ema = EMA::new(signal);
     let mut out = Vec::with_capacity(points.len());
     for (ts, price) in points {
-        let (dif, dea, macdv) = macd.next(*price);
-        out.push(MACDPoint {
+        let s = ema_short.next(*price);
+        let l = ema_long.next(*price);
+        let ppo = if l == 0.0 { 0.0 } else { 100.0 * (s - l) / l };
+        let signal = signal_ema.next(ppo);
+        out.push(PPOPoint {
             ts: *ts,
             price: *price,
-            dif,
-            dea,
-            macd: macdv,
+            ppo,
+            signal,
+            histogram: ppo - signal,
+        });
+    }
+    out
+}
+
+#[allow(dead_code)] // not served by an endpoint yet
+#[derive(Debug, Clone, Serialize)]
+pub struct RSIPoint {
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
+    pub rsi: f64,
+}
+
+/// Wilder's RSI over time-ordered (ts, price) points. The average gain and loss are
+/// seeded with the simple mean of the first `period` changes and then smoothed with
+/// `EMA::new_wilder`, so the first value is at index `period` and earlier points get
+/// none. A window without losses has an RSI of 100 (50 if it is also without gains).
+#[allow(dead_code)]
+pub fn compute_rsi_series(points: &[(TsMillis, f64)], period: usize) -> Vec<RSIPoint> {
+    let period = period.max(1);
+    if points.len() <= period {
+        return Vec::new();
+    }
+    let changes: Vec<f64> = points.windows(2).map(|w| w[1].1 - w[0].1).collect();
+    let mut gains = EMA::new_wilder(period);
+    let mut losses = EMA::new_wilder(period);
+    let mut gain =
+        gains.next(changes[..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64);
+    let mut loss =
+        losses.next(changes[..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64);
+
+    let rsi = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            if gain == 0.0 { 50.0 } else { 100.0 }
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+    let mut out = Vec::with_capacity(points.len() - period);
+    out.push(RSIPoint {
+        ts: points[period].0,
+        rsi: rsi(gain, loss),
+    });
+    for (i, change) in changes.iter().enumerate().skip(period) {
+        gain = gains.next(change.max(0.0));
+        loss = losses.next((-change).max(0.0));
+        out.push(RSIPoint {
+            ts: points[i + 1].0,
+            rsi: rsi(gain, loss),
         });
     }
     out
 }
+
+#[allow(dead_code)] // not served by an endpoint yet
+#[derive(Debug, Clone, Serialize)]
+pub struct ATRPoint {
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
+    pub atr: f64,
+}
+
+/// Wilder's Average True Range over time-ordered bars. The true range is the largest
+/// of high - low and the distances from the previous close to the high and low (just
+/// high - low for the first bar). It is seeded with the mean of the first `period` true
+/// ranges and then smoothed with `EMA::new_wilder`; the first value is at index
+/// `period - 1`.
+#[allow(dead_code)]
+pub fn compute_atr_series(bars: &[Bar], period: usize) -> Vec<ATRPoint> {
+    let period = period.max(1);
+    if bars.len() < period {
+        return Vec::new();
+    }
+    let true_ranges: Vec<f64> = bars
+        .iter()
+        .enumerate()
+        .map(|(i, bar)| match i.checked_sub(1).map(|p| bars[p].close) {
+            Some(prev_close) => (bar.high - bar.low)
+                .max((bar.high - prev_close).abs())
+                .max((bar.low - prev_close).abs()),
+            None => bar.high - bar.low,
+        })
+        .collect();
+
+    let mut atr = EMA::new_wilder(period);
+    let seed = atr.next(true_ranges[..period].iter().sum::<f64>() / period as f64);
+    let mut out = Vec::with_capacity(bars.len() - period + 1);
+    out.push(ATRPoint {
+        ts: bars[period - 1].ts,
+        atr: seed,
+    });
+    for (bar, tr) in bars.iter().zip(&true_ranges).skip(period) {
+        out.push(ATRPoint {
+            ts: bar.ts,
+            atr: atr.next(*tr),
+        });
+    }
+    out
+}
+
+/// Wilder average seeded like RSI and ATR: nothing until `period` values arrived, then
+/// their mean, then `EMA::new_wilder` smoothing.
+#[derive(Debug)]
+struct WilderAverage {
+    period: usize,
+    seed: Vec<f64>,
+    ema: EMA,
+}
+
+impl WilderAverage {
+    fn new(period: usize) -> Self {
+        let period = period.max(1);
+        WilderAverage {
+            period,
+            seed: Vec::with_capacity(period),
+            ema: EMA::new_wilder(period),
+        }
+    }
+
+    fn next(&mut self, value: f64) -> Option<f64> {
+        if self.seed.len() < self.period {
+            self.seed.push(value);
+            if self.seed.len() < self.period {
+                return None;
+            }
+            let mean = self.seed.iter().sum::<f64>() / self.period as f64;
+            return Some(self.ema.next(mean));
+        }
+        Some(self.ema.next(value))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ADXPoint {
+    pub ts: TsMillis,
+    #[serde(with = "number_format::option_float")]
+    pub plus_di: Option<f64>,
+    #[serde(with = "number_format::option_float")]
+    pub minus_di: Option<f64>,
+    /// 0-100; above ~25 the market is trending, below ~20 it is ranging
+    #[serde(with = "number_format::option_float")]
+    pub adx: Option<f64>,
+}
+
+/// Streaming Wilder ADX over time-ordered bars.
+#[derive(Debug)]
+pub struct ADXCalc {
+    prev: Option<(f64, f64, f64)>,
+    tr: WilderAverage,
+    plus_dm: WilderAverage,
+    minus_dm: WilderAverage,
+    adx: WilderAverage,
+}
+
+impl ADXCalc {
+    pub fn new(period: usize) -> Self {
+        ADXCalc {
+            prev: None,
+            tr: WilderAverage::new(period),
+            plus_dm: WilderAverage::new(period),
+            minus_dm: WilderAverage::new(period),
+            adx: WilderAverage::new(period),
+        }
+    }
+
+    /// feed the next bar and get +DI, -DI and ADX once each has warmed up
+    pub fn next(&mut self, bar: &Bar) -> ADXPoint {
+        let mut point = ADXPoint {
+            ts: bar.ts,
+            plus_di: None,
+            minus_di: None,
+            adx: None,
+        };
+        let Some((prev_high, prev_low, prev_close)) =
+            self.prev.replace((bar.high, bar.low, bar.close))
+        else {
+            return point;
+        };
+
+        let tr = (bar.high - bar.low)
+            .max((bar.high - prev_close).abs())
+            .max((bar.low - prev_close).abs());
+        let up = bar.high - prev_high;
+        let down = prev_low - bar.low;
+        let plus_dm = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus_dm = if down > up && down > 0.0 { down } else { 0.0 };
+
+        let (Some(tr), Some(plus_dm), Some(minus_dm)) = (
+            self.tr.next(tr),
+            self.plus_dm.next(plus_dm),
+            self.minus_dm.next(minus_dm),
+        ) else {
+            return point;
+        };
+        let (plus_di, minus_di) = if tr > 0.0 {
+            (100.0 * plus_dm / tr, 100.0 * minus_dm / tr)
+        } else {
+            (0.0, 0.0)
+        };
+        let di_sum = plus_di + minus_di;
+        let dx = if di_sum > 0.0 {
+            100.0 * (plus_di - minus_di).abs() / di_sum
+        } else {
+            0.0
+        };
+        point.plus_di = Some(plus_di);
+        point.minus_di = Some(minus_di);
+        point.adx = self.adx.next(dx);
+        point
+    }
+}
+
+/// Average Directional Index with Wilder smoothing, one point per bar. +DM/-DM are
+/// the larger of the high's rise and the low's fall from the previous bar (the other
+/// is 0); true range, +DM and -DM are averaged over `period` into +DI and -DI, DX is
+/// `|+DI - -DI| / (+DI + -DI)` and ADX is the Wilder average of DX. +DI/-DI appear from
+/// index `period` and ADX from index `2 * period - 1`; earlier values are `None`.
+pub fn compute_adx(bars: &[Bar], period: usize) -> Vec<ADXPoint> {
+    let mut adx = ADXCalc::new(period);
+    bars.iter().map(|bar| adx.next(bar)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CrossDirection {
+    Bullish,
+    Bearish,
+}
+
+/// A crossover found in an indicator series. `index` points into that series.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrossEvent {
+    pub index: usize,
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
+    pub price: f64,
+    pub direction: CrossDirection,
+}
+
+/// MACD histogram zero-line crosses, skipping any in the first `warmup` points
+/// (the EMAs seed on the first value so early crosses are noise).
+pub fn macd_cross_events(points: &[MACDPoint], warmup: usize) -> Vec<CrossEvent> {
+    let mut events = Vec::new();
+    for i in warmup.max(1)..points.len() {
+        let (prev, current) = (&points[i - 1], &points[i]);
+        let direction = if prev.macd <= 0.0 && current.macd > 0.0 {
+            CrossDirection::Bullish
+        } else if prev.macd >= 0.0 && current.macd < 0.0 {
+            CrossDirection::Bearish
+        } else {
+            continue;
+        };
+        events.push(CrossEvent {
+            index: i,
+            ts: current.ts,
+            price: current.price,
+            direction,
+        });
+    }
+    events
+}
+
+/// Keep MACD crosses confirmed by momentum: the histogram must exceed `min_histogram`
+/// in the cross's direction at the cross or within `within` points after it. A cross
+/// too close to the end of `points` to be confirmed yet is dropped.
+/// `min_histogram <= 0` keeps everything.
+pub fn confirm_crosses(
+    events: Vec<CrossEvent>,
+    points: &[MACDPoint],
+    min_histogram: f64,
+    within: usize,
+) -> Vec<CrossEvent> {
+    if min_histogram <= 0.0 {
+        return events;
+    }
+    events
+        .into_iter()
+        .filter(|event| {
+            let end = (event.index + within + 1).min(points.len());
+            points[event.index..end]
+                .iter()
+                .any(|p| match event.direction {
+                    CrossDirection::Bullish => p.macd > min_histogram,
+                    CrossDirection::Bearish => p.macd < -min_histogram,
+                })
+        })
+        .collect()
+}
+
+/// Zero-line crossings of the DIF line (`ema_short - ema_long`), i.e. the short EMA
+/// crossing the long one: a slower trend-change signal. Crossings within the first
+/// `warmup` points are ignored.
+pub fn count_dif_zero_crossings(points: &[MACDPoint], warmup: usize) -> usize {
+    count_zero_crossings(points.iter().map(|p| p.dif), warmup)
+}
+
+/// Zero-line crossings of the histogram (`macd = 2 * (dif - dea)`), i.e. DIF crossing
+/// its DEA signal line: the faster momentum signal that `macd_cross_events` reports,
+/// usually firing well before DIF itself changes sign.
+pub fn count_histogram_zero_crossings(points: &[MACDPoint], warmup: usize) -> usize {
+    count_zero_crossings(points.iter().map(|p| p.macd), warmup)
+}
+
+/// Sign changes in `values` (touching zero and moving on counts once), from index
+/// `warmup` on.
+fn count_zero_crossings(values: impl Iterator<Item = f64>, warmup: usize) -> usize {
+    let mut count = 0;
+    let mut prev: Option<f64> = None;
+    for (i, value) in values.enumerate() {
+        if let Some(prev) = prev
+            && i >= warmup
+            && ((prev <= 0.0 && value > 0.0) || (prev >= 0.0 && value < 0.0))
+        {
+            count += 1;
+        }
+        prev = Some(value);
+    }
+    count
+}
+
+/// Drop events that repeat the direction of the last kept event of that direction
+/// within `cooldown_ms`, so choppy action does not emit a burst of identical signals.
+/// Opposite-direction events are never suppressed. `cooldown_ms <= 0` keeps everything.
+pub fn apply_cooldown(events: Vec<CrossEvent>, cooldown_ms: i64) -> Vec<CrossEvent> {
+    if cooldown_ms <= 0 {
+        return events;
+    }
+    let (mut last_bullish, mut last_bearish): (Option<TsMillis>, Option<TsMillis>) = (None, None);
+    events
+        .into_iter()
+        .filter(|event| {
+            let last = match event.direction {
+                CrossDirection::Bullish => &mut last_bullish,
+                CrossDirection::Bearish => &mut last_bearish,
+            };
+            if last.is_some_and(|ts| event.ts - ts < cooldown_ms) {
+                return false;
+            }
+            *last = Some(event.ts);
+            true
+        })
+        .collect()
+}
+
+/// Keep events that fall inside one of `windows` of a trading session, dropping those
+/// between them (mid-session, lunch, overnight). No windows keeps everything.
+pub fn apply_session_windows(
+    events: Vec<CrossEvent>,
+    calendar: &TradingCalendar,
+    windows: &[SessionWindow],
+) -> Vec<CrossEvent> {
+    if windows.is_empty() {
+        return events;
+    }
+    events
+        .into_iter()
+        .filter(|event| {
+            event
+                .ts
+                .to_datetime()
+                .is_some_and(|at| calendar.in_session_window(at, windows))
+        })
+        .collect()
+}
+
+/// Which crosses a caller wants: `golden` (bullish), `death` (bearish) or `all`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossKind {
+    Golden,
+    Death,
+    #[default]
+    All,
+}
+
+impl CrossKind {
+    fn matches(self, direction: CrossDirection) -> bool {
+        match self {
+            CrossKind::Golden => direction == CrossDirection::Bullish,
+            CrossKind::Death => direction == CrossDirection::Bearish,
+            CrossKind::All => true,
+        }
+    }
+}
+
+/// The first `limit` events at or after `since` (epoch ms) of the given kind, in time
+/// order. Passing the last returned `ts + 1` as the next `since` pages through the rest.
+pub fn select_cross_events(
+    events: Vec<CrossEvent>,
+    since: Option<TsMillis>,
+    kind: CrossKind,
+    limit: usize,
+) -> Vec<CrossEvent> {
+    events
+        .into_iter()
+        .filter(|event| since.is_none_or(|since| event.ts >= since))
+        .filter(|event| kind.matches(event.direction))
+        .take(limit)
+        .collect()
+}
+
+/// Which crossover rule drives signal detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    /// MACD histogram crossing zero
+    #[default]
+    Macd,
+    /// short SMA crossing the long SMA
+    SmaCross,
+}
+
+impl std::str::FromStr for StrategyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "macd" => Ok(StrategyKind::Macd),
+            "sma_cross" | "sma" => Ok(StrategyKind::SmaCross),
+            _ => Err(format!("Invalid strategy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SMAPoint {
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
+    pub price: f64,
+    #[serde(with = "number_format::float")]
+    pub sma: f64,
+}
+
+/// Streaming simple moving average holding only the last `period` prices.
+#[derive(Debug)]
+pub struct SMACalc {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl SMACalc {
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        SMACalc {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        }
+    }
+
+    /// feed a price and get the mean of the window (or of all prices while it fills)
+    pub fn next(&mut self, price: f64) -> f64 {
+        self.sum += price;
+        self.window.push_back(price);
+        if self.window.len() > self.period
+            && let Some(oldest) = self.window.pop_front()
+        {
+            self.sum -= oldest;
+        }
+        self.sum / self.window.len() as f64
+    }
+}
+
+/// Simple moving average over `period` points, one output per input. Until the window
+/// fills the value is the mean of the points seen so far, so like the EMA seed the
+/// first `period - 1` values are unreliable and callers should apply a warmup.
+pub fn compute_sma_series(points: &[(TsMillis, f64)], period: usize) -> Vec<SMAPoint> {
+    let mut sma = SMACalc::new(period);
+    points
+        .iter()
+        .map(|(ts, price)| SMAPoint {
+            ts: *ts,
+            price: *price,
+            sma: sma.next(*price),
+        })
+        .collect()
+}
+
+/// Golden (bullish) and death (bearish) crosses of `short_sma` over `long_sma`.
+/// Both series must come from the same input; crosses in the first `warmup`
+/// points are skipped.
+pub fn sma_cross_events(
+    short_sma: &[SMAPoint],
+    long_sma: &[SMAPoint],
+    warmup: usize,
+) -> Vec<CrossEvent> {
+    let n = short_sma.len().min(long_sma.len());
+    let mut events = Vec::new();
+    for i in warmup.max(1)..n {
+        let prev = short_sma[i - 1].sma - long_sma[i - 1].sma;
+        let current = short_sma[i].sma - long_sma[i].sma;
+        let direction = if prev <= 0.0 && current > 0.0 {
+            CrossDirection::Bullish
+        } else if prev >= 0.0 && current < 0.0 {
+            CrossDirection::Bearish
+        } else {
+            continue;
+        };
+        events.push(CrossEvent {
+            index: i,
+            ts: short_sma[i].ts,
+            price: short_sma[i].price,
+            direction,
+        });
+    }
+    events
+}
+
+/// Which daily price limit a bar touched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LimitKind {
+    Up,
+    Down,
+}
+
+/// Bars trading at/above the upper or at/below the lower daily limit. Limit prices are
+/// `prev_close * (1 ± limit_pct / 100)` rounded to the cent as the exchanges do, so a
+/// `limit_pct` of 10.0 means ±10%. A bar touching both limits yields two entries.
+pub fn detect_price_limits(
+    bars: &[Bar],
+    prev_close: f64,
+    limit_pct: f64,
+) -> Vec<(TsMillis, LimitKind)> {
+    if prev_close <= 0.0 || limit_pct <= 0.0 {
+        return Vec::new();
+    }
+    let round_cent = |p: f64| (p * 100.0).round() / 100.0;
+    let upper = round_cent(prev_close * (1.0 + limit_pct / 100.0));
+    let lower = round_cent(prev_close * (1.0 - limit_pct / 100.0));
+    // tolerate float noise on prices that are quoted at exactly the limit
+    const EPS: f64 = 1e-6;
+
+    let mut events = Vec::new();
+    for bar in bars {
+        if bar.high >= upper - EPS {
+            events.push((bar.ts, LimitKind::Up));
+        }
+        if bar.low <= lower + EPS {
+            events.push((bar.ts, LimitKind::Down));
+        }
+    }
+    events
+}
+
+/// What a tick's "volume" means for the volume-weighted indicators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VolumeBasis {
+    /// number of shares traded
+    #[default]
+    Shares,
+    /// currency traded; ticks without a reported amount fall back to `price * vol`
+    Turnover,
+}
+
+impl VolumeBasis {
+    pub fn weight(self, tick: &Tick) -> f64 {
+        match self {
+            VolumeBasis::Shares => tick.vol,
+            VolumeBasis::Turnover => tick.amount.unwrap_or(tick.price * tick.vol),
+        }
+    }
+}
+
+/// Streaming volume-weighted average price.
+#[derive(Debug)]
+pub struct VWAPCalc {
+    basis: VolumeBasis,
+    weighted_price: f64,
+    weight: f64,
+}
+
+impl VWAPCalc {
+    pub fn new(basis: VolumeBasis) -> Self {
+        VWAPCalc {
+            basis,
+            weighted_price: 0.0,
+            weight: 0.0,
+        }
+    }
+
+    /// feed a tick and get the VWAP so far, `None` while the total weight is zero
+    pub fn next(&mut self, tick: &Tick) -> Option<f64> {
+        let weight = self.basis.weight(tick);
+        self.weighted_price += tick.price * weight;
+        self.weight += weight;
+        (self.weight > 0.0).then(|| self.weighted_price / self.weight)
+    }
+}
+
+/// Volume-weighted average price over `ticks`; `None` when the total weight is zero.
+pub fn compute_vwap(ticks: &[Tick], basis: VolumeBasis) -> Option<f64> {
+    let mut vwap = VWAPCalc::new(basis);
+    ticks.iter().fold(None, |_, t| vwap.next(t))
+}
+
+/// Streaming on-balance volume, see `compute_obv`.
+#[derive(Debug)]
+pub struct OBVCalc {
+    basis: VolumeBasis,
+    prev_price: Option<f64>,
+    obv: f64,
+}
+
+impl OBVCalc {
+    pub fn new(basis: VolumeBasis) -> Self {
+        OBVCalc {
+            basis,
+            prev_price: None,
+            obv: 0.0,
+        }
+    }
+
+    /// feed a tick and get the running total
+    pub fn next(&mut self, tick: &Tick) -> f64 {
+        if let Some(prev) = self.prev_price {
+            if tick.price > prev {
+                self.obv += self.basis.weight(tick);
+            } else if tick.price < prev {
+                self.obv -= self.basis.weight(tick);
+            }
+        }
+        self.prev_price = Some(tick.price);
+        self.obv
+    }
+}
+
+/// On-balance volume: running total that adds a tick's weight when the price rose
+/// from the previous tick and subtracts it when it fell. The first tick starts at 0.
+pub fn compute_obv(ticks: &[Tick], basis: VolumeBasis) -> Vec<(TsMillis, f64)> {
+    let mut obv = OBVCalc::new(basis);
+    ticks.iter().map(|t| (t.ts, obv.next(t))).collect()
+}
+
+/// Score the disagreement between price direction and MACD direction over a window.
+/// Positive means bearish divergence (price rising while MACD falls), negative means
+/// bullish divergence (price falling while MACD rises), 0 means no divergence.
+/// The magnitude is the negated correlation of the two series, so it lies in [-1, 1].
+#[allow(dead_code)] // not wired into the analysis yet
+pub fn divergence_score(price: &[f64], macd: &[f64]) -> f64 {
+    let n = price.len().min(macd.len());
+    if n < 2 {
+        return 0.0;
+    }
+    let (price, macd) = (&price[..n], &macd[..n]);
+
+    let price_dir = (price[n - 1] - price[0]).signum();
+    let macd_dir = (macd[n - 1] - macd[0]).signum();
+    if price_dir == macd_dir || price[n - 1] == price[0] || macd[n - 1] == macd[0] {
+        return 0.0;
+    }
+
+    match pearson(price, macd) {
+        Some(corr) => price_dir * (-corr).max(0.0),
+        None => 0.0,
+    }
+}
+
+/// Pearson correlation of two equally long series; `None` with fewer than two values
+/// or when either series is constant.
+pub fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return None;
+    }
+    let (x, y) = (&x[..n], &y[..n]);
+
+    let mean_x = x.iter().sum::<f64>() / n as f64;
+    let mean_y = y.iter().sum::<f64>() / n as f64;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (a, b) in x.iter().zip(y) {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x).powi(2);
+        var_y += (b - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x.sqrt() * var_y.sqrt()))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReturnCorrelation {
+    /// `None` when there are fewer than two returns or either side never moved
+    #[serde(with = "number_format::option_float")]
+    pub correlation: Option<f64>,
+    /// grid points where both series have a price
+    pub points: usize,
+    /// first grid point where both have a price, i.e. the later of the two starts
+    pub anchor: Option<TsMillis>,
+}
+
+/// Correlation of the per-interval returns of two bar series. Both are put on the
+/// union of their bar timestamps, each forward-filling its last close over bars it is
+/// missing, starting where both have traded; returns are taken between consecutive
+/// grid points.
+pub fn return_correlation(a: &[Bar], b: &[Bar]) -> ReturnCorrelation {
+    let (mut i, mut j) = (0, 0);
+    let (mut last_a, mut last_b) = (None, None);
+    let mut aligned: Vec<(f64, f64)> = Vec::with_capacity(a.len().max(b.len()));
+    let mut anchor = None;
+    while i < a.len() || j < b.len() {
+        let ts = match (a.get(i), b.get(j)) {
+            (Some(x), Some(y)) => x.ts.min(y.ts),
+            (Some(x), None) => x.ts,
+            (None, Some(y)) => y.ts,
+            (None, None) => break,
+        };
+        if let Some(x) = a.get(i).filter(|x| x.ts == ts) {
+            last_a = Some(x.close);
+            i += 1;
+        }
+        if let Some(y) = b.get(j).filter(|y| y.ts == ts) {
+            last_b = Some(y.close);
+            j += 1;
+        }
+        if let (Some(pa), Some(pb)) = (last_a, last_b) {
+            anchor.get_or_insert(ts);
+            aligned.push((pa, pb));
+        }
+    }
+
+    let (returns_a, returns_b): (Vec<f64>, Vec<f64>) = aligned
+        .windows(2)
+        .filter(|w| w[0].0 != 0.0 && w[0].1 != 0.0)
+        .map(|w| (w[1].0 / w[0].0 - 1.0, w[1].1 / w[0].1 - 1.0))
+        .unzip();
+    ReturnCorrelation {
+        correlation: pearson(&returns_a, &returns_b),
+        points: aligned.len(),
+        anchor,
+    }
+}
+
+/// Closes of several symbols rebased to 100 at one timestamp they all share.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rebased {
+    /// the latest first bar among the symbols with data, so that every series has a
+    /// price there; `None` when no symbol has any bars
+    pub anchor: Option<TsMillis>,
+    /// by symbol, `(anchor, 100)` then `(ts, 100 * close / base)` for each later bar
+    #[serde(serialize_with = "number_format::serialize_series_map")]
+    pub series: BTreeMap<String, Vec<(TsMillis, f64)>>,
+    /// symbols with no bar at or after the anchor, or a zero price to rebase from
+    pub missing: Vec<String>,
+}
+
+/// Rebase each symbol's (time-ordered) bars from the shared anchor. A symbol with no bar
+/// exactly at the anchor uses its last close before it as the base.
+pub fn rebase_to_common_anchor(bars: &[(String, Vec<Bar>)]) -> Rebased {
+    let anchor = bars
+        .iter()
+        .filter_map(|(_, bars)| bars.first())
+        .map(|b| b.ts)
+        .max();
+    let mut series = BTreeMap::new();
+    let mut missing = Vec::new();
+    for (symbol, bars) in bars {
+        let base = anchor.and_then(|anchor| {
+            if bars.last()?.ts < anchor {
+                return None;
+            }
+            // no series starts after the anchor, so some bar is at or before it
+            let at = bars.partition_point(|b| b.ts <= anchor);
+            Some(bars[at - 1].close).filter(|close| *close != 0.0)
+        });
+        let (Some(anchor), Some(base)) = (anchor, base) else {
+            missing.push(symbol.clone());
+            continue;
+        };
+        let mut points = vec![(anchor, 100.0)];
+        points.extend(
+            bars.iter()
+                .filter(|b| b.ts > anchor)
+                .map(|b| (b.ts, 100.0 * b.close / base)),
+        );
+        series.insert(symbol.clone(), points);
+    }
+    Rebased {
+        anchor,
+        series,
+        missing,
+    }
+}
@@ -1,23 +1,57 @@
 // src/main.rs
+#[allow(dead_code)] // daily-bar signal path, not wired into the server yet
+mod analysis;
 mod app;
+mod backfill;
+mod backtest;
 mod config;
+#[allow(dead_code)] // depth/trades endpoints are not wired yet
+mod data_fetch;
+#[allow(dead_code)]
+mod eastmoney;
 mod error;
+#[allow(dead_code)] // order routing is not wired into the server yet
+mod executor;
+mod feed;
 mod indicators;
+mod models;
+mod notify;
+#[cfg(feature = "parquet")]
+#[allow(dead_code)] // research tooling, not wired into the server
+mod parquet_store;
+mod poller;
+mod report;
+mod sim;
 mod storage;
+mod tasks;
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
+mod utils;
 mod web;
 
 use anyhow::Result;
 use app::TradingApp;
-use chrono::{NaiveTime, Utc};
+use chrono::NaiveDate;
 use clap::Parser;
 use config::AppConfig;
-use rand::Rng;
+use data_fetch::DataFetcher;
+use feed::TickFeed;
+use models::RunMode;
+use poller::PollGate;
+use std::path::PathBuf;
 use std::sync::Arc;
-use storage::{Storage, Tick};
+use storage::Storage;
+use tasks::TaskRegistry;
 use tokio::time::{Duration, sleep};
+use utils::time::market_today;
 
 #[derive(Parser, Debug)]
 struct CliConfig {
+    /// load this config file instead of config/default.toml + config/{RUN_MODE}.toml
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     #[arg(long, help = "Override default symbol")]
     symbol: Option<String>,
 
@@ -36,6 +70,22 @@ struct CliConfig {
     /// generate a simulated full trading day into sqlite for testing (yesterday)
     #[arg(long, default_value_t = false)]
     gen_sim: bool,
+
+    /// backfill historical klines for this symbol into sqlite, then exit
+    #[arg(long, requires = "from")]
+    backfill: Option<String>,
+
+    /// first date to backfill (YYYY-MM-DD)
+    #[arg(long)]
+    from: Option<NaiveDate>,
+
+    /// last date to backfill (YYYY-MM-DD), defaults to today
+    #[arg(long)]
+    to: Option<NaiveDate>,
+
+    /// kline period to backfill (1min, 5min, ..., day)
+    #[arg(long, default_value = "1min")]
+    period: String,
 }
 
 #[tokio::main]
@@ -47,7 +97,10 @@ async fn main() -> Result<()> {
     let cli_config = CliConfig::parse();
 
     // Load application configuration
-    let mut app_config = AppConfig::new()?;
+    let mut app_config = match &cli_config.config {
+        Some(path) => AppConfig::from_file(path)?,
+        None => AppConfig::new()?,
+    };
 
     // Override config with CLI values if provided
     if let Some(symbol) = cli_config.symbol {
@@ -72,30 +125,144 @@ async fn main() -> Result<()> {
         app_config.version,
         app_config.environment
     );
+    utils::number_format::init(app_config.server.number_format);
+
+    // a band narrower than a limit-down to limit-up swing would drop real prints
+    let max_swing_pct = app_config.trading.price_limits.max_swing_pct();
+    let outlier_pct = app_config.database.outlier_pct.map(|pct| {
+        if pct < max_swing_pct {
+            tracing::warn!(
+                "database.outlier_pct {} is narrower than the {:.0}% daily swing, using that",
+                pct,
+                max_swing_pct
+            );
+        }
+        pct.max(max_swing_pct)
+    });
+
+    let storage = Arc::new(
+        Storage::new(
+            &app_config.database.sqlite_path,
+            &app_config.database.redis_url,
+        )?
+        .with_sqlite_workers(app_config.database.worker_threads)
+        .with_dedupe_ticks(app_config.database.dedupe_ticks)
+        .with_outlier_guard(outlier_pct, app_config.database.outlier_action)
+        .with_on_duplicate(app_config.database.on_duplicate)
+        .with_bar_timestamp(app_config.trading.bar_timestamp)
+        .with_calendar(app_config.trading.calendar())
+        .with_run_mode(app_config.database.run_mode),
+    );
 
-    let storage = Arc::new(Storage::new(
-        &app_config.database.sqlite_path,
-        &app_config.database.redis_url,
-    )?);
+    if let (Some(symbol), Some(from)) = (&cli_config.backfill, cli_config.from) {
+        let to = cli_config
+            .to
+            .unwrap_or_else(|| market_today(app_config.trading.timezone));
+        let fetcher = DataFetcher::new(Arc::new(app_config.clone()));
+        let inserted = backfill::backfill_klines(
+            &fetcher,
+            &storage,
+            &app_config.trading.tick_sizes,
+            symbol,
+            from,
+            to,
+            &cli_config.period,
+        )
+        .await?;
+        tracing::info!("Backfill for {} stored {} new ticks", symbol, inserted);
+        return Ok(());
+    }
 
     let trading_app = Arc::new(TradingApp::new(
         storage.clone(),
         Arc::new(app_config.clone()),
     ));
 
-    // Optionally populate one full day of simulated minute data (useful on non-trading days)
+    // Optionally populate one full day of simulated minute data (useful on non-trading
+    // days). Asking for it is explicit, so the storage accepts sim writes for the
+    // generation even when the service runs in real mode.
     if cli_config.gen_sim {
-        generate_and_store_mock_day(&storage, &app_config.trading.default_symbol).await?;
-        tracing::info!(
-            "Generated simulated day for {}",
-            app_config.trading.default_symbol
-        );
+        let yesterday = market_today(app_config.trading.timezone) - chrono::Duration::days(1);
+        let mode = storage.run_mode();
+        let previous = std::mem::replace(&mut *mode.write().await, RunMode::Sim);
+        let generated = sim::generate_mock_day(
+            &storage,
+            &app_config.trading.default_symbol,
+            yesterday,
+            &app_config.trading,
+        )
+        .await;
+        *mode.write().await = previous;
+        if generated? == 0 {
+            tracing::warn!(
+                "--gen-sim stored no ticks for {} on {}; they are all stored already",
+                app_config.trading.default_symbol,
+                yesterday
+            );
+        }
+    }
+
+    // Warm the indicator cache without holding up the listener
+    if app_config.server.prime_cache {
+        let app = trading_app.clone();
+        let symbols = app_config.trading.watchlist_or_default();
+        tokio::spawn(async move { app.prime_indicator_cache(&symbols).await });
+    }
+
+    let fetcher = Arc::new(DataFetcher::new(Arc::new(app_config.clone())));
+    let feed = TickFeed::with_capacity(app_config.server.stream_buffer);
+
+    let notifiers = notify::notifiers_from_config(&app_config.alerts, app_config.trading.timezone)?;
+    if !notifiers.is_empty() {
+        let engine = notify::AlertEngine::new(notifiers, &app_config.trading);
+        tokio::spawn(engine.run(feed.subscribe()));
+    }
+
+    // registered even when polling is off, so it can be started at runtime
+    let tasks = TaskRegistry::new();
+    let gate = if app_config.trading.poll_outside_sessions {
+        PollGate::always()
+    } else {
+        PollGate::sessions(app_config.trading.calendar())
+    };
+    let (source, sink, live, schedule, tick_sizes) = (
+        fetcher.clone(),
+        storage.clone(),
+        feed.clone(),
+        app_config.trading.poll_schedule(),
+        app_config.trading.tick_sizes.clone(),
+    );
+    tasks
+        .register("ingestion", move |cancel| {
+            let pollers = poller::spawn_pollers(
+                source.clone(),
+                sink.clone(),
+                live.clone(),
+                schedule.clone(),
+                tick_sizes.clone(),
+                gate.clone(),
+                cancel,
+            );
+            async move {
+                futures::future::join_all(pollers).await;
+            }
+        })
+        .await;
+    if app_config.trading.poll_enabled {
+        tasks.start("ingestion").await;
     }
 
     // Start web server
-    web::start_web(trading_app, &app_config.server.host, app_config.server.port)
-        .await
-        .unwrap();
+    web::start_web(
+        trading_app,
+        fetcher,
+        feed,
+        tasks,
+        &app_config.server.host,
+        app_config.server.port,
+    )
+    .await
+    .unwrap();
 
     // Keep main alive. In production your strategy loop would run here.
     let server_address = app_config.get_server_address();
@@ -105,49 +272,3 @@ async fn main() -> Result<()> {
         sleep(Duration::from_secs(60)).await;
     }
 }
-
-/// generate a mock full trading day minute-level data (09:30-11:30 and 13:00-15:00) for yesterday
-async fn generate_and_store_mock_day(storage: &Arc<Storage>, symbol: &str) -> Result<()> {
-    // pick date = yesterday
-    let today = chrono::Local::now().date_naive();
-    let date = today - chrono::Duration::days(1);
-
-    // trading sessions:
-    let morning_start = NaiveTime::from_hms_opt(9, 30, 0).unwrap();
-    let morning_end = NaiveTime::from_hms_opt(11, 30, 0).unwrap();
-    let afternoon_start = NaiveTime::from_hms_opt(13, 0, 0).unwrap();
-    let afternoon_end = NaiveTime::from_hms_opt(15, 0, 0).unwrap();
-
-    let mut rng = rand::thread_rng();
-    // base price
-    let mut price = 10.0 + rng.gen_range(-0.5..0.5);
-
-    let mut push = |dt: chrono::NaiveDateTime| -> Tick {
-        // random walk small moves
-        price = f64::max(price + rng.gen_range(-0.2..0.2), 0.01);
-        Tick {
-            ts: chrono::DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc).timestamp_millis(),
-            symbol: symbol.to_string(),
-            price,
-            vol: (rng.gen_range(100..2000)) as f64,
-        }
-    };
-
-    // morning minutes
-    let mut t = chrono::NaiveDateTime::new(date, morning_start);
-    while t.time() <= morning_end {
-        let tick = push(t);
-        storage.save_tick(&tick).await?;
-        t = t + chrono::Duration::minutes(1);
-    }
-
-    // afternoon minutes
-    let mut t2 = chrono::NaiveDateTime::new(date, afternoon_start);
-    while t2.time() <= afternoon_end {
-        let tick = push(t2);
-        storage.save_tick(&tick).await?;
-        t2 = t2 + chrono::Duration::minutes(1);
-    }
-
-    Ok(())
-}
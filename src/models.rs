@@ -0,0 +1,126 @@
+// src/models.rs
+use crate::utils::number_format;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Whether the service serves simulated or live market data. Tick writes from the
+/// other mode are dropped by `Storage::save_tick_as`.
+#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "lowercase"))]
+pub enum RunMode {
+    Sim,
+    Real,
+}
+
+impl std::fmt::Display for RunMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunMode::Sim => write!(f, "sim"),
+            RunMode::Real => write!(f, "real"),
+        }
+    }
+}
+
+impl std::str::FromStr for RunMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sim" => Ok(RunMode::Sim),
+            "real" => Ok(RunMode::Real),
+            _ => Err(format!("Invalid run mode: {}", s)),
+        }
+    }
+}
+
+/// Real-time quote as returned by the upstream data sources.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    pub symbol: String,
+    pub timestamp: i64,
+    #[serde(with = "number_format::decimal")]
+    pub price: Decimal,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub open: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub high: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub low: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub prev_close: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub volume: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub amount: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub change: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub change_pct: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub bid_price: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub ask_price: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub bid_volume: Option<Decimal>,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub ask_volume: Option<Decimal>,
+    /// upstream that produced the quote (`eastmoney`, `baidu`, `sina` or `simulated`)
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// One candlestick of historical K-line data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Kline {
+    pub symbol: String,
+    pub timestamp: i64,
+    #[serde(with = "number_format::decimal")]
+    pub open: Decimal,
+    #[serde(with = "number_format::decimal")]
+    pub high: Decimal,
+    #[serde(with = "number_format::decimal")]
+    pub low: Decimal,
+    #[serde(with = "number_format::decimal")]
+    pub close: Decimal,
+    #[serde(with = "number_format::decimal")]
+    pub volume: Decimal,
+    #[serde(default, with = "number_format::option_decimal")]
+    pub amount: Option<Decimal>,
+    pub period: String,
+}
+
+/// Order book snapshot, levels are (price, volume) best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketDepth {
+    pub symbol: String,
+    pub timestamp: i64,
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub trade_id: String,
+    pub symbol: String,
+    pub timestamp: i64,
+    pub price: Decimal,
+    pub volume: Decimal,
+    pub side: TradeSide,
+    pub trade_type: Option<String>,
+}
+
+/// Envelope for paginated list endpoints.
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// total number of items across all pages
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
@@ -0,0 +1,451 @@
+// src/notify.rs
+use crate::config::{AlertsConfig, SmtpConfig, SmtpTls, TradingConfig};
+use crate::feed::is_replayed;
+use crate::indicators::{
+    CrossDirection, CrossEvent, MACDCalc, MACDPoint, apply_cooldown, apply_session_windows,
+    confirm_crosses, macd_cross_events,
+};
+use crate::storage::Tick;
+use crate::utils::calendar::{SessionWindow, TradingCalendar};
+use crate::utils::http_client::HttpClient;
+use crate::utils::number_format;
+use crate::utils::time::TsMillis;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use futures::future::join_all;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, warn};
+
+/// A signal worth telling someone about.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub symbol: String,
+    pub ts: TsMillis,
+    #[serde(with = "number_format::float")]
+    pub price: f64,
+    pub direction: CrossDirection,
+}
+
+impl Alert {
+    /// One-line human summary, e.g. `600733.SH bullish MACD cross at 10.52`.
+    pub fn summary(&self) -> String {
+        let direction = match self.direction {
+            CrossDirection::Bullish => "bullish",
+            CrossDirection::Bearish => "bearish",
+        };
+        format!("{} {} MACD cross at {}", self.symbol, direction, self.price)
+    }
+
+    /// The alert's time in market time, e.g. `2024-03-01 09:41:00 CST`.
+    pub fn market_time(&self, tz: Tz) -> String {
+        self.ts
+            .to_datetime()
+            .map(|dt| {
+                dt.with_timezone(&tz)
+                    .format("%Y-%m-%d %H:%M:%S %Z")
+                    .to_string()
+            })
+            .unwrap_or_else(|| self.ts.to_string())
+    }
+}
+
+/// A delivery channel for alerts.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// short name for logs
+    fn name(&self) -> &str;
+    async fn notify(&self, alert: &Alert) -> Result<()>;
+}
+
+/// POSTs the alert as JSON to an arbitrary URL.
+pub struct WebhookNotifier {
+    client: HttpClient,
+    url: String,
+    timeout: Duration,
+}
+
+impl WebhookNotifier {
+    pub fn new(client: HttpClient, url: String, timeout: Duration) -> Self {
+        Self {
+            client,
+            url,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(alert)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .context("Webhook delivery failed")?;
+        Ok(())
+    }
+}
+
+/// Posts formatted text to a Slack incoming-webhook URL.
+pub struct SlackNotifier {
+    client: HttpClient,
+    url: String,
+    timeout: Duration,
+    tz: Tz,
+}
+
+impl SlackNotifier {
+    pub fn new(client: HttpClient, url: String, timeout: Duration, tz: Tz) -> Self {
+        Self {
+            client,
+            url,
+            timeout,
+            tz,
+        }
+    }
+
+    /// Incoming-webhook body: bold symbol, direction emoji, price and market time.
+    pub fn payload(&self, alert: &Alert) -> serde_json::Value {
+        let emoji = match alert.direction {
+            CrossDirection::Bullish => ":chart_with_upwards_trend:",
+            CrossDirection::Bearish => ":chart_with_downwards_trend:",
+        };
+        let time = alert.market_time(self.tz);
+        let summary = alert.summary();
+        let rest = summary.strip_prefix(&alert.symbol).unwrap_or(&summary);
+        serde_json::json!({
+            "text": format!("{} *{}*{} ({})", emoji, alert.symbol, rest, time),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(&self.payload(alert))
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .context("Slack delivery failed")?;
+        Ok(())
+    }
+}
+
+/// Emails the alert through an SMTP relay, one message to all recipients.
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    tz: Tz,
+}
+
+impl EmailNotifier {
+    /// Fails on an unparsable mailbox or a relay host TLS cannot be set up for.
+    pub fn new(config: &SmtpConfig, timeout: Duration, tz: Tz) -> Result<Self> {
+        let builder = match config.tls {
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .context("Invalid SMTP relay")?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .context("Invalid SMTP relay")?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+        let mut builder = builder.port(config.port).timeout(Some(timeout));
+        match (&config.username, &config.password) {
+            (Some(user), Some(pass)) => {
+                builder = builder.credentials(Credentials::new(user.clone(), pass.clone()));
+            }
+            (None, None) => {}
+            _ => anyhow::bail!("SMTP username and password must be set together"),
+        }
+        let from = config
+            .from
+            .parse()
+            .with_context(|| format!("Invalid SMTP sender {:?}", config.from))?;
+        let to = config
+            .to
+            .iter()
+            .map(|addr| {
+                addr.parse()
+                    .with_context(|| format!("Invalid SMTP recipient {:?}", addr))
+            })
+            .collect::<Result<Vec<Mailbox>>>()?;
+        if to.is_empty() {
+            anyhow::bail!("SMTP alerts need at least one recipient");
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            to,
+            tz,
+        })
+    }
+
+    /// The alert as a plain-text message with its summary as the subject.
+    pub fn message(&self, alert: &Alert) -> Result<Message> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(alert.summary())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let body = format!("{} ({})\n", alert.summary(), alert.market_time(self.tz));
+        builder.body(body).context("Failed to build alert email")
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        self.transport
+            .send(self.message(alert)?)
+            .await
+            .context("Email delivery failed")?;
+        Ok(())
+    }
+}
+
+/// Notifiers enabled in `[alerts]`, in a fixed order (webhook, Slack, email). Fails
+/// on an invalid `[alerts.smtp]` section rather than dropping email silently.
+pub fn notifiers_from_config(config: &AlertsConfig, tz: Tz) -> Result<Vec<Box<dyn Notifier>>> {
+    let client = HttpClient::new();
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    if let Some(url) = &config.webhook_url {
+        notifiers.push(Box::new(WebhookNotifier::new(
+            client.clone(),
+            url.clone(),
+            timeout,
+        )));
+    }
+    if let Some(url) = &config.slack_webhook_url {
+        notifiers.push(Box::new(SlackNotifier::new(
+            client,
+            url.clone(),
+            timeout,
+            tz,
+        )));
+    }
+    if let Some(smtp) = &config.smtp {
+        notifiers.push(Box::new(EmailNotifier::new(smtp, timeout, tz)?));
+    }
+    Ok(notifiers)
+}
+
+/// Send `alert` through every notifier at once. A failing notifier is logged and does
+/// not hold up or cancel the others. Returns how many deliveries succeeded.
+pub async fn dispatch(notifiers: &[Box<dyn Notifier>], alert: &Alert) -> usize {
+    let results = join_all(notifiers.iter().map(|n| async move {
+        n.notify(alert)
+            .await
+            .inspect_err(|e| warn!("{} notifier failed for {}: {:#}", n.name(), alert.symbol, e))
+    }))
+    .await;
+    results.iter().filter(|r| r.is_ok()).count()
+}
+
+/// Alerts waiting for delivery; once this many are queued, new ones are dropped (and
+/// logged) rather than holding up the tick loop.
+const ALERT_QUEUE_CAPACITY: usize = 64;
+
+/// Raises an alert for every MACD histogram cross in the live ticks, one MACD per
+/// symbol, and sends it through the configured notifiers. Crosses go through the same
+/// rules as `TradingApp::get_market_analysis_with`: none in a symbol's first
+/// `macd_warmup` ticks or before it has `min_points`, each confirmed by `min_histogram`
+/// within `histogram_confirm_bars` (so the alert waits for the confirming tick), then
+/// masked to `signal_windows` and thinned by `cooldown_secs`.
+pub struct AlertEngine {
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
+    periods: (usize, usize, usize),
+    warmup: usize,
+    min_points: usize,
+    min_histogram: f64,
+    confirm_bars: usize,
+    calendar: TradingCalendar,
+    signal_windows: Vec<SessionWindow>,
+    cooldown_ms: i64,
+    symbols: HashMap<String, SymbolAlerts>,
+}
+
+struct SymbolAlerts {
+    macd: MACDCalc,
+    seen: usize,
+    /// the last `confirm_bars + 1` points, enough to confirm any pending cross
+    recent: VecDeque<MACDPoint>,
+    /// crosses still waiting for the histogram to confirm them, by tick index
+    pending: Vec<CrossEvent>,
+    last_bullish: Option<CrossEvent>,
+    last_bearish: Option<CrossEvent>,
+}
+
+impl AlertEngine {
+    pub fn new(notifiers: Vec<Box<dyn Notifier>>, trading: &TradingConfig) -> Self {
+        Self {
+            notifiers: Arc::new(notifiers),
+            periods: (trading.macd_short, trading.macd_long, trading.macd_signal),
+            warmup: trading.macd_warmup.unwrap_or(trading.macd_long),
+            min_points: trading.min_points.unwrap_or(trading.macd_long * 2),
+            min_histogram: trading.min_histogram,
+            confirm_bars: trading.histogram_confirm_bars,
+            calendar: trading.calendar(),
+            signal_windows: trading.signal_windows.clone(),
+            cooldown_ms: trading.cooldown_secs as i64 * 1000,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Advance `tick.symbol`'s MACD; the alert this tick raises, if any. Replayed ticks
+    /// are historical and neither advance the MACD nor alert.
+    pub fn on_tick(&mut self, tick: &Tick) -> Option<Alert> {
+        if is_replayed(tick) {
+            return None;
+        }
+        let (short, long, signal) = self.periods;
+        let keep = self.confirm_bars + 1;
+        let state = self
+            .symbols
+            .entry(tick.symbol.clone())
+            .or_insert_with(|| SymbolAlerts {
+                macd: MACDCalc::new(short, long, signal),
+                seen: 0,
+                recent: VecDeque::with_capacity(keep + 1),
+                pending: Vec::new(),
+                last_bullish: None,
+                last_bearish: None,
+            });
+        let point = state.macd.next_point(tick.ts, tick.price);
+        let index = state.seen;
+        state.seen += 1;
+        state.recent.push_back(point);
+        if state.recent.len() > keep.max(2) {
+            state.recent.pop_front();
+        }
+        let recent = state.recent.make_contiguous();
+        let first = index + 1 - recent.len();
+        if index >= self.warmup.max(1)
+            && let Some(event) = macd_cross_events(&recent[recent.len() - 2..], 1).pop()
+        {
+            state.pending.push(CrossEvent { index, ..event });
+        }
+
+        // decide pending crosses on the points seen since each of them
+        let mut confirmed = Vec::new();
+        state.pending.retain(|event| {
+            let local = CrossEvent {
+                index: event.index - first,
+                ..event.clone()
+            };
+            let kept = confirm_crosses(vec![local], recent, self.min_histogram, self.confirm_bars);
+            if !kept.is_empty() {
+                confirmed.push(event.clone());
+                return false;
+            }
+            // still inside its confirmation window
+            index < event.index + self.confirm_bars
+        });
+        // masked first, so a suppressed cross does not start a cooldown
+        let event = apply_session_windows(confirmed, &self.calendar, &self.signal_windows).pop()?;
+        let last = match event.direction {
+            CrossDirection::Bullish => &mut state.last_bullish,
+            CrossDirection::Bearish => &mut state.last_bearish,
+        };
+        let previous: Vec<CrossEvent> = last.iter().cloned().collect();
+        let mut thinned = apply_cooldown(
+            previous.into_iter().chain([event.clone()]).collect(),
+            self.cooldown_ms,
+        );
+        if thinned.pop().is_none_or(|kept| kept.index != event.index) {
+            debug!(
+                "{} {:?} cross within cooldown, not alerted",
+                tick.symbol, event.direction
+            );
+            return None;
+        }
+        // a cross before min_points still starts a cooldown, as it does in the analysis
+        *last = Some(event.clone());
+        if state.seen < self.min_points {
+            debug!(
+                "{} has {} of {} ticks, cross not alerted",
+                tick.symbol, state.seen, self.min_points
+            );
+            return None;
+        }
+        Some(Alert {
+            symbol: tick.symbol.clone(),
+            ts: event.ts,
+            price: event.price,
+            direction: event.direction,
+        })
+    }
+
+    /// Dispatch the alert `tick` raises, if any, and wait for it; returns how many
+    /// deliveries succeeded.
+    #[cfg(test)]
+    pub async fn handle(&mut self, tick: &Tick) -> usize {
+        match self.on_tick(tick) {
+            Some(alert) => dispatch(&self.notifiers, &alert).await,
+            None => 0,
+        }
+    }
+
+    /// Follow `ticks` until the feed closes. Alerts are delivered by a separate task
+    /// through a bounded queue, so a slow notifier never holds up the MACD. Ticks
+    /// missed by falling behind the feed are logged and left out of the MACD.
+    pub async fn run(mut self, mut ticks: broadcast::Receiver<Tick>) {
+        let (queue, mut alerts) = mpsc::channel::<Alert>(ALERT_QUEUE_CAPACITY);
+        let notifiers = self.notifiers.clone();
+        let delivery = tokio::spawn(async move {
+            while let Some(alert) = alerts.recv().await {
+                dispatch(&notifiers, &alert).await;
+            }
+        });
+        loop {
+            match ticks.recv().await {
+                Ok(tick) => {
+                    if let Some(alert) = self.on_tick(&tick)
+                        && let Err(e) = queue.try_send(alert)
+                    {
+                        warn!("Alert queue full, dropping {}", e.into_inner().summary());
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(
+                        "Alert engine fell behind the live feed, skipped {} ticks",
+                        missed
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        // let the queued alerts go out
+        drop(queue);
+        let _ = delivery.await;
+    }
+}
@@ -0,0 +1,184 @@
+// src/parquet_store.rs
+use crate::storage::{Tick, TickStore};
+use crate::utils::time::TsMillis;
+use anyhow::{Context, Result, bail};
+use arrow_array::{Array, Float64Array, Int64Array, RecordBatch, StringArray};
+use bytes::Bytes;
+use memmap2::Mmap;
+use parquet::arrow::ProjectionMask;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::statistics::Statistics;
+use std::fs::File;
+use std::path::Path;
+use tracing::{debug, instrument};
+
+/// Columns read from the file; anything else in it is never decoded.
+const COLUMNS: [&str; 4] = ["symbol", "ts", "price", "vol"];
+
+/// Read-only tick store over a Parquet file with `symbol` (UTF-8), `ts` (int64 epoch
+/// ms), `price` and `vol` (float64) columns, for analytical scans over long research
+/// datasets. The file is memory-mapped once; a query decodes only those columns and
+/// skips row groups whose `symbol`/`ts` statistics cannot match, so files sorted by
+/// symbol then time scan fastest.
+#[derive(Debug, Clone)]
+pub struct ParquetStore {
+    data: Bytes,
+}
+
+impl ParquetStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open Parquet file {}", path.display()))?;
+        // SAFETY: the file is treated as immutable research data; truncating it while
+        // mapped is undefined behaviour, as with any memory-mapped reader
+        let mmap = unsafe { Mmap::map(&file) }
+            .with_context(|| format!("Failed to map Parquet file {}", path.display()))?;
+        Ok(Self {
+            data: Bytes::from_owner(mmap),
+        })
+    }
+
+    fn scan(&self, symbol: &str, start_ts: i64, end_ts: i64) -> Result<Vec<Tick>> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(self.data.clone())?;
+        let parquet_schema = builder.parquet_schema();
+        let mut indices = Vec::with_capacity(COLUMNS.len());
+        for name in COLUMNS {
+            match parquet_schema
+                .columns()
+                .iter()
+                .position(|c| c.name() == name)
+            {
+                Some(i) => indices.push(i),
+                None => bail!("Parquet file has no `{}` column", name),
+            }
+        }
+        let (symbol_col, ts_col) = (indices[0], indices[1]);
+        let mask = ProjectionMask::leaves(parquet_schema, indices);
+
+        let row_groups: Vec<usize> = builder
+            .metadata()
+            .row_groups()
+            .iter()
+            .enumerate()
+            .filter(|(_, rg)| may_contain(rg, symbol_col, ts_col, symbol, start_ts, end_ts))
+            .map(|(i, _)| i)
+            .collect();
+        debug!(
+            "Scanning {} of {} row groups for {}",
+            row_groups.len(),
+            builder.metadata().num_row_groups(),
+            symbol
+        );
+
+        let reader = builder
+            .with_projection(mask)
+            .with_row_groups(row_groups)
+            .build()?;
+        let mut ticks = Vec::new();
+        for batch in reader {
+            collect_ticks(&batch?, symbol, start_ts, end_ts, &mut ticks)?;
+        }
+        ticks.sort_by_key(|t| t.ts);
+        Ok(ticks)
+    }
+}
+
+impl TickStore for ParquetStore {
+    #[instrument(skip(self))]
+    async fn get_ticks_range(&self, symbol: &str, start_ts: i64, end_ts: i64) -> Result<Vec<Tick>> {
+        let store = self.clone();
+        let symbol = symbol.to_string();
+        tokio::task::spawn_blocking(move || store.scan(&symbol, start_ts, end_ts))
+            .await?
+            .context("Failed to read ticks from Parquet")
+    }
+}
+
+/// False only when the row group's statistics rule out `symbol` or `[start_ts, end_ts)`;
+/// a group without statistics is always read.
+fn may_contain(
+    rg: &RowGroupMetaData,
+    symbol_col: usize,
+    ts_col: usize,
+    symbol: &str,
+    start_ts: i64,
+    end_ts: i64,
+) -> bool {
+    let symbol_fits = match rg.column(symbol_col).statistics() {
+        Some(Statistics::ByteArray(stats)) => match (stats.min_opt(), stats.max_opt()) {
+            (Some(min), Some(max)) => match (min.as_utf8(), max.as_utf8()) {
+                (Ok(min), Ok(max)) => min <= symbol && symbol <= max,
+                _ => true,
+            },
+            _ => true,
+        },
+        _ => true,
+    };
+    let ts_fits = match rg.column(ts_col).statistics() {
+        Some(Statistics::Int64(stats)) => match (stats.min_opt(), stats.max_opt()) {
+            (Some(&min), Some(&max)) => min < end_ts && max >= start_ts,
+            _ => true,
+        },
+        _ => true,
+    };
+    symbol_fits && ts_fits
+}
+
+/// Append the rows of `batch` (projected to `COLUMNS`) that match the query.
+fn collect_ticks(
+    batch: &RecordBatch,
+    symbol: &str,
+    start_ts: i64,
+    end_ts: i64,
+    out: &mut Vec<Tick>,
+) -> Result<()> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .with_context(|| format!("Parquet batch has no `{}` column", name))
+    };
+    let symbols = column("symbol")?
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .context("`symbol` must be a UTF-8 column")?;
+    let ts = column("ts")?
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .context("`ts` must be an int64 column")?;
+    let price = column("price")?
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .context("`price` must be a float64 column")?;
+    let vol = column("vol")?
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .context("`vol` must be a float64 column")?;
+
+    for row in 0..batch.num_rows() {
+        if symbols.is_null(row)
+            || ts.is_null(row)
+            || price.is_null(row)
+            || symbols.value(row) != symbol
+        {
+            continue;
+        }
+        let at = ts.value(row);
+        if at < start_ts || at >= end_ts {
+            continue;
+        }
+        out.push(Tick {
+            ts: TsMillis(at),
+            symbol: symbol.to_string(),
+            price: price.value(row),
+            vol: if vol.is_null(row) {
+                0.0
+            } else {
+                vol.value(row)
+            },
+            amount: None,
+            source: Some("parquet".to_string()),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+// src/poller.rs
+use crate::data_fetch::QuoteSource;
+use crate::feed::TickFeed;
+use crate::models::{Quote, RunMode};
+use crate::storage::{Storage, Tick};
+use crate::utils::calendar::TradingCalendar;
+use crate::utils::tick_size::TickSizeTable;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Decides whether a poll tick actually hits the source: always, or only while the
+/// market is in session ("quiet hours" otherwise). Sessions are the China A-share
+/// `SESSIONS`; other markets should poll `always` (`trading.poll_outside_sessions`).
+#[derive(Clone)]
+pub struct PollGate {
+    calendar: Option<TradingCalendar>,
+    clock: Arc<dyn Fn() -> DateTime<Utc> + Send + Sync>,
+}
+
+impl PollGate {
+    /// Poll around the clock, e.g. for 24h markets.
+    pub fn always() -> Self {
+        Self {
+            calendar: None,
+            clock: Arc::new(Utc::now),
+        }
+    }
+
+    /// Poll only inside `calendar`'s sessions on its trading days.
+    pub fn sessions(calendar: TradingCalendar) -> Self {
+        Self {
+            calendar: Some(calendar),
+            clock: Arc::new(Utc::now),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock.
+    #[cfg(test)]
+    pub fn with_clock(mut self, clock: impl Fn() -> DateTime<Utc> + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.calendar
+            .as_ref()
+            .is_none_or(|calendar| calendar.is_session_open((self.clock)()))
+    }
+}
+
+/// Poll `symbol` every `every` and hand each quote to `on_quote`. Failed polls are
+/// logged and retried on the next tick; ticks while `gate` is closed are skipped, so
+/// polling resumes by itself at the next session. Runs until `cancel` fires; a poll
+/// already under way is finished first.
+pub async fn poll_symbol<Q, F, Fut>(
+    source: Arc<Q>,
+    symbol: String,
+    every: Duration,
+    gate: PollGate,
+    cancel: CancellationToken,
+    on_quote: F,
+) where
+    Q: QuoteSource,
+    F: Fn(Quote) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut interval = tokio::time::interval(every);
+    // a slow upstream should not trigger a burst of catch-up polls
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        if !gate.is_open() {
+            continue;
+        }
+        match source.fetch_quote(&symbol).await {
+            Ok(quote) => on_quote(quote).await,
+            Err(e) => warn!("Poll for {} failed: {}", symbol, e),
+        }
+    }
+    debug!("Poller for {} stopped", symbol);
+}
+
+/// Spawn one poller per `(symbol, interval)`; each quote is stored as a tick priced
+/// on `tick_sizes` and published on the live feed. All of them stop when `cancel` fires.
+pub fn spawn_pollers<Q>(
+    source: Arc<Q>,
+    storage: Arc<Storage>,
+    feed: TickFeed,
+    schedule: Vec<(String, Duration)>,
+    tick_sizes: TickSizeTable,
+    gate: PollGate,
+    cancel: CancellationToken,
+) -> Vec<JoinHandle<()>>
+where
+    Q: QuoteSource + Send + Sync + 'static,
+{
+    info!("Starting quote pollers for {} symbols", schedule.len());
+    schedule
+        .into_iter()
+        .map(|(symbol, every)| {
+            let storage = storage.clone();
+            let feed = feed.clone();
+            let tick_sizes = tick_sizes.clone();
+            debug!("Polling {} every {:?}", symbol, every);
+            let symbol_key = symbol.clone();
+            tokio::spawn(poll_symbol(
+                source.clone(),
+                symbol,
+                every,
+                gate.clone(),
+                cancel.clone(),
+                move |quote: Quote| {
+                    let storage = storage.clone();
+                    let feed = feed.clone();
+                    let symbol = symbol_key.clone();
+                    let tick_sizes = tick_sizes.clone();
+                    async move {
+                        // stored under the configured symbol, not the normalized one,
+                        // which is also what the tick size is looked up by
+                        let quote = Quote { symbol, ..quote };
+                        let tick = Tick::from_quote(&quote, &tick_sizes);
+                        match storage.save_tick_as(&tick, RunMode::Real).await {
+                            Ok(true) => {
+                                feed.publish(tick);
+                            }
+                            Ok(false) => {}
+                            Err(e) => {
+                                warn!("Failed to store polled tick for {}: {}", tick.symbol, e)
+                            }
+                        }
+                    }
+                },
+            ))
+        })
+        .collect()
+}
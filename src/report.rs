@@ -0,0 +1,104 @@
+// src/report.rs
+use crate::app::MarketAnalysis;
+use crate::indicators::{CrossEvent, MACDPoint};
+use crate::storage::Tick;
+use crate::utils::number_format;
+use crate::utils::time::market_date;
+use chrono_tz::Tz;
+use serde::Serialize;
+
+/// Shareable snapshot of everything we know about a symbol. Renderers (JSON today)
+/// consume this struct as-is so every format shows the same numbers.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub symbol: String,
+    pub generated_at: i64,
+    pub analysis_period: String,
+    pub latest_tick: Option<Tick>,
+    pub summary: ReportSummary,
+    pub cross_events: Vec<CrossEvent>,
+    pub macd_points: Vec<MACDPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportSummary {
+    pub has_data: bool,
+    pub signal_count: usize,
+    pub bullish_signals: usize,
+    pub bearish_signals: usize,
+    /// first price of the latest trading day in the series
+    #[serde(with = "number_format::option_float")]
+    pub day_open: Option<f64>,
+    #[serde(with = "number_format::option_float")]
+    pub last_price: Option<f64>,
+    #[serde(with = "number_format::option_float")]
+    pub day_change: Option<f64>,
+    #[serde(with = "number_format::option_float")]
+    pub day_change_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Json,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("Unsupported report format: {}", s)),
+        }
+    }
+}
+
+impl Report {
+    pub fn new(analysis: MarketAnalysis, latest_tick: Option<Tick>, tz: Tz) -> Self {
+        let last = analysis.macd_points.last();
+        let day_open = last.and_then(|last| {
+            let day = market_date(tz, last.ts.to_datetime()?);
+            analysis
+                .macd_points
+                .iter()
+                .find(|p| {
+                    p.ts.to_datetime()
+                        .is_some_and(|dt| market_date(tz, dt) == day)
+                })
+                .map(|p| p.price)
+        });
+        let last_price = last.map(|p| p.price);
+        let day_change = day_open.zip(last_price).map(|(open, last)| last - open);
+        let day_change_pct = day_open
+            .zip(day_change)
+            .filter(|(open, _)| *open != 0.0)
+            .map(|(open, change)| change / open * 100.0);
+
+        Report {
+            symbol: analysis.symbol,
+            generated_at: chrono::Utc::now().timestamp_millis(),
+            analysis_period: analysis.analysis_period,
+            latest_tick,
+            summary: ReportSummary {
+                has_data: analysis.has_data,
+                signal_count: analysis.signal_count,
+                bullish_signals: analysis.bullish_signals,
+                bearish_signals: analysis.bearish_signals,
+                day_open,
+                last_price,
+                day_change,
+                day_change_pct,
+            },
+            cross_events: analysis.cross_events,
+            macd_points: analysis.macd_points,
+        }
+    }
+}
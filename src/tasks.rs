@@ -0,0 +1,107 @@
+// src/tasks.rs
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Builds one run of a task. The run should return soon after its token is
+/// cancelled, checking between iterations rather than abandoning one midway.
+type TaskFactory = Arc<dyn Fn(CancellationToken) -> BoxFuture<'static, ()> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// cancelled, waiting for the current iteration to finish
+    Stopping,
+    Stopped,
+}
+
+struct Run {
+    token: CancellationToken,
+    /// taken by `stop` while it waits for the run to end; the run stays registered
+    /// until then, so `start` cannot overlap it with a new one
+    handle: Option<JoinHandle<()>>,
+}
+
+struct Task {
+    factory: TaskFactory,
+    run: Option<Run>,
+}
+
+/// Named background tasks (e.g. `ingestion`) that can be stopped and started again
+/// at runtime through `/api/admin/task/{name}/...`. Every run gets its own token.
+#[derive(Clone, Default)]
+pub struct TaskRegistry {
+    tasks: Arc<Mutex<HashMap<String, Task>>>,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `name` in the stopped state; `start` runs `factory`.
+    pub async fn register<F, Fut>(&self, name: &str, factory: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let factory: TaskFactory = Arc::new(move |token| Box::pin(factory(token)));
+        self.tasks
+            .lock()
+            .await
+            .insert(name.to_string(), Task { factory, run: None });
+    }
+
+    /// Start `name` unless it is already running or still stopping. `None` for an
+    /// unknown task.
+    pub async fn start(&self, name: &str) -> Option<TaskState> {
+        let mut tasks = self.tasks.lock().await;
+        let task = tasks.get_mut(name)?;
+        match task.run.as_ref().map(|run| &run.handle) {
+            Some(None) => return Some(TaskState::Stopping),
+            Some(Some(handle)) if !handle.is_finished() => return Some(TaskState::Running),
+            _ => {}
+        }
+        let token = CancellationToken::new();
+        let handle = tokio::spawn((task.factory)(token.clone()));
+        task.run = Some(Run {
+            token,
+            handle: Some(handle),
+        });
+        info!("Started task {}", name);
+        Some(TaskState::Running)
+    }
+
+    /// Cancel `name` and wait for its current iteration to finish. `None` for an
+    /// unknown task; `Stopping` when another `stop` is already waiting for it.
+    pub async fn stop(&self, name: &str) -> Option<TaskState> {
+        // only the handle is taken, so the registry stays usable while the run winds down
+        let handle = {
+            let mut tasks = self.tasks.lock().await;
+            let Some(run) = tasks.get_mut(name)?.run.as_mut() else {
+                return Some(TaskState::Stopped);
+            };
+            run.token.cancel();
+            match run.handle.take() {
+                Some(handle) => handle,
+                None => return Some(TaskState::Stopping),
+            }
+        };
+        if let Err(e) = handle.await {
+            warn!("Task {} ended abnormally: {}", name, e);
+        }
+        // `start` leaves a stopping run alone, so the one registered is still ours
+        if let Some(task) = self.tasks.lock().await.get_mut(name) {
+            task.run = None;
+        }
+        info!("Stopped task {}", name);
+        Some(TaskState::Stopped)
+    }
+}
@@ -1,7 +1,161 @@
 // src/tests.rs
 #[cfg(test)]
 mod tests {
-    use crate::indicators::{EMA, MACDCalc, divergence_score};
+    use crate::analysis::{analyze_signals, signal_confidence};
+    use crate::app::{
+        AnalysisOptions, SymbolInfo, TradingApp, WarmSummary, collect_sorted_by_symbol,
+    };
+    use crate::backfill::backfill_klines;
+    use crate::config::{
+        AlertsConfig, AppConfig, DuplicateTickAction, OutlierAction, PriceLimitConfig,
+        SlowClientPolicy, SmtpConfig, SmtpTls, WatchlistEntry,
+    };
+    use crate::data_fetch::{
+        DataFetcher, FieldKind, KlineSource, QuoteSource, ResponseCache, parse_field,
+        parse_json_decimal, parse_kline_timestamp,
+    };
+    use crate::eastmoney::StockData;
+    use crate::error::{AppError, ErrorCode};
+    use crate::executor::SimExecutor;
+    use crate::feed::{TickFeed, is_replayed, replay_day};
+    use crate::indicators::{
+        ADXPoint, CrossDirection, CrossEvent, CrossKind, EMA, LimitKind, MACD_HISTOGRAM_MULTIPLIER,
+        MACDCalc, MACDPoint, PriceSource, StrategyKind, VolumeBasis, apply_cooldown,
+        apply_session_windows, bar_prices, compute_adx, compute_atr_series, compute_macd_series,
+        compute_macd_series_flagged, compute_macd_series_log, compute_ppo_series,
+        compute_rsi_series, compute_sma_series, compute_vwap, confirm_crosses,
+        count_dif_zero_crossings, count_histogram_zero_crossings, detect_price_limits,
+        divergence_score, macd_cross_events, rebase_to_common_anchor, return_correlation,
+        select_cross_events, sma_cross_events,
+    };
+    use crate::models::{Kline, Quote, RunMode};
+    use crate::notify::{
+        Alert, AlertEngine, EmailNotifier, Notifier, SlackNotifier, dispatch, notifiers_from_config,
+    };
+    use crate::poller::{PollGate, poll_symbol, spawn_pollers};
+    use crate::sim::mock_day_ticks;
+    #[cfg(feature = "redis")]
+    use crate::storage::retry_transient;
+    use crate::storage::{
+        Bar, BarTimestamp, Storage, SymbolMatch, SymbolMeta, Tick, aggregate_bars_stamped,
+        retry_busy,
+    };
+    use crate::tasks::{TaskRegistry, TaskState};
+    use crate::utils::calendar::{SessionWindow, TradingCalendar, WindowScope};
+    use crate::utils::downsample::lttb;
+    use crate::utils::http_client::HttpClient;
+    use crate::utils::json_stream::stream_json_with_array;
+    use crate::utils::number_format::NumberFormat;
+    use crate::utils::time::{TsMillis, market_date, market_local_to_millis, market_today};
+    use crate::utils::worker_pool::WorkerPool;
+    use crate::web::{AppState, configure_api, configure_static, json_config, parse_days};
+    use actix_web::test::{
+        TestRequest, call_and_read_body_json, call_service, init_service, read_body,
+    };
+    use actix_web::{App, http::StatusCode, http::header, web};
+    use chrono::{NaiveDate, TimeZone};
+    use proptest::prelude::*;
+    use rust_decimal::Decimal;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tokio_util::sync::CancellationToken;
+
+    /// Storage backed by an in-memory SQLite db and the test Redis instance
+    /// (`TEST_REDIS_URL`, default `redis://localhost:6379/1`). Returns `None` when
+    /// Redis is unreachable so storage tests are skipped instead of failing.
+    fn test_storage() -> Option<Arc<Storage>> {
+        match Storage::new(":memory:", &test_redis_url()) {
+            Ok(storage) => Some(Arc::new(storage)),
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Storage for tests that never reach the cache. `Storage::new` only needs its Redis
+    /// to answer a PING, which this stand-in does for every command it is sent.
+    fn storage_without_redis() -> Arc<Storage> {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}/0", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut socket in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut buf = [0u8; 1024];
+                    while matches!(socket.read(&mut buf), Ok(n) if n > 0) {
+                        if socket.write_all(b"+PONG\r\n").is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        Arc::new(Storage::new(":memory:", &url).unwrap())
+    }
+
+    fn test_config() -> AppConfig {
+        AppConfig::new().expect("config/default.toml should load")
+    }
+
+    /// Fetcher whose upstreams all fail immediately (nothing listens on port 1).
+    fn offline_fetcher(allow_simulated_fallback: bool) -> DataFetcher {
+        let mut config = test_config();
+        for source in [
+            &mut config.data_source.eastmoney,
+            &mut config.data_source.baidu,
+            &mut config.data_source.sina,
+        ] {
+            source.enabled = true;
+            source.base_url = "http://127.0.0.1:1".to_string();
+        }
+        config.data_source.allow_simulated_fallback = allow_simulated_fallback;
+        DataFetcher::new(Arc::new(config))
+    }
+
+    fn test_state(storage: Arc<Storage>) -> AppState {
+        let config = Arc::new(test_config());
+        AppState {
+            mode: storage.run_mode(),
+            mode_history: Arc::new(RwLock::new(VecDeque::new())),
+            trading_app: Arc::new(TradingApp::new(storage, config.clone())),
+            fetcher: Arc::new(DataFetcher::new(config.clone())),
+            feed: TickFeed::new(),
+            sse_connections: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            tasks: TaskRegistry::new(),
+            replay_running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            config,
+        }
+    }
+
+    fn test_redis_url() -> String {
+        std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/1".to_string())
+    }
+
+    /// Redis keys outlive a test run, so every test works on its own symbol.
+    fn unique_symbol(prefix: &str) -> String {
+        format!(
+            "{}{}",
+            prefix,
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        )
+    }
+
+    fn tick(symbol: &str, ts: i64, price: f64) -> Tick {
+        Tick {
+            ts: TsMillis(ts),
+            symbol: symbol.to_string(),
+            price,
+            vol: 100.0,
+            amount: None,
+            source: None,
+        }
+    }
 
     #[test]
     fn test_ema() {
@@ -14,6 +168,235 @@ mod tests {
         assert!(last > 2.0 && last < 4.0);
     }
 
+    #[test]
+    fn test_dif_and_histogram_zero_crossings_differ() {
+        // DIF turns positive once at 3; the histogram (DIF vs DEA) flips at 1, 4 and 6
+        let series = [
+            (-2.0, -1.0),
+            (-1.0, 0.5),
+            (-0.5, 0.2),
+            (0.5, 0.4),
+            (1.0, -0.1),
+            (1.5, -0.2),
+            (2.0, 0.3),
+        ];
+        let points: Vec<MACDPoint> = series
+            .iter()
+            .enumerate()
+            .map(|(i, (dif, macd))| MACDPoint {
+                ts: TsMillis(i as i64),
+                price: 10.0,
+                dif: *dif,
+                dea: dif - macd / 2.0,
+                macd: *macd,
+                ema_short: None,
+                ema_long: None,
+                warming_up: false,
+            })
+            .collect();
+
+        assert_eq!(count_dif_zero_crossings(&points, 0), 1);
+        assert_eq!(count_histogram_zero_crossings(&points, 0), 3);
+        // warmup hides the early crossings only
+        assert_eq!(count_dif_zero_crossings(&points, 4), 0);
+        assert_eq!(count_histogram_zero_crossings(&points, 4), 2);
+    }
+
+    #[test]
+    fn test_cooldown_drops_repeated_direction_only() {
+        use CrossDirection::{Bearish, Bullish};
+        // whipsaw: alternating crosses a second apart, then a bullish one after the window
+        let events: Vec<CrossEvent> = [
+            (0, Bullish),
+            (1, Bearish),
+            (2, Bullish),
+            (3, Bearish),
+            (70, Bullish),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, (secs, direction))| CrossEvent {
+            index,
+            ts: TsMillis::from_secs(secs),
+            price: 10.0,
+            direction,
+        })
+        .collect();
+
+        let kept: Vec<(i64, CrossDirection)> = apply_cooldown(events.clone(), 60_000)
+            .iter()
+            .map(|e| (e.ts.as_secs(), e.direction))
+            .collect();
+        assert_eq!(kept, vec![(0, Bullish), (1, Bearish), (70, Bullish)]);
+        assert_eq!(apply_cooldown(events, 0).len(), 5);
+    }
+
+    #[test]
+    fn test_session_windows_keep_only_crosses_near_open_and_close() {
+        let calendar = TradingCalendar::default();
+        let windows: Vec<SessionWindow> = serde_json::from_value(serde_json::json!([
+            { "from": "open", "minutes": 30 },
+            { "from": "close", "minutes": 30 },
+        ]))
+        .unwrap();
+        // Friday 2024-03-01, then a Saturday
+        let times = [
+            "2024-03-01 09:35", // morning open window
+            "2024-03-01 10:30", // mid-morning
+            "2024-03-01 11:30", // morning close, inclusive
+            "2024-03-01 12:00", // lunch
+            "2024-03-01 13:20", // afternoon open window
+            "2024-03-01 14:00", // mid-afternoon
+            "2024-03-01 14:45", // afternoon close window
+            "2024-03-02 09:35", // no session on a weekend
+        ];
+        let events: Vec<CrossEvent> = times
+            .iter()
+            .enumerate()
+            .map(|(index, time)| CrossEvent {
+                index,
+                ts: TsMillis(market_local_to_millis(
+                    calendar.tz,
+                    chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+                )),
+                price: 10.0,
+                direction: CrossDirection::Bullish,
+            })
+            .collect();
+
+        let kept = |windows: &[SessionWindow]| -> Vec<usize> {
+            apply_session_windows(events.clone(), &calendar, windows)
+                .iter()
+                .map(|e| e.index)
+                .collect()
+        };
+        // measured from the day's open and close, so lunch edges are not windows
+        assert_eq!(kept(&windows), vec![0, 6]);
+        // unless each session is asked for
+        let per_session: Vec<SessionWindow> = windows
+            .iter()
+            .map(|w| SessionWindow {
+                of: WindowScope::Session,
+                ..*w
+            })
+            .collect();
+        assert_eq!(kept(&per_session), vec![0, 2, 4, 6]);
+        // a day window running past the morning close (to 12:30) still skips lunch
+        let long_open = SessionWindow {
+            minutes: 180,
+            ..windows[0]
+        };
+        assert_eq!(kept(&[long_open]), vec![0, 1, 2]);
+        assert_eq!(apply_session_windows(events, &calendar, &[]).len(), 8);
+    }
+
+    #[test]
+    fn test_weak_crosses_below_min_histogram_are_dropped() {
+        // a bullish cross that barely grazes zero, a shallow bearish one, then a strong
+        // bullish cross
+        let histogram = [-1.0, -0.5, 0.1, 0.05, -0.2, -0.3, 0.2, 0.8, 1.0];
+        let points: Vec<MACDPoint> = histogram
+            .iter()
+            .enumerate()
+            .map(|(i, &macd)| MACDPoint {
+                ts: TsMillis(i as i64),
+                price: 10.0,
+                dif: macd,
+                dea: 0.0,
+                macd,
+                ema_short: None,
+                ema_long: None,
+                warming_up: false,
+            })
+            .collect();
+        let events = macd_cross_events(&points, 0);
+        assert_eq!(events.len(), 3);
+
+        let kept = confirm_crosses(events.clone(), &points, 0.5, 3);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(
+            (kept[0].index, kept[0].direction),
+            (6, CrossDirection::Bullish)
+        );
+        // confirmation must come within the window
+        assert!(confirm_crosses(events.clone(), &points, 0.5, 0).is_empty());
+        assert_eq!(confirm_crosses(events, &points, 0.0, 3).len(), 3);
+    }
+
+    #[test]
+    fn test_cross_event_selection_filters_kind_since_and_limit() {
+        use CrossDirection::{Bearish, Bullish};
+        let events: Vec<CrossEvent> = [Bullish, Bearish, Bullish, Bullish, Bearish, Bullish]
+            .into_iter()
+            .enumerate()
+            .map(|(index, direction)| CrossEvent {
+                index,
+                ts: TsMillis::from_secs(index as i64),
+                price: 10.0,
+                direction,
+            })
+            .collect();
+        let ts = |selected: Vec<CrossEvent>| {
+            selected
+                .iter()
+                .map(|e| e.ts.as_millis())
+                .collect::<Vec<_>>()
+        };
+
+        let golden = select_cross_events(events.clone(), None, CrossKind::Golden, 3);
+        assert!(golden.iter().all(|e| e.direction == Bullish));
+        assert_eq!(ts(golden), [0, 2000, 3000]);
+        // next page from just after the last one returned
+        let next = select_cross_events(events.clone(), Some(TsMillis(3001)), CrossKind::Golden, 3);
+        assert_eq!(ts(next), [5000]);
+        let death = select_cross_events(events.clone(), None, CrossKind::Death, 10);
+        assert_eq!(ts(death), [1000, 4000]);
+        assert_eq!(
+            select_cross_events(events, Some(TsMillis(2000)), CrossKind::All, usize::MAX).len(),
+            4
+        );
+
+        let kind: CrossKind = serde_json::from_str("\"golden\"").unwrap();
+        assert_eq!(kind, CrossKind::Golden);
+        assert!(serde_json::from_str::<CrossKind>("\"sideways\"").is_err());
+    }
+
+    #[test]
+    fn test_ppo_is_scale_invariant() {
+        let points: Vec<(TsMillis, f64)> = (0..80)
+            .map(|i| {
+                (
+                    TsMillis(i),
+                    20.0 + (i as f64 / 7.0).sin() * 2.0 + i as f64 * 0.05,
+                )
+            })
+            .collect();
+        let scaled: Vec<(TsMillis, f64)> = points.iter().map(|(ts, p)| (*ts, p * 37.5)).collect();
+
+        let ppo = compute_ppo_series(&points, 12, 26, 9);
+        let ppo_scaled = compute_ppo_series(&scaled, 12, 26, 9);
+        assert_eq!(ppo.len(), points.len());
+        for (a, b) in ppo.iter().zip(&ppo_scaled) {
+            assert!((a.ppo - b.ppo).abs() < 1e-9);
+            assert!((a.signal - b.signal).abs() < 1e-9);
+            assert!((a.histogram - b.histogram).abs() < 1e-9);
+        }
+        assert!(ppo.iter().any(|p| p.ppo != 0.0));
+
+        // a zero long EMA must not divide by zero
+        let zeros = compute_ppo_series(&[(TsMillis(0), 0.0), (TsMillis(1), 0.0)], 12, 26, 9);
+        assert!(zeros.iter().all(|p| p.ppo == 0.0));
+    }
+
+    #[test]
+    fn test_ema_zero_period_acts_as_one() {
+        let (mut zero, mut one) = (EMA::new(0), EMA::new(1));
+        for v in [5.0, 1.0, 9.0, -3.0] {
+            assert_eq!(zero.next(v), v);
+            assert_eq!(one.next(v), v);
+        }
+    }
+
     #[test]
     fn test_macd_sequence() {
         let mut macd = MACDCalc::new(12, 26, 9);
@@ -23,8 +406,134 @@ mod tests {
             values.push(macd.next(p));
         }
         // latest MACD dif should be > 0
-        let last = values.last().unwrap();
-        assert!(last.dif > 0.0);
+        let (dif, _dea, _macd) = *values.last().unwrap();
+        assert!(dif > 0.0);
+    }
+
+    #[test]
+    fn test_macd_warmup_points_are_flagged_on_request() {
+        let points: Vec<(TsMillis, f64)> = (0..40)
+            .map(|i| (TsMillis(i), 10.0 + i as f64 * 0.1))
+            .collect();
+
+        let flagged = compute_macd_series_flagged(&points, 12, 26, 9);
+        assert_eq!(flagged.len(), 40);
+        assert!(flagged[..26].iter().all(|p| p.warming_up));
+        assert!(flagged[26..].iter().all(|p| !p.warming_up));
+        assert_eq!(
+            serde_json::to_value(&flagged[0]).unwrap()["warming_up"],
+            true
+        );
+
+        // default output is unchanged: nothing flagged, no extra JSON field
+        let plain = compute_macd_series(&points, 12, 26, 9);
+        assert!(plain.iter().all(|p| !p.warming_up));
+        assert!(
+            serde_json::to_value(&plain[0])
+                .unwrap()
+                .get("warming_up")
+                .is_none()
+        );
+        assert_eq!(plain[30].macd, flagged[30].macd);
+
+        // fewer points than `long`: all of them are warmup
+        assert!(
+            compute_macd_series_flagged(&points[..5], 12, 26, 9)
+                .iter()
+                .all(|p| p.warming_up)
+        );
+    }
+
+    #[test]
+    fn test_log_scale_macd_weighs_doubling_and_halving_equally() {
+        // flat at 10, then a doubling in one series and a halving in the other
+        let step = |to: f64| -> Vec<(TsMillis, f64)> {
+            (0..80)
+                .map(|i| (TsMillis(i), if i < 40 { 10.0 } else { to }))
+                .collect()
+        };
+        let (up, down) = (step(20.0), step(5.0));
+        let peak = |s: &[MACDPoint]| s.iter().map(|p| p.dif).fold(f64::MIN, f64::max);
+        let trough = |s: &[MACDPoint]| s.iter().map(|p| p.dif).fold(f64::MAX, f64::min);
+
+        let linear = peak(&compute_macd_series(&up, 12, 26, 9))
+            / -trough(&compute_macd_series(&down, 12, 26, 9));
+        let log_up = compute_macd_series_log(&up, 12, 26, 9).unwrap();
+        let log = peak(&log_up) / -trough(&compute_macd_series_log(&down, 12, 26, 9).unwrap());
+        assert!((linear - 2.0).abs() < 1e-9);
+        assert!((log - 1.0).abs() < 1e-9);
+
+        // output prices and EMAs stay on the raw price scale
+        assert_eq!(log_up[60].price, 20.0);
+        assert!(log_up[60].ema_short.unwrap() > 10.0 && log_up[60].ema_short.unwrap() < 20.0);
+        assert_eq!(
+            compute_macd_series_log(&[(TsMillis(0), 1.0), (TsMillis(1), 0.0)], 12, 26, 9)
+                .unwrap_err(),
+            1
+        );
+    }
+
+    /// MACD periods with `short < long`, as configured in practice.
+    fn macd_periods() -> impl Strategy<Value = (usize, usize, usize)> {
+        (1usize..30, 1usize..40, 1usize..20)
+            .prop_map(|(short, extra, signal)| (short, short + extra, signal))
+    }
+
+    fn price_series(max_len: usize) -> impl Strategy<Value = Vec<(TsMillis, f64)>> {
+        prop::collection::vec(0.01f64..10_000.0, 0..max_len).prop_map(|prices| {
+            prices
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| (TsMillis::from_secs(i as i64), p))
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_macd_is_one_finite_point_per_input(
+            points in price_series(300),
+            (short, long, signal) in macd_periods(),
+        ) {
+            let macd = compute_macd_series(&points, short, long, signal);
+            prop_assert_eq!(macd.len(), points.len());
+            for (p, (ts, price)) in macd.iter().zip(&points) {
+                prop_assert_eq!(p.ts, *ts);
+                prop_assert_eq!(p.price, *price);
+                prop_assert!(p.dif.is_finite() && p.dea.is_finite() && p.macd.is_finite());
+                prop_assert!(p.ema_short.is_some_and(f64::is_finite));
+                prop_assert!(p.ema_long.is_some_and(f64::is_finite));
+            }
+        }
+
+        #[test]
+        fn prop_histogram_is_scaled_dif_minus_dea(
+            points in price_series(300),
+            (short, long, signal) in macd_periods(),
+        ) {
+            for p in compute_macd_series(&points, short, long, signal) {
+                let expected = MACD_HISTOGRAM_MULTIPLIER * (p.dif - p.dea);
+                prop_assert!((p.macd - expected).abs() <= 1e-9 * expected.abs().max(1.0));
+            }
+        }
+
+        #[test]
+        fn prop_constant_tail_drives_macd_to_zero(
+            mut points in price_series(100),
+            (short, long, signal) in macd_periods(),
+            level in 0.01f64..10_000.0,
+        ) {
+            // whatever came before, a long enough flat stretch lets every EMA catch up
+            let start = points.len() as i64;
+            points.extend((0..3_000).map(|i| (TsMillis::from_secs(start + i), level)));
+            let last = compute_macd_series(&points, short, long, signal)
+                .pop()
+                .unwrap();
+            let tolerance = 1e-6 * level.max(1.0);
+            prop_assert!(last.dif.abs() < tolerance, "dif {}", last.dif);
+            prop_assert!(last.dea.abs() < tolerance, "dea {}", last.dea);
+            prop_assert!(last.macd.abs() < tolerance, "macd {}", last.macd);
+        }
     }
 
     #[test]
@@ -35,4 +544,5040 @@ mod tests {
         let score = divergence_score(&price, &macd);
         assert!(score > 0.0); // bearish divergence -> sell
     }
+
+    #[test]
+    fn test_return_correlation_of_mirrored_series() {
+        let bars = |closes: &[(TsMillis, f64)]| -> Vec<Bar> {
+            closes
+                .iter()
+                .map(|&(ts, close)| Bar {
+                    ts,
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 0.0,
+                })
+                .collect()
+        };
+        let base: Vec<(TsMillis, f64)> = [10.0, 10.5, 10.2, 10.8, 10.4, 11.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (TsMillis(i as i64 * 60_000), p))
+            .collect();
+
+        // same returns at twice the price
+        let scaled: Vec<(TsMillis, f64)> = base.iter().map(|&(ts, p)| (ts, p * 2.0)).collect();
+        let same = return_correlation(&bars(&base), &bars(&scaled));
+        assert_eq!(same.points, 6);
+        assert!((same.correlation.unwrap() - 1.0).abs() < 1e-9);
+
+        // every return of `b` is the negated return of `a`
+        let mut mirrored = vec![base[0]];
+        for w in base.windows(2) {
+            let prev = mirrored.last().unwrap().1;
+            mirrored.push((w[1].0, prev * (2.0 - w[1].1 / w[0].1)));
+        }
+        let opposite = return_correlation(&bars(&base), &bars(&mirrored));
+        assert!((opposite.correlation.unwrap() + 1.0).abs() < 1e-9);
+
+        // `b` misses a bar and starts late: forward-filled from its first trade on
+        let mut gappy = scaled.clone();
+        gappy.remove(3);
+        gappy.remove(0);
+        let filled = return_correlation(&bars(&base), &bars(&gappy));
+        assert_eq!(filled.points, 5);
+        assert_eq!(filled.anchor, Some(TsMillis(60_000)));
+        assert!(filled.correlation.unwrap() > 0.0);
+
+        // a flat series has no variance to correlate with
+        let flat: Vec<(TsMillis, f64)> = base.iter().map(|&(ts, _)| (ts, 5.0)).collect();
+        let none = return_correlation(&bars(&base), &bars(&flat));
+        assert_eq!(none.points, 6);
+        assert_eq!(none.correlation, None);
+    }
+
+    #[test]
+    fn test_rebase_uses_the_latest_common_start_as_anchor() {
+        let bars = |closes: &[(i64, f64)]| -> Vec<Bar> {
+            closes
+                .iter()
+                .map(|&(minute, close)| Bar {
+                    ts: TsMillis(minute * 60_000),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 0.0,
+                })
+                .collect()
+        };
+        // `early` trades from minute 0, `late` only from minute 2, `gone` stops at 1
+        let rebased = rebase_to_common_anchor(&[
+            (
+                "early".to_string(),
+                bars(&[(0, 10.0), (1, 20.0), (3, 25.0)]),
+            ),
+            ("late".to_string(), bars(&[(2, 4.0), (3, 5.0)])),
+            ("gone".to_string(), bars(&[(0, 7.0), (1, 7.5)])),
+            ("empty".to_string(), vec![]),
+        ]);
+
+        let anchor = TsMillis(2 * 60_000);
+        assert_eq!(rebased.anchor, Some(anchor));
+        // no bar at the anchor: rebased from the close carried into it, not from 10.0
+        assert_eq!(
+            rebased.series["early"],
+            [(anchor, 100.0), (TsMillis(3 * 60_000), 125.0)]
+        );
+        assert_eq!(
+            rebased.series["late"],
+            [(anchor, 100.0), (TsMillis(3 * 60_000), 125.0)]
+        );
+        assert_eq!(rebased.missing, ["gone", "empty"]);
+
+        let nothing = rebase_to_common_anchor(&[("empty".to_string(), vec![])]);
+        assert_eq!(nothing.anchor, None);
+        assert_eq!(nothing.missing, ["empty"]);
+    }
+
+    #[actix_web::test]
+    async fn test_compare_endpoint_rebases_stored_symbols_and_lists_missing() {
+        let storage = storage_without_redis();
+        let (early, late, absent) = (
+            unique_symbol("CMPA"),
+            unique_symbol("CMPB"),
+            unique_symbol("CMPC"),
+        );
+        let open = 1_709_256_600_000; // 2024-03-01 09:30 Shanghai
+        let mut ticks: Vec<Tick> = (0..5)
+            .map(|i| tick(&early, open + i * 60_000, 4.0 + 2.0 * i as f64))
+            .collect();
+        // `late` only starts trading at 09:32
+        ticks.extend((2..5).map(|i| tick(&late, open + i * 60_000, 2.0 * i as f64)));
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let uri = format!(
+            "/api/compare?symbols={},{},{}&date=2024-03-01&interval=1m",
+            early, late, absent
+        );
+        let body: serde_json::Value =
+            call_and_read_body_json(&app, TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(body["success"], true);
+        let data = &body["data"];
+        let anchor = open + 2 * 60_000;
+        assert_eq!(data["anchor"], anchor);
+        // 8 -> 10 -> 12 and 4 -> 6 -> 8 from the shared 09:32 anchor
+        assert_eq!(
+            data["series"][&early],
+            serde_json::json!([
+                [anchor, 100.0],
+                [anchor + 60_000, 125.0],
+                [anchor + 120_000, 150.0]
+            ])
+        );
+        assert_eq!(
+            data["series"][&late],
+            serde_json::json!([
+                [anchor, 100.0],
+                [anchor + 60_000, 150.0],
+                [anchor + 120_000, 200.0]
+            ])
+        );
+        assert_eq!(data["missing"], serde_json::json!([absent]));
+    }
+
+    #[actix_web::test]
+    async fn test_history_etag_round_trip() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("ETAG");
+        let now = chrono::Utc::now().timestamp_millis();
+        for i in 0..5 {
+            storage
+                .save_tick(&tick(&symbol, now - (10 - i) * 60_000, 10.0 + i as f64))
+                .await
+                .unwrap();
+        }
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage.clone())))
+                .configure(configure_api),
+        )
+        .await;
+        let uri = format!("/api/history/{}", symbol);
+
+        let resp = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+
+        let req = TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        // different params must not reuse the validator
+        let req = TestRequest::get()
+            .uri(&format!("{}?date=2024-01-02", uri))
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // a backfilled tick older than the newest one changes the series too
+        let inserted = storage
+            .insert_ticks_ignore(&[tick(&symbol, now - 30 * 60_000, 9.0)])
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+        let req = TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let backfilled = resp.headers().get(header::ETAG).unwrap().clone();
+        assert_ne!(backfilled, etag);
+
+        storage.save_tick(&tick(&symbol, now, 20.0)).await.unwrap();
+        let req = TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::IF_NONE_MATCH, backfilled.clone()))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_ne!(resp.headers().get(header::ETAG).unwrap(), &backfilled);
+    }
+
+    #[actix_web::test]
+    async fn test_history_negotiates_json_csv_or_406() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("ACCEPT");
+        let now = chrono::Utc::now().timestamp_millis();
+        for i in 0..5 {
+            storage
+                .save_tick(&tick(&symbol, now - (10 - i) * 60_000, 10.0 + i as f64))
+                .await
+                .unwrap();
+        }
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage.clone())))
+                .configure(configure_api),
+        )
+        .await;
+        let uri = format!("/api/history/{}?last=5", symbol);
+        let get = |accept: &'static str| {
+            TestRequest::get()
+                .uri(&uri)
+                .insert_header((header::ACCEPT, accept))
+                .to_request()
+        };
+
+        let resp = call_service(&app, get("application/json")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let json_etag = resp.headers().get(header::ETAG).unwrap().clone();
+        let body: serde_json::Value = serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(body["data"]["points"].as_array().unwrap().len(), 5);
+
+        let resp = call_service(&app, get("text/csv")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            resp.headers()
+                .get(header::CONTENT_TYPE)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("text/csv")
+        );
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Accept");
+        assert_ne!(resp.headers().get(header::ETAG).unwrap(), json_etag);
+        let csv = String::from_utf8(read_body(resp).await.to_vec()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "ts,price,dif,dea,macd,ema_short,ema_long");
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[5].split(',').nth(1), Some("14"));
+
+        // a preference order is honoured, and anything-goes falls back to JSON
+        let resp = call_service(&app, get("application/xml, text/csv;q=0.5")).await;
+        assert!(
+            resp.headers()
+                .get(header::CONTENT_TYPE)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("text/csv")
+        );
+        let resp = call_service(&app, get("*/*")).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let resp = call_service(&app, get("application/xml")).await;
+        assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+        let resp = call_service(&app, get("text/csv;q=0")).await;
+        assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+
+    struct MockKlines(Vec<Kline>);
+
+    impl KlineSource for MockKlines {
+        async fn fetch_klines(
+            &self,
+            _symbol: &str,
+            _start_date: NaiveDate,
+            _end_date: NaiveDate,
+            _period: &str,
+        ) -> Result<Vec<Kline>, AppError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn kline(symbol: &str, ts: i64, close: i64) -> Kline {
+        let close = Decimal::new(close, 2);
+        Kline {
+            symbol: symbol.to_string(),
+            timestamp: ts,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: Decimal::from(1500),
+            amount: None,
+            period: "1min".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_symbol_normalization_covers_bse_and_funds() {
+        let fetcher = offline_fetcher(false);
+        let cases = [
+            ("600733", "600733.SH", 1),
+            ("000001", "000001.SZ", 0),
+            ("300750", "300750.SZ", 0),
+            (" 600733.sh ", "600733.SH", 1),
+            // Beijing Stock Exchange
+            ("830799", "830799.BJ", 0),
+            ("430047", "430047.BJ", 0),
+            // Shanghai ETF and Shenzhen ETF
+            ("510300", "510300.SH", 1),
+            ("159915", "159915.SZ", 0),
+        ];
+        for (raw, normalized, market) in cases {
+            let symbol = fetcher.normalize_symbol(raw);
+            assert_eq!(symbol, normalized, "normalizing {:?}", raw);
+            let (m, code) = fetcher.parse_symbol(&symbol).unwrap();
+            assert_eq!((m, code), (market, &normalized[..6]));
+        }
+        assert!(fetcher.parse_symbol("AAPL").is_err());
+    }
+
+    #[test]
+    fn test_config_loads_from_explicit_path() {
+        let default = std::fs::read_to_string("config/default.toml").unwrap();
+        let contents = default.replace("port = 8080", "port = 9191");
+        let path = std::env::temp_dir().join(format!("{}.toml", unique_symbol("macd-config")));
+        std::fs::write(&path, contents).unwrap();
+
+        let config = AppConfig::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(config.unwrap().server.port, 9191);
+
+        assert!(AppConfig::from_file(std::path::Path::new("/nonexistent/app.toml")).is_err());
+    }
+
+    #[test]
+    fn test_kline_timestamps_are_read_in_market_timezone() {
+        let tz = test_config().trading.timezone;
+        // 09:31 Beijing time is 01:31 UTC
+        assert_eq!(
+            parse_kline_timestamp("2024-03-01 09:31", tz),
+            Some(1_709_256_660_000)
+        );
+        // daily bars sit at the 15:00 close, 07:00 UTC
+        assert_eq!(
+            parse_kline_timestamp("2024-03-01", tz),
+            Some(1_709_276_400_000)
+        );
+        assert_eq!(parse_kline_timestamp("20240301", tz), None);
+    }
+
+    #[test]
+    fn test_quote_and_kline_convert_to_ticks() {
+        let quote = Quote {
+            symbol: "600733.SH".to_string(),
+            timestamp: 1_709_257_260_000,
+            price: Decimal::new(1052, 2),
+            open: None,
+            high: None,
+            low: None,
+            prev_close: None,
+            volume: Some(Decimal::from(12_300)),
+            amount: Some(Decimal::new(1_293_960, 1)),
+            change: None,
+            change_pct: None,
+            bid_price: None,
+            ask_price: None,
+            bid_volume: None,
+            ask_volume: None,
+            source: Some("eastmoney".to_string()),
+        };
+        let tick_sizes = test_config().trading.tick_sizes;
+        let tick = Tick::from_quote(&quote, &tick_sizes);
+        assert_eq!(tick.price, 10.52);
+        assert_eq!(tick.vol, 12_300.0);
+        assert_eq!(tick.amount, Some(129_396.0));
+        assert_eq!(tick.source.as_deref(), Some("eastmoney"));
+
+        // a price off the tick grid is snapped to the symbol's tick size
+        let off_grid = Quote {
+            price: Decimal::new(42_567, 4),
+            ..quote.clone()
+        };
+        assert_eq!(Tick::from_quote(&off_grid, &tick_sizes).price, 4.26);
+        let etf = Quote {
+            symbol: "510300.SH".to_string(),
+            ..off_grid
+        };
+        assert_eq!(Tick::from_quote(&etf, &tick_sizes).price, 4.257);
+
+        let k = kline("600733.SH", 1_709_257_260_000, 1062);
+        let tick = Tick::from_kline(&k, &tick_sizes);
+        assert_eq!((tick.ts, tick.price), (TsMillis(k.timestamp), 10.62));
+        assert_eq!(tick.source, None);
+    }
+
+    #[test]
+    fn test_quote_and_tick_prices_share_one_json_representation() {
+        let quote = serde_json::from_value::<Quote>(serde_json::json!({
+            "symbol": "600733.SH",
+            "timestamp": 1_709_257_260_000_i64,
+            "price": "10.52",
+        }))
+        .unwrap();
+        let tick = Tick::from_quote(&quote, &test_config().trading.tick_sizes);
+
+        let as_numbers = (
+            serde_json::to_value(&quote).unwrap(),
+            serde_json::to_value(&tick).unwrap(),
+        );
+        assert_eq!(as_numbers.0["price"], serde_json::json!(10.52));
+        assert_eq!(as_numbers.1["price"], serde_json::json!(10.52));
+        assert_eq!(as_numbers.1["ts"], serde_json::json!(1_709_257_260_000_i64));
+
+        let as_strings = NumberFormat::String.scope(|| {
+            (
+                serde_json::to_value(&quote).unwrap(),
+                serde_json::to_value(&tick).unwrap(),
+            )
+        });
+        assert_eq!(as_strings.0["price"], serde_json::json!("10.52"));
+        assert_eq!(as_strings.1["price"], serde_json::json!("10.52"));
+        // only values switch form, not timestamps
+        assert_eq!(as_strings.1["ts"], serde_json::json!(1_709_257_260_000_i64));
+
+        // either form reads back, e.g. ticks cached before the format changed
+        let read_back: Tick = serde_json::from_value(as_strings.1).unwrap();
+        assert_eq!(read_back.price, tick.price);
+        assert_eq!(read_back.amount, tick.amount);
+
+        // computed values are rounded instead of showing float noise
+        let event = CrossEvent {
+            index: 0,
+            ts: TsMillis(0),
+            price: 0.1 + 0.2,
+            direction: CrossDirection::Bullish,
+        };
+        let sma = compute_sma_series(&[(TsMillis(0), 10.0), (TsMillis(1), 11.04)], 2);
+        let rebased = rebase_to_common_anchor(&[(
+            "A".to_string(),
+            vec![
+                Bar {
+                    ts: TsMillis(0),
+                    open: 3.0,
+                    high: 3.0,
+                    low: 3.0,
+                    close: 3.0,
+                    volume: 1.0,
+                },
+                Bar {
+                    ts: TsMillis(60_000),
+                    open: 3.0,
+                    high: 3.0,
+                    low: 3.0,
+                    close: 1.0,
+                    volume: 1.0,
+                },
+            ],
+        )]);
+        let (event, sma, rebased) = NumberFormat::String.scope(|| {
+            (
+                serde_json::to_value(&event).unwrap(),
+                serde_json::to_value(&sma).unwrap(),
+                serde_json::to_value(&rebased).unwrap(),
+            )
+        });
+        assert_eq!(event["price"], "0.3");
+        assert_eq!(sma[1]["sma"], "10.52");
+        assert_eq!(
+            rebased["series"]["A"][1],
+            serde_json::json!([60_000, "33.3333333333"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_stores_klines_as_ticks() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("BACKFILL");
+        let source = MockKlines(vec![
+            kline(&symbol, 1_700_000_000_000, 1050),
+            kline(&symbol, 1_700_000_060_000, 1062),
+            kline(&symbol, 1_700_000_120_000, 1041),
+        ]);
+        let day = NaiveDate::from_ymd_opt(2023, 11, 14).unwrap();
+        let tick_sizes = test_config().trading.tick_sizes;
+
+        let inserted = backfill_klines(&source, &storage, &tick_sizes, &symbol, day, day, "1min")
+            .await
+            .unwrap();
+        assert_eq!(inserted, 3);
+
+        let ticks = storage
+            .get_ticks_range(&symbol, 1_700_000_000_000, 1_700_000_180_000)
+            .await
+            .unwrap();
+        let prices: Vec<f64> = ticks.iter().map(|t| t.price).collect();
+        assert_eq!(prices, vec![10.50, 10.62, 10.41]);
+        assert!(ticks.iter().all(|t| t.vol == 1500.0));
+
+        // a second run must not duplicate anything
+        let inserted = backfill_klines(&source, &storage, &tick_sizes, &symbol, day, day, "1min")
+            .await
+            .unwrap();
+        assert_eq!(inserted, 0);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_all_symbols_info_skips_failing_symbols() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let prefix = unique_symbol("INFO");
+        let symbols: Vec<String> = (0..5).map(|i| format!("{}-{}", prefix, i)).collect();
+        let now = chrono::Utc::now().timestamp_millis();
+        for symbol in &symbols {
+            storage.save_tick(&tick(symbol, now, 10.0)).await.unwrap();
+        }
+
+        // a corrupt cached tick makes get_symbol_info fail for that symbol only
+        let client = redis::Client::open(test_redis_url()).unwrap();
+        let mut con = client.get_connection().unwrap();
+        let _: () = redis::cmd("SET")
+            .arg(format!("tick:{}", symbols[2]))
+            .arg("not json")
+            .query(&mut con)
+            .unwrap();
+
+        let app = test_state(storage).trading_app;
+        let returned: Vec<String> = app
+            .get_all_symbols_info(100, 0)
+            .await
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|info| info.symbol)
+            .collect();
+
+        let expected: Vec<String> = symbols
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 2)
+            .map(|(_, s)| s.clone())
+            .collect();
+        assert_eq!(returned, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_symbol_infos_sorted_despite_completion_order() {
+        let symbols: Vec<String> = ["A", "B", "C", "D", "E"].map(String::from).to_vec();
+        let completed = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        // later symbols finish first
+        let infos = collect_sorted_by_symbol(symbols, 5, |symbol| {
+            let completed = completed.clone();
+            async move {
+                let delay = 100 - symbol.as_bytes()[0] as u64;
+                tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                completed.lock().unwrap().push(symbol.clone());
+                if symbol == "C" {
+                    return Err(AppError::data_not_found("no ticks"));
+                }
+                Ok(SymbolInfo {
+                    symbol,
+                    latest_tick: None,
+                    data_points: 0,
+                })
+            }
+        })
+        .await;
+
+        assert_eq!(*completed.lock().unwrap(), ["E", "D", "C", "B", "A"]);
+        let order: Vec<&str> = infos.iter().map(|i| i.symbol.as_str()).collect();
+        assert_eq!(order, ["A", "B", "D", "E"]);
+    }
+
+    #[actix_web::test]
+    async fn test_ohlc_endpoint_buckets_ticks() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("OHLC");
+        // 2024-03-01 09:30:00 UTC, three ticks in the first 5m bucket, two in the next
+        let base = 1_709_285_400_000;
+        let prices = [
+            (0, 10.0),
+            (60_000, 10.4),
+            (120_000, 9.8),
+            (300_000, 10.1),
+            (360_000, 10.3),
+        ];
+        for (offset, price) in prices {
+            storage
+                .save_tick(&tick(&symbol, base + offset, price))
+                .await
+                .unwrap();
+        }
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/ohlc/{}?date=2024-03-01&interval=5m", symbol))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let bars = body["data"].as_array().unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0]["ts"], base);
+        assert_eq!(bars[0]["open"], 10.0);
+        assert_eq!(bars[0]["high"], 10.4);
+        assert_eq!(bars[0]["low"], 9.8);
+        assert_eq!(bars[0]["close"], 9.8);
+        assert_eq!(bars[0]["volume"], 300.0);
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/ohlc/{}?date=2024-03-02", symbol))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"], serde_json::json!([]));
+    }
+
+    #[actix_web::test]
+    async fn test_ticks_endpoint_filters_by_source() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("SRC");
+        // 2024-03-01 09:30:00 UTC
+        let base = 1_709_285_400_000;
+        let ticks: Vec<Tick> = (0..4)
+            .map(|i| Tick {
+                source: Some(if i % 2 == 0 { "eastmoney" } else { "sina" }.to_string()),
+                ..tick(&symbol, base + i * 60_000, 10.0 + i as f64)
+            })
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/ticks/{}?date=2024-03-01", symbol))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 4);
+
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/api/ticks/{}?date=2024-03-01&source=sina",
+                symbol
+            ))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let data = body["data"].as_array().unwrap();
+        assert_eq!(data.len(), 2);
+        assert!(data.iter().all(|t| t["source"] == "sina"));
+        assert_eq!(data[0]["ts"], base + 60_000);
+        assert_eq!(data[1]["price"], 13.0);
+    }
+
+    #[test]
+    fn test_sqlite_busy_write_is_retried() {
+        use anyhow::Context;
+
+        let sqlite_error =
+            |code| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None);
+        let mut calls = 0;
+        let result = retry_busy(3, || {
+            calls += 1;
+            if calls < 3 {
+                // as the write path reports it: wrapped in context
+                Err(sqlite_error(rusqlite::ffi::SQLITE_BUSY)).context("Failed to insert tick")
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // other failures (here a constraint violation) are returned at once
+        let mut calls = 0;
+        let result: anyhow::Result<()> = retry_busy(3, || {
+            calls += 1;
+            Err(sqlite_error(rusqlite::ffi::SQLITE_CONSTRAINT).into())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test(start_paused = true)]
+    async fn test_redis_retry_recovers_from_transient_errors() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let flaky = || async {
+            let n = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if n < 2 {
+                let reset = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+                Err(redis::RedisError::from(reset))
+            } else {
+                Ok(n)
+            }
+        };
+        assert_eq!(retry_transient(3, flaky).await.unwrap(), 2);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // out of attempts: the last error is returned
+        calls.store(0, std::sync::atomic::Ordering::SeqCst);
+        assert!(retry_transient(2, flaky).await.unwrap_err().is_io_error());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // command errors are not connection problems and are not retried
+        let type_errors = std::sync::atomic::AtomicUsize::new(0);
+        let result: redis::RedisResult<()> = retry_transient(3, || async {
+            type_errors.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "bad value",
+            )))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(type_errors.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_outlier_guard_rejects_spike_but_keeps_limit_move() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let band = PriceLimitConfig::default().max_swing_pct();
+        assert!((band - 50.0).abs() < 1e-9);
+        let storage = Arc::try_unwrap(storage)
+            .unwrap()
+            .with_outlier_guard(Some(band), OutlierAction::Reject);
+        let symbol = unique_symbol("SPIKE");
+        let base = 1_700_000_000_000;
+
+        for (i, price) in [10.0, 10.1, 9.9, 10.0].into_iter().enumerate() {
+            let stored = storage
+                .save_tick(&tick(&symbol, base + i as i64 * 1_000, price))
+                .await
+                .unwrap();
+            assert!(stored);
+        }
+        // a 10x misprint is dropped
+        assert!(
+            !storage
+                .save_tick(&tick(&symbol, base + 4_000, 100.0))
+                .await
+                .unwrap()
+        );
+        // a jump to the 10% limit is a normal move
+        assert!(
+            storage
+                .save_tick(&tick(&symbol, base + 5_000, 11.0))
+                .await
+                .unwrap()
+        );
+
+        // a flagged one is kept in the table but out of the ticks read back
+        let flagging = storage.with_outlier_guard(Some(band), OutlierAction::Flag);
+        assert!(
+            !flagging
+                .save_tick(&tick(&symbol, base + 6_000, 100.0))
+                .await
+                .unwrap()
+        );
+
+        let prices: Vec<f64> = flagging
+            .get_ticks_range(&symbol, base, base + 10_000)
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.price)
+            .collect();
+        assert_eq!(prices, vec![10.0, 10.1, 9.9, 10.0, 11.0]);
+    }
+
+    #[tokio::test]
+    async fn test_outlier_guard_accepts_new_level_and_persists_flag() {
+        let symbol = unique_symbol("LEVEL");
+        let path = std::env::temp_dir().join(format!("{}.db", symbol));
+        let storage = match Storage::new(path.to_str().unwrap(), &test_redis_url()) {
+            Ok(storage) => storage.with_outlier_guard(Some(50.0), OutlierAction::Reject),
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                return;
+            }
+        };
+        let base = 1_700_000_000_000;
+        for (i, price) in [10.0, 10.1, 9.9].into_iter().enumerate() {
+            assert!(
+                storage
+                    .save_tick(&tick(&symbol, base + i as i64 * 1_000, price))
+                    .await
+                    .unwrap()
+            );
+        }
+
+        // the third agreeing outlier in a row is taken as a real re-basing
+        let jumps = [20.0, 20.2, 19.9];
+        let mut stored = Vec::new();
+        for (i, price) in jumps.into_iter().enumerate() {
+            stored.push(
+                storage
+                    .save_tick(&tick(&symbol, base + 10_000 + i as i64 * 1_000, price))
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(stored, vec![false, false, true]);
+        // and later ticks at that level are normal
+        assert!(
+            storage
+                .save_tick(&tick(&symbol, base + 20_000, 20.1))
+                .await
+                .unwrap()
+        );
+
+        // the median forgets prices from the previous day
+        let next_day = base + 24 * 60 * 60 * 1000;
+        assert!(
+            storage
+                .save_tick(&tick(&symbol, next_day, 60.0))
+                .await
+                .unwrap()
+        );
+
+        let flagging = storage.with_outlier_guard(Some(50.0), OutlierAction::Flag);
+        assert!(
+            !flagging
+                .save_tick(&tick(&symbol, next_day + 1_000, 600.0))
+                .await
+                .unwrap()
+        );
+        // the flagged price stays out of the median, so this is no outlier
+        assert!(
+            flagging
+                .save_tick(&tick(&symbol, next_day + 2_000, 61.0))
+                .await
+                .unwrap()
+        );
+        let read: Vec<f64> = flagging
+            .get_ticks_range(&symbol, next_day, next_day + 10_000)
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.price)
+            .collect();
+        assert_eq!(read, vec![60.0, 61.0]);
+        let latest = flagging.get_latest_tick(&symbol).await.unwrap().unwrap();
+        assert_eq!(latest.price, 61.0);
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let flagged: Vec<(f64, bool)> = conn
+            .prepare("SELECT price, outlier FROM ticks WHERE symbol = ?1 AND ts >= ?2 ORDER BY ts")
+            .unwrap()
+            .query_map(rusqlite::params![symbol, next_day], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(flagged, vec![(60.0, false), (600.0, true), (61.0, false)]);
+        drop(conn);
+        drop(flagging);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_outlier_streak_survives_only_committed_batches() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let storage = Arc::try_unwrap(storage)
+            .unwrap()
+            .with_outlier_guard(Some(50.0), OutlierAction::Reject)
+            .with_on_duplicate(DuplicateTickAction::Error);
+        let symbol = unique_symbol("ROLLBACK");
+        let base = 1_700_000_000_000;
+        for (i, price) in [10.0, 10.1, 9.9].into_iter().enumerate() {
+            assert!(
+                storage
+                    .save_tick(&tick(&symbol, base + i as i64 * 1_000, price))
+                    .await
+                    .unwrap()
+            );
+        }
+
+        let other = unique_symbol("ROLLBACK");
+        assert!(storage.save_tick(&tick(&other, base, 5.0)).await.unwrap());
+
+        // two outliers, then another symbol's duplicate that fails and rolls the whole
+        // batch back
+        let failed = storage
+            .save_ticks(&[
+                tick(&symbol, base + 10_000, 20.0),
+                tick(&symbol, base + 11_000, 20.2),
+                tick(&other, base, 5.0),
+            ])
+            .await;
+        assert!(failed.is_err());
+
+        // the rolled-back outliers do not count towards confirming a new level
+        let mut stored = Vec::new();
+        for (i, price) in [19.9, 20.1, 20.0].into_iter().enumerate() {
+            stored.push(
+                storage
+                    .save_tick(&tick(&symbol, base + 12_000 + i as i64 * 1_000, price))
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(stored, vec![false, false, true]);
+    }
+
+    #[tokio::test]
+    async fn test_on_duplicate_replaces_ignores_or_errors() {
+        let base = 1_700_000_000_000;
+        for action in [
+            DuplicateTickAction::Replace,
+            DuplicateTickAction::Ignore,
+            DuplicateTickAction::Error,
+        ] {
+            let Some(storage) = test_storage() else {
+                return;
+            };
+            let storage = Arc::try_unwrap(storage).unwrap().with_on_duplicate(action);
+            let symbol = unique_symbol("DUP");
+            assert!(storage.save_tick(&tick(&symbol, base, 10.0)).await.unwrap());
+
+            // a correction for the same (symbol, ts)
+            let correction = storage.save_tick(&tick(&symbol, base, 10.5)).await;
+            let stored = storage
+                .get_ticks_range(&symbol, base, base + 1)
+                .await
+                .unwrap();
+            assert_eq!(stored.len(), 1);
+            match action {
+                DuplicateTickAction::Replace => {
+                    assert!(correction.unwrap());
+                    assert_eq!(stored[0].price, 10.5);
+                }
+                DuplicateTickAction::Ignore => {
+                    assert!(!correction.unwrap());
+                    assert_eq!(stored[0].price, 10.0);
+                }
+                DuplicateTickAction::Error => {
+                    let err = correction.unwrap_err();
+                    assert!(format!("{:#}", err).contains("Duplicate tick"), "{:#}", err);
+                    assert_eq!(AppError::from(err).status_code(), 409);
+                    assert_eq!(stored[0].price, 10.0);
+                    // the latest tick in Redis is untouched too
+                    let latest = storage.get_latest_tick(&symbol).await.unwrap().unwrap();
+                    assert_eq!(latest.price, 10.0);
+
+                    // the new ticks batched with a duplicate are rolled back with it
+                    let batch = storage
+                        .save_ticks(&[
+                            tick(&symbol, base + 1_000, 10.1),
+                            tick(&symbol, base, 10.5),
+                            tick(&symbol, base + 2_000, 10.2),
+                        ])
+                        .await;
+                    assert_eq!(AppError::from(batch.unwrap_err()).status_code(), 409);
+                    let stored = storage
+                        .get_ticks_range(&symbol, base, base + 10_000)
+                        .await
+                        .unwrap();
+                    assert_eq!(stored.len(), 1);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_collapses_identical_consecutive_ticks() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let storage = Arc::try_unwrap(storage).unwrap().with_dedupe_ticks(true);
+        let symbol = unique_symbol("DEDUPE");
+        let base = 1_700_000_000_000;
+
+        storage.save_tick(&tick(&symbol, base, 10.0)).await.unwrap();
+        // same price, same second -> dropped
+        storage
+            .save_tick(&tick(&symbol, base + 200, 10.0))
+            .await
+            .unwrap();
+        storage
+            .save_tick(&tick(&symbol, base + 700, 10.0))
+            .await
+            .unwrap();
+        // price moved -> kept
+        storage
+            .save_tick(&tick(&symbol, base + 800, 10.1))
+            .await
+            .unwrap();
+        // same price but next second -> kept
+        storage
+            .save_tick(&tick(&symbol, base + 1_500, 10.1))
+            .await
+            .unwrap();
+
+        let ticks = storage
+            .get_ticks_range(&symbol, base, base + 2_000)
+            .await
+            .unwrap();
+        let ts: Vec<i64> = ticks.iter().map(|t| t.ts.as_millis()).collect();
+        assert_eq!(ts, vec![base, base + 800, base + 1_500]);
+    }
+
+    #[tokio::test]
+    async fn test_one_trading_day_on_monday_returns_friday() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("TDAY");
+        // 10:00 Shanghai on Thu 2024-02-29 and Fri 2024-03-01
+        let thursday = 1_709_172_000_000;
+        let friday = thursday + 86_400_000;
+        storage
+            .insert_ticks_ignore(&[tick(&symbol, thursday, 9.0), tick(&symbol, friday, 10.0)])
+            .await
+            .unwrap();
+
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let ticks = storage
+            .get_ticks_trading_days_until(&symbol, 1, monday)
+            .await
+            .unwrap();
+        let ts: Vec<i64> = ticks.iter().map(|t| t.ts.as_millis()).collect();
+        assert_eq!(ts, vec![friday]);
+
+        // a Friday holiday pushes the window back to Thursday
+        let friday_date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let calendar = TradingCalendar::new(chrono_tz::Asia::Shanghai, vec![friday_date]);
+        assert!(!calendar.is_trading_day(friday_date));
+        assert_eq!(
+            calendar.trading_days_before(monday, 1),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_market_analysis_without_data_is_not_an_error() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let app = test_state(storage).trading_app;
+        let analysis = app
+            .get_market_analysis(&unique_symbol("EMPTY"), Some(5))
+            .await
+            .unwrap();
+
+        assert!(!analysis.has_data);
+        assert!(analysis.macd_points.is_empty());
+        assert_eq!(analysis.signal_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_market_analysis_below_min_points_reports_no_signals() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("FEW");
+        let now = chrono::Utc::now().timestamp_millis();
+        let prices = [10.0, 10.5, 9.8, 10.6, 9.7, 10.8, 9.6, 10.9];
+        for (i, price) in prices.iter().enumerate() {
+            let ts = now - (prices.len() - i) as i64 * 60_000;
+            storage.save_tick(&tick(&symbol, ts, *price)).await.unwrap();
+        }
+        let analysis_with = |min_points: usize| {
+            let mut config = test_config();
+            config.trading.macd_short = 2;
+            config.trading.macd_long = 3;
+            config.trading.macd_signal = 2;
+            config.trading.macd_warmup = Some(0);
+            config.trading.min_points = Some(min_points);
+            TradingApp::new(storage.clone(), Arc::new(config))
+        };
+
+        let app = analysis_with(prices.len() + 1);
+        let analysis = app.get_market_analysis(&symbol, Some(1)).await.unwrap();
+        assert!(analysis.has_data);
+        assert!(!analysis.sufficient_data);
+        assert_eq!(analysis.macd_points.len(), prices.len());
+        assert!(analysis.cross_events.is_empty());
+        assert_eq!(analysis.signal_count, 0);
+
+        // the same zig-zag does cross once the threshold is met
+        let app = analysis_with(prices.len());
+        let analysis = app.get_market_analysis(&symbol, Some(1)).await.unwrap();
+        assert!(analysis.sufficient_data);
+        assert!(analysis.signal_count > 0);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_analysis_matches_in_memory() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("STREAM");
+        let start = chrono::Utc::now().timestamp_millis() - 3 * 86_400_000;
+        // a zig-zag every 7 minutes that steps up 12% each day, so limits are hit too
+        let ticks: Vec<Tick> = (0..600)
+            .map(|i| {
+                let ts = start + i * 7 * 60_000;
+                let step = 1.12f64.powi(((ts - start) / 86_400_000) as i32);
+                let price = (10.0 + (i as f64 * 0.7).sin() * 0.2) * step;
+                Tick {
+                    vol: 100.0 + (i % 13) as f64 * 10.0,
+                    ..tick(&symbol, ts, price)
+                }
+            })
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        for (strategy, log_scale) in [
+            (StrategyKind::Macd, false),
+            (StrategyKind::Macd, true),
+            (StrategyKind::SmaCross, false),
+        ] {
+            let app_with = |streaming_analysis_days: i64| {
+                let mut config = test_config();
+                config.trading.streaming_analysis_days = streaming_analysis_days;
+                TradingApp::new(storage.clone(), Arc::new(config))
+            };
+            let options = AnalysisOptions {
+                strategy: Some(strategy),
+                log_scale,
+            };
+            let in_memory = app_with(365)
+                .get_market_analysis_with(&symbol, Some(5), options)
+                .await
+                .unwrap();
+            let streamed = app_with(0)
+                .get_market_analysis_with(&symbol, Some(5), options)
+                .await
+                .unwrap();
+
+            assert_eq!(streamed.macd_points.len(), ticks.len());
+            if log_scale {
+                let prices: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+                let expected = compute_macd_series_log(&prices, 12, 26, 9).unwrap();
+                assert_eq!(streamed.macd_points[300].dif, expected[300].dif);
+            }
+            assert!(!streamed.limit_events.is_empty());
+            assert!(streamed.signal_count > 0);
+            assert_eq!(
+                serde_json::to_value(&streamed).unwrap(),
+                serde_json::to_value(&in_memory).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fold_ticks_range_pages_through_every_row_in_order() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("FOLD");
+        // a few pages plus a partial one, and a row right at the end bound
+        let ticks: Vec<Tick> = (0..12_001)
+            .map(|i| tick(&symbol, 1_000 + i, 10.0))
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let seen = storage
+            .fold_ticks_range(&symbol, 1_000, 13_000, Vec::new(), |seen, t| {
+                seen.push(t.ts.as_millis())
+            })
+            .await
+            .unwrap();
+        assert_eq!(seen, (1_000..13_000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_simulated_fallback_disabled_returns_error() {
+        let fetcher = offline_fetcher(false);
+
+        let err = fetcher.get_quote("600733").await.unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Api {
+                code: ErrorCode::DataSourceError,
+                ..
+            }
+        ));
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let err = fetcher
+            .get_kline_data("600733", day, day + chrono::Duration::days(5), "day")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Api { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_simulated_fallback_enabled_serves_fake_data() {
+        let fetcher = offline_fetcher(true);
+
+        let quote = fetcher.get_quote("600733").await.unwrap();
+        assert_eq!(quote.symbol, "600733.SH");
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let klines = fetcher
+            .get_kline_data("600733", day, day + chrono::Duration::days(5), "day")
+            .await
+            .unwrap();
+        assert!(!klines.is_empty());
+    }
+
+    #[test]
+    fn test_market_today_differs_from_utc_around_midnight() {
+        // 2024-03-04 17:30 UTC is already 01:30 on the 5th in Shanghai.
+        let instant = chrono::Utc.with_ymd_and_hms(2024, 3, 4, 17, 30, 0).unwrap();
+        assert_eq!(
+            instant.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 3, 4).unwrap()
+        );
+        assert_eq!(
+            market_date(chrono_tz::Asia::Shanghai, instant),
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        );
+
+        let tz = test_config().trading.timezone;
+        assert_eq!(tz, chrono_tz::Asia::Shanghai);
+        let today = market_today(tz);
+        let utc_today = chrono::Utc::now().date_naive();
+        assert!(today == utc_today || today == utc_today.succ_opt().unwrap());
+    }
+
+    #[test]
+    fn test_ts_millis_round_trips_through_chrono_serde_and_sqlite() {
+        let instant = chrono::Utc.with_ymd_and_hms(2024, 3, 1, 1, 41, 0).unwrap();
+        let ts = TsMillis::from_datetime(&instant.with_timezone(&chrono_tz::Asia::Shanghai));
+        assert_eq!(ts, TsMillis(1_709_257_260_000));
+        assert_eq!(ts.to_datetime(), Some(instant));
+        assert_eq!(TsMillis(i64::MAX).to_datetime(), None);
+
+        assert_eq!(TsMillis::from_secs(ts.as_secs()), ts);
+        // seconds floor, so a pre-epoch instant is not rounded up to the next second
+        assert_eq!(TsMillis(-1).as_secs(), -1);
+        assert_eq!((ts + 1_500) - ts, 1_500);
+
+        assert_eq!(serde_json::to_string(&ts).unwrap(), "1709257260000");
+        assert_eq!(
+            serde_json::from_str::<TsMillis>("1709257260000").unwrap(),
+            ts
+        );
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let stored: TsMillis = conn.query_row("SELECT ?1", [ts], |row| row.get(0)).unwrap();
+        assert_eq!(stored, ts);
+    }
+
+    #[test]
+    fn test_parse_json_decimal_keeps_precision() {
+        let precision = test_config().data_source.precision;
+
+        let price: serde_json::Value = serde_json::from_str("12.345").unwrap();
+        assert_eq!(
+            parse_json_decimal(&price, "f43", precision.price).unwrap(),
+            Decimal::new(12345, 3)
+        );
+
+        let volume: serde_json::Value = serde_json::from_str("9007199254740993").unwrap();
+        assert_eq!(
+            parse_json_decimal(&volume, "f48", precision.volume).unwrap(),
+            Decimal::from(9_007_199_254_740_993_i64)
+        );
+
+        let text = serde_json::Value::String("3.14159".to_string());
+        assert_eq!(
+            parse_json_decimal(&text, "f46", precision.price).unwrap(),
+            Decimal::new(3142, 3)
+        );
+    }
+
+    /// Minimal EastMoney stand-in: answers the quote request for 600733 and
+    /// returns 404 for anything else.
+    async fn spawn_mock_eastmoney() -> String {
+        spawn_counting_mock_eastmoney().await.0
+    }
+
+    /// `spawn_mock_eastmoney` that also counts the requests it receives.
+    async fn spawn_counting_mock_eastmoney() -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let mut buf = vec![0u8; 8192];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let response = if request.contains("secid=1.600733") {
+                    let body = r#"{"data":{"f43":12.34,"f48":1000,"f58":"北汽蓝谷"}}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (format!("http://{}", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn test_forced_quote_skips_cache_but_refreshes_it() {
+        use std::sync::atomic::Ordering;
+
+        let (base_url, requests) = spawn_counting_mock_eastmoney().await;
+        let mut config = test_config();
+        config.data_source.eastmoney.enabled = true;
+        config.data_source.eastmoney.base_url = base_url;
+        config.data_source.baidu.enabled = false;
+        config.data_source.sina.enabled = false;
+        config.data_source.cache.quote_ttl = 60;
+        let fetcher = DataFetcher::new(Arc::new(config));
+
+        fetcher.get_quote("600733").await.unwrap();
+        fetcher.get_quote("600733").await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let quote = fetcher.get_quote_with("600733", true).await.unwrap();
+        assert_eq!(quote.price, Decimal::new(1234, 2));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // the forced result went back into the cache
+        fetcher.get_quote("600733").await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_meta_lookup_by_bare_code_is_served_from_storage() {
+        use std::sync::atomic::Ordering;
+
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let (base_url, requests) = spawn_counting_mock_eastmoney().await;
+        let mut config = test_config();
+        config.data_source.eastmoney.enabled = true;
+        config.data_source.eastmoney.base_url = base_url;
+        let mut state = test_state(storage);
+        state.fetcher = Arc::new(DataFetcher::new(Arc::new(config)));
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::get().uri("/api/meta/600733").to_request();
+            let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+            assert_eq!(body["data"]["symbol"], "600733.SH");
+            assert_eq!(body["data"]["name"], "北汽蓝谷");
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_field_mapping_override_changes_key_read() {
+        let mut config = test_config();
+        config.data_source.eastmoney.enabled = true;
+        config.data_source.eastmoney.base_url = spawn_mock_eastmoney().await;
+        config.data_source.baidu.enabled = false;
+        config.data_source.sina.enabled = false;
+        assert_eq!(config.data_source.eastmoney.fields.code("price"), "f43");
+        let fetcher = DataFetcher::new(Arc::new(config.clone()));
+        let quote = fetcher.get_quote("600733").await.unwrap();
+        assert_eq!(quote.price, Decimal::new(1234, 2));
+
+        config.data_source.eastmoney.fields =
+            serde_json::from_value(serde_json::json!({ "price": "f48" })).unwrap();
+        assert_eq!(config.data_source.eastmoney.fields.code("open"), "f46");
+        let fetcher = DataFetcher::new(Arc::new(config));
+        let quote = fetcher.get_quote("600733").await.unwrap();
+        assert_eq!(quote.price, Decimal::from(1000));
+    }
+
+    #[test]
+    fn test_response_cache_evicts_least_recently_used_past_cap() {
+        let mut cache = ResponseCache::new(3);
+        let now = 1_000_000;
+        for key in ["a", "b", "c"] {
+            cache.insert(key, serde_json::json!(key), 60_000, now);
+        }
+        // reading `a` makes `b` the least recently used
+        assert!(cache.get("a", now).is_some());
+        cache.insert("d", serde_json::json!("d"), 60_000, now);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get("b", now), None);
+
+        for key in ["e", "f"] {
+            cache.insert(key, serde_json::json!(key), 60_000, now);
+        }
+        assert_eq!(cache.len(), 3);
+        for key in ["a", "c"] {
+            assert_eq!(cache.get(key, now), None, "{} should be evicted", key);
+        }
+        for key in ["d", "e", "f"] {
+            assert_eq!(cache.get(key, now), Some(serde_json::json!(key)));
+        }
+
+        // TTL expiry still applies, and frees room before anything is evicted
+        assert_eq!(cache.get("d", now + 60_000), None);
+        cache.insert("g", serde_json::json!("g"), 1_000, now + 60_000);
+        assert_eq!(cache.len(), 1);
+        cache.insert("h", serde_json::json!("h"), 0, now + 60_000);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_configured_kline_ttl_governs_cache() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // one daily kline dated before the range, so paging stops after a request
+        let requests = Arc::new(AtomicUsize::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = vec![0u8; 8192];
+                let _ = socket.read(&mut buf).await;
+                let body = r#"{"data":{"klines":["2024-03-01,10.0,10.5,10.6,9.9,1000"]}}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let fetch_twice = |kline_ttl: i64| {
+            let mut config = test_config();
+            config.data_source.eastmoney.enabled = true;
+            config.data_source.eastmoney.base_url = format!("http://{}", addr);
+            config.data_source.cache.kline_ttl = kline_ttl;
+            let fetcher = DataFetcher::new(Arc::new(config));
+            async move {
+                for _ in 0..2 {
+                    let klines = fetcher.get_kline_data("600733", day, day, "day").await;
+                    assert_eq!(klines.unwrap().len(), 1);
+                }
+            }
+        };
+
+        fetch_twice(3600).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        fetch_twice(0).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_kline_pages_are_each_fetched_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let tz = test_config().trading.timezone;
+        let split = parse_kline_timestamp("2024-03-05", tz).unwrap();
+        let first = parse_kline_timestamp("2024-03-01", tz).unwrap();
+        // newest page first, then the page before it, then nothing; the older page
+        // repeats its boundary row, as a sloppy upstream might
+        let requests = Arc::new(AtomicUsize::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = vec![0u8; 8192];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let end: i64 = request
+                    .split("&end=")
+                    .nth(1)
+                    .and_then(|rest| rest.split(['&', ' ']).next())
+                    .and_then(|end| end.parse().ok())
+                    .unwrap();
+                let rows: &[&str] = if end >= split {
+                    &[
+                        "2024-03-05,10.2,10.3,10.4,10.1,300",
+                        "2024-03-06,10.3,10.4,10.5,10.2,400",
+                    ]
+                } else if end >= first {
+                    &[
+                        "2024-03-01,10.0,10.1,10.2,9.9,100",
+                        "2024-03-04,10.1,10.2,10.3,10.0,200",
+                        "2024-03-05,10.2,10.3,10.4,10.1,300",
+                    ]
+                } else {
+                    &[]
+                };
+                let body = serde_json::json!({ "data": { "klines": rows } }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut config = test_config();
+        config.data_source.eastmoney.enabled = true;
+        config.data_source.eastmoney.base_url = format!("http://{}", addr);
+        config.data_source.cache.kline_ttl = 0;
+        let fetcher = DataFetcher::new(Arc::new(config));
+        let klines = fetcher
+            .get_kline_data(
+                "600733",
+                NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 6).unwrap(),
+                "day",
+            )
+            .await
+            .unwrap();
+
+        let volumes: Vec<Decimal> = klines.iter().map(|k| k.volume).collect();
+        assert_eq!(volumes, [100, 200, 300, 400].map(Decimal::from));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_source_statuses_track_success_and_failure() {
+        let mut config = test_config();
+        config.data_source.eastmoney.enabled = true;
+        config.data_source.eastmoney.base_url = spawn_mock_eastmoney().await;
+        config.data_source.baidu.enabled = false;
+        config.data_source.sina.enabled = false;
+        config.data_source.allow_simulated_fallback = false;
+        let fetcher = DataFetcher::new(Arc::new(config));
+
+        let statuses = fetcher.source_statuses().await;
+        assert_eq!(statuses.len(), 3);
+        assert!(statuses[0].last_success_ts.is_none());
+
+        fetcher.get_quote("600733").await.unwrap();
+        assert!(fetcher.get_quote("000001").await.is_err());
+
+        let statuses = fetcher.source_statuses().await;
+        let eastmoney = &statuses[0];
+        assert_eq!(eastmoney.name, "EastMoney");
+        assert!(eastmoney.enabled);
+        assert!(eastmoney.last_success_ts.is_some());
+        assert!(eastmoney.last_error.is_some());
+
+        let baidu = &statuses[1];
+        assert!(!baidu.enabled);
+        assert!(baidu.last_success_ts.is_none() && baidu.last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_requests_never_exceed_concurrency_cap() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // answers every quote after a pause, recording the most requests seen at once
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (in_flight_srv, peak_srv) = (in_flight.clone(), peak.clone());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (in_flight, peak) = (in_flight_srv.clone(), peak_srv.clone());
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 8192];
+                    let _ = socket.read(&mut buf).await;
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let body = r#"{"data":{"f43":12.34,"f48":1000}}"#;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        let mut config = test_config();
+        config.data_source.eastmoney.enabled = true;
+        config.data_source.eastmoney.base_url = format!("http://{}", addr);
+        config.data_source.baidu.enabled = false;
+        config.data_source.sina.enabled = false;
+        config.data_source.max_concurrent_requests = 3;
+        let fetcher = DataFetcher::new(Arc::new(config));
+
+        // distinct symbols so none is served from the quote cache
+        let quotes = futures::future::join_all((0..12).map(|i| {
+            let fetcher = fetcher.clone();
+            async move { fetcher.get_quote(&format!("6000{:02}", i)).await }
+        }))
+        .await;
+        assert!(quotes.iter().all(|q| q.is_ok()));
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak <= 3, "{} requests were in flight at once", peak);
+        assert!(peak > 1);
+    }
+
+    #[actix_web::test]
+    async fn test_writes_from_the_other_run_mode_are_dropped() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("OWNED");
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage.clone())))
+                .configure(configure_api),
+        )
+        .await;
+
+        // the service starts in Sim mode: a live (Real) write is rejected
+        assert!(
+            !storage
+                .save_tick_as(&tick(&symbol, 1_000, 10.0), RunMode::Real)
+                .await
+                .unwrap()
+        );
+        assert!(storage.get_latest_tick(&symbol).await.unwrap().is_none());
+        assert!(
+            storage
+                .save_tick_as(&tick(&symbol, 2_000, 11.0), RunMode::Sim)
+                .await
+                .unwrap()
+        );
+
+        let req = TestRequest::post().uri("/api/set_mode/real").to_request();
+        assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+        assert!(
+            !storage
+                .save_tick_as(&tick(&symbol, 3_000, 12.0), RunMode::Sim)
+                .await
+                .unwrap()
+        );
+        let latest = storage.get_latest_tick(&symbol).await.unwrap().unwrap();
+        assert_eq!(latest.ts, TsMillis(2_000));
+    }
+
+    #[actix_web::test]
+    async fn test_mode_history_records_transitions_in_order() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        for mode in ["real", "real", "sim", "real"] {
+            let req = TestRequest::post()
+                .uri(&format!("/api/set_mode/{}", mode))
+                .to_request();
+            assert_eq!(call_service(&app, req).await.status(), StatusCode::OK);
+        }
+
+        let req = TestRequest::get().uri("/api/mode_history").to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let history = body["data"].as_array().unwrap();
+        // the repeated "real" does not produce an entry
+        let pairs: Vec<(&str, &str)> = history
+            .iter()
+            .map(|t| (t["from"].as_str().unwrap(), t["to"].as_str().unwrap()))
+            .collect();
+        assert_eq!(pairs, [("Sim", "Real"), ("Real", "Sim"), ("Sim", "Real")]);
+        assert!(history[0]["ts"].as_i64() <= history[2]["ts"].as_i64());
+    }
+
+    #[test]
+    fn test_signal_warmup_suppresses_early_crossovers() {
+        let point = |i: i64, macd: f64| MACDPoint {
+            ts: TsMillis(i),
+            price: 10.0,
+            dif: 0.0,
+            dea: 0.0,
+            macd,
+            ema_short: None,
+            ema_long: None,
+            warming_up: false,
+        };
+        // bullish cross at index 1, bearish cross at index 6
+        let macds = [-0.1, 0.2, 0.3, 0.2, 0.1, 0.05, -0.1, -0.2];
+        let points: Vec<MACDPoint> = macds
+            .iter()
+            .enumerate()
+            .map(|(i, m)| point(i as i64, *m))
+            .collect();
+
+        assert_eq!(
+            TradingApp::count_cross_events(&macd_cross_events(&points, 0)),
+            (1, 1)
+        );
+        assert_eq!(
+            TradingApp::count_cross_events(&macd_cross_events(&points, 5)),
+            (0, 1)
+        );
+        assert_eq!(
+            TradingApp::count_cross_events(&macd_cross_events(&points, 7)),
+            (0, 0)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_report_contains_all_sections() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("RPT");
+        let now = chrono::Utc::now().timestamp_millis();
+        for i in 0..40 {
+            let price = 10.0 + (i as f64 / 3.0).sin();
+            storage
+                .save_tick(&tick(&symbol, now - (40 - i) * 60_000, price))
+                .await
+                .unwrap();
+        }
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/report/{}", symbol))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let disposition = resp
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(disposition.starts_with("attachment"));
+        assert!(disposition.contains(&format!("{}-report.json", symbol)));
+
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        for section in [
+            "symbol",
+            "generated_at",
+            "latest_tick",
+            "summary",
+            "cross_events",
+            "macd_points",
+        ] {
+            assert!(body.get(section).is_some(), "missing section {}", section);
+        }
+        assert_eq!(body["macd_points"].as_array().unwrap().len(), 40);
+        assert!(body["summary"]["day_change"].is_number());
+        assert_eq!(body["latest_tick"]["symbol"], symbol);
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/report/{}?format=pdf", symbol))
+            .to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_recent_ticks_served_from_redis_list() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("RCT");
+        let base = 1_709_285_400_000;
+        for i in 0..5 {
+            storage
+                .save_tick(&tick(&symbol, base + i * 1000, 10.0 + i as f64))
+                .await
+                .unwrap();
+        }
+        // written to SQLite only, so it is invisible to the cached path
+        storage
+            .insert_ticks_ignore(&[tick(&symbol, base + 10_000, 99.0)])
+            .await
+            .unwrap();
+
+        let recent = storage.get_recent_ticks_cached(&symbol, 3).await.unwrap();
+        let prices: Vec<f64> = recent.iter().map(|t| t.price).collect();
+        assert_eq!(prices, [12.0, 13.0, 14.0]);
+
+        // more than the list holds: falls back to SQLite, which has the extra tick
+        let recent = storage.get_recent_ticks_cached(&symbol, 10).await.unwrap();
+        assert_eq!(recent.len(), 6);
+        assert_eq!(recent.last().unwrap().price, 99.0);
+
+        // a replaced tick is listed twice but returned once, with its new price
+        storage
+            .save_tick(&tick(&symbol, base + 4_000, 15.0))
+            .await
+            .unwrap();
+        let recent = storage.get_recent_ticks_cached(&symbol, 3).await.unwrap();
+        let prices: Vec<f64> = recent.iter().map(|t| t.price).collect();
+        assert_eq!(prices, [12.0, 13.0, 15.0]);
+
+        let client = redis::Client::open(test_redis_url()).unwrap();
+        let mut con = client.get_connection().unwrap();
+        let ttl: i64 = redis::cmd("TTL")
+            .arg(format!("ticks:{}", symbol))
+            .query(&mut con)
+            .unwrap();
+        assert!(ttl > 0, "ticks list TTL is {}", ttl);
+
+        // requests past the list's cap are clamped to it
+        let recent = storage
+            .get_recent_ticks_cached(&symbol, usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 6);
+    }
+
+    /// Only built by `cargo test --no-default-features`.
+    #[cfg(not(feature = "redis"))]
+    #[actix_web::test]
+    async fn test_sqlite_only_storage_serves_ticks_end_to_end() {
+        // nothing listens here; without the redis feature it is never dialled
+        let storage = Arc::new(Storage::new(":memory:", "redis://127.0.0.1:1/0").unwrap());
+        let symbol = unique_symbol("NOREDIS");
+        let now = chrono::Utc::now().timestamp_millis();
+        let ticks: Vec<Tick> = (0..5)
+            .map(|i| tick(&symbol, now - (10 - i) * 60_000, 10.0 + i as f64))
+            .collect();
+        assert_eq!(storage.save_ticks(&ticks).await.unwrap(), [true; 5]);
+
+        let latest = storage.get_latest_tick(&symbol).await.unwrap().unwrap();
+        assert_eq!(latest.price, 14.0);
+        let missing = unique_symbol("NODATA");
+        let latest = storage
+            .get_latest_ticks(&[symbol.clone(), missing.clone()])
+            .await
+            .unwrap();
+        assert_eq!(latest[&symbol].as_ref().unwrap().price, 14.0);
+        assert!(latest[&missing].is_none());
+        let recent = storage.get_recent_ticks_cached(&symbol, 3).await.unwrap();
+        let prices: Vec<f64> = recent.iter().map(|t| t.price).collect();
+        assert_eq!(prices, [12.0, 13.0, 14.0]);
+        storage.ping_redis().await.unwrap();
+        assert_eq!(storage.query_counts().redis, 0);
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        let get = |uri: &str| TestRequest::get().uri(uri).to_request();
+        let resp = call_service(&app, get(&format!("/api/history/{}?last=5", symbol))).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(body["data"]["points"].as_array().unwrap().len(), 5);
+        let resp = call_service(&app, get("/api/readyz")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_batched_save_fills_redis_for_every_symbol() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let (a, b) = (unique_symbol("PIPEA"), unique_symbol("PIPEB"));
+        let base = 1_709_285_400_000;
+        let ticks: Vec<Tick> = (0..6)
+            .map(|i| {
+                let symbol = if i % 2 == 0 { &a } else { &b };
+                tick(symbol, base + i * 1000, 10.0 + i as f64)
+            })
+            .collect();
+
+        let saved = storage.save_ticks(&ticks).await.unwrap();
+        assert_eq!(saved, [true; 6]);
+
+        let latest = storage.get_latest_tick(&a).await.unwrap().unwrap();
+        assert_eq!(latest.price, 14.0);
+        let latest = storage.get_latest_tick(&b).await.unwrap().unwrap();
+        assert_eq!(latest.price, 15.0);
+        let recent = storage.get_recent_ticks_cached(&b, 3).await.unwrap();
+        let prices: Vec<f64> = recent.iter().map(|t| t.price).collect();
+        assert_eq!(prices, [11.0, 13.0, 15.0]);
+
+        // the single-tick path goes through the same pipeline
+        assert!(
+            storage
+                .save_tick(&tick(&a, base + 10_000, 20.0))
+                .await
+                .unwrap()
+        );
+        let latest = storage.get_latest_tick(&a).await.unwrap().unwrap();
+        assert_eq!(latest.price, 20.0);
+    }
+
+    #[test]
+    fn test_sma_series_matches_input_length() {
+        let points: Vec<(TsMillis, f64)> = (0..7).map(|i| (TsMillis(i), i as f64)).collect();
+        let sma = compute_sma_series(&points, 3);
+        assert_eq!(sma.len(), points.len());
+        // expanding mean until the window fills, then a true 3-point average
+        assert_eq!(sma[1].sma, 0.5);
+        assert_eq!(sma[6].sma, 5.0);
+        assert!(compute_sma_series(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_sma_golden_cross() {
+        // flat, then a dip, then a sharp rally: the short SMA crosses above the long once
+        let prices = [
+            10.0, 10.0, 10.0, 10.0, 10.0, 9.0, 8.0, 8.0, 9.0, 11.0, 13.0, 14.0,
+        ];
+        let points: Vec<(TsMillis, f64)> = prices
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (TsMillis(i as i64), *p))
+            .collect();
+        let short = compute_sma_series(&points, 2);
+        let long = compute_sma_series(&points, 5);
+
+        let events = sma_cross_events(&short, &long, 5);
+        let golden: Vec<&CrossEvent> = events
+            .iter()
+            .filter(|e| e.direction == CrossDirection::Bullish)
+            .collect();
+        assert_eq!(golden.len(), 1);
+        assert_eq!(golden[0].index, 9);
+        assert_eq!(golden[0].price, 11.0);
+        assert!(events.iter().all(|e| e.index >= 5));
+    }
+
+    #[test]
+    fn test_bucket_start_bars_land_on_interval_boundaries() {
+        let five_min = 5 * 60_000;
+        // 1m43s into a 5-minute bucket (like 09:31:43 in 09:30-09:35), then the next bucket
+        let open = 1_700_000_000_000 - 1_700_000_000_000 % five_min + 103_000;
+        let ticks: Vec<Tick> = [0, 20_000, 200_000, 260_000]
+            .iter()
+            .enumerate()
+            .map(|(i, offset)| tick("BARTS", open + offset, 10.0 + i as f64))
+            .collect();
+
+        let floored = aggregate_bars_stamped(&ticks, five_min, BarTimestamp::BucketStart);
+        assert_eq!(floored.len(), 2);
+        for bar in &floored {
+            assert_eq!(bar.ts.as_millis() % five_min, 0);
+        }
+        assert_eq!(floored[0].ts, TsMillis(open - 103_000));
+        assert_eq!(floored[1].ts, floored[0].ts + five_min);
+        assert_eq!((floored[0].open, floored[0].close), (10.0, 11.0));
+
+        // the raw first-tick label is still available
+        let raw = aggregate_bars_stamped(&ticks, five_min, BarTimestamp::FirstTick);
+        assert_eq!(raw[0].ts, TsMillis(open));
+        assert_eq!(raw[1].ts, TsMillis(open + 200_000));
+    }
+
+    #[test]
+    fn test_detect_price_limits_flags_limit_up_bar() {
+        let bar = |ts: i64, high: f64, low: f64| Bar {
+            ts: TsMillis(ts),
+            open: low,
+            high,
+            low,
+            close: high,
+            volume: 100.0,
+        };
+        // prev close 9.87: +10% is 10.857, which the exchange rounds to 10.86
+        let bars = [
+            bar(1, 10.20, 9.90),
+            bar(2, 10.86, 10.50),
+            bar(3, 10.86, 10.86),
+        ];
+        let events = detect_price_limits(&bars, 9.87, 10.0);
+        assert_eq!(
+            events,
+            vec![(TsMillis(2), LimitKind::Up), (TsMillis(3), LimitKind::Up)]
+        );
+
+        // the same move stays inside a STAR board's 20% band
+        let star_pct = test_config()
+            .trading
+            .price_limits
+            .pct_for("688001.SH", None);
+        assert!(detect_price_limits(&bars, 9.87, star_pct).is_empty());
+    }
+
+    #[test]
+    fn test_wilder_rsi_matches_textbook_values() {
+        let mut wilder = EMA::new_wilder(14);
+        wilder.next(0.0);
+        assert!((wilder.next(14.0) - 1.0).abs() < 1e-12);
+
+        // the 14-period worked example from Wilder's RSI worksheets (StockCharts),
+        // computed without rounding intermediate averages
+        let closes = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45,
+            45.78, 45.35, 44.03, 44.18, 44.22, 44.57, 43.42, 42.66, 43.13,
+        ];
+        let points: Vec<(TsMillis, f64)> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (TsMillis(i as i64), *c))
+            .collect();
+        let rsi = compute_rsi_series(&points, 14);
+        assert_eq!(rsi.len(), closes.len() - 14);
+        assert_eq!(rsi[0].ts, TsMillis(14));
+        for (i, expected) in [(0, 70.46), (1, 66.25), (5, 57.92), (12, 40.02), (18, 37.79)] {
+            assert!(
+                (rsi[i].rsi - expected).abs() < 0.01,
+                "rsi[{}] = {}, expected {}",
+                i,
+                rsi[i].rsi,
+                expected
+            );
+        }
+
+        // ATR uses the same smoothing: constant 1.0 ranges stay at 1.0
+        let bars: Vec<Bar> = (0..5)
+            .map(|i| Bar {
+                ts: TsMillis(i),
+                open: 10.0,
+                high: 10.5,
+                low: 9.5,
+                close: 10.0,
+                volume: 0.0,
+            })
+            .collect();
+        let atr = compute_atr_series(&bars, 3);
+        assert_eq!(atr.len(), 3);
+        assert!(atr.iter().all(|p| (p.atr - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_typical_price_macd_differs_from_close_price_macd() {
+        // closes pinned near the lows while the highs drift up
+        let bars: Vec<Bar> = (0..60)
+            .map(|i| {
+                let close = 10.0 + (i as f64 / 6.0).sin();
+                Bar {
+                    ts: TsMillis(i * 60_000),
+                    open: close,
+                    high: close + 0.2 + i as f64 * 0.02,
+                    low: close - 0.1,
+                    close,
+                    volume: 100.0,
+                }
+            })
+            .collect();
+        let macd = |source| compute_macd_series(&bar_prices(&bars, source), 12, 26, 9);
+        let (close, typical, weighted) = (
+            macd(PriceSource::Close),
+            macd(PriceSource::Typical),
+            macd(PriceSource::Weighted),
+        );
+
+        assert_eq!(PriceSource::default(), PriceSource::Close);
+        assert_eq!(close[59].price, bars[59].close);
+        assert!(
+            (typical[59].price - (bars[59].high + bars[59].low + bars[59].close) / 3.0).abs()
+                < 1e-12
+        );
+        assert_eq!(typical.len(), close.len());
+        // the rising highs lift the typical-price trend above the close-price one
+        assert!(typical[59].dif > close[59].dif);
+        assert!(weighted[59].dif > close[59].dif && weighted[59].dif < typical[59].dif);
+    }
+
+    #[test]
+    fn test_adx_separates_trending_from_choppy_series() {
+        let bars = |close: &dyn Fn(i64) -> f64| -> Vec<Bar> {
+            (0..80)
+                .map(|i| Bar {
+                    ts: TsMillis(i),
+                    open: close(i),
+                    high: close(i) + 0.3,
+                    low: close(i) - 0.3,
+                    close: close(i),
+                    volume: 0.0,
+                })
+                .collect()
+        };
+        let trending = compute_adx(&bars(&|i| 10.0 + i as f64 * 0.1), 14);
+        let choppy = compute_adx(&bars(&|i| if i % 2 == 0 { 10.0 } else { 10.5 }), 14);
+
+        // +DI/-DI after `period` moves, ADX after `period` DX values on top
+        assert_eq!(trending.len(), 80);
+        assert!(trending[13].plus_di.is_none() && trending[14].plus_di.is_some());
+        assert!(trending[..27].iter().all(|p| p.adx.is_none()));
+        assert!(trending[27].adx.is_some());
+
+        let last_adx = |points: &[ADXPoint]| points.last().unwrap().adx.unwrap();
+        assert!(
+            last_adx(&trending) > 50.0,
+            "trending ADX {}",
+            last_adx(&trending)
+        );
+        assert!(last_adx(&choppy) < 20.0, "choppy ADX {}", last_adx(&choppy));
+        let last = trending.last().unwrap();
+        assert!(last.plus_di.unwrap() > last.minus_di.unwrap());
+    }
+
+    #[test]
+    fn test_turnover_vwap_differs_from_share_vwap() {
+        // equal share volume at rising prices: turnover weights the pricier ticks more
+        let ticks: Vec<Tick> = [10.0, 11.0, 12.0, 13.0]
+            .iter()
+            .enumerate()
+            .map(|(i, price)| tick("VWAP", i as i64, *price))
+            .collect();
+        let shares = compute_vwap(&ticks, VolumeBasis::Shares).unwrap();
+        let turnover = compute_vwap(&ticks, VolumeBasis::Turnover).unwrap();
+        assert!((shares - 11.5).abs() < 1e-9);
+        assert!(turnover > shares);
+
+        // a reported amount is used as-is instead of price * vol
+        let mut reported = ticks.clone();
+        reported[0].amount = Some(10.0 * 100.0 * 50.0);
+        assert!(compute_vwap(&reported, VolumeBasis::Turnover).unwrap() < shares);
+        assert!(compute_vwap(&[], VolumeBasis::Shares).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_symbols_set_tracks_inserted_ticks() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        assert!(storage.get_symbols().await.unwrap().is_empty());
+
+        let (a, b, c) = ("600733.SH", "000001.SZ", "300750.SZ");
+        storage
+            .save_ticks(&[tick(a, 1_000, 10.0), tick(b, 1_000, 11.0)])
+            .await
+            .unwrap();
+        // more ticks for a known symbol, a replaced duplicate and a bulk insert
+        storage.save_tick(&tick(a, 2_000, 10.1)).await.unwrap();
+        storage.save_tick(&tick(b, 1_000, 11.1)).await.unwrap();
+        storage
+            .insert_ticks_ignore(&[tick(a, 3_000, 10.2), tick(c, 1_000, 200.0)])
+            .await
+            .unwrap();
+
+        assert_eq!(storage.get_symbols().await.unwrap(), vec![b, c, a]);
+        let (page, total) = storage.get_symbols_page(2, 1).await.unwrap();
+        assert_eq!((page, total), (vec![c.to_string(), a.to_string()], 3));
+    }
+
+    #[tokio::test]
+    async fn test_existing_tick_rows_survive_amount_migration() {
+        let symbol = unique_symbol("MIGRATE");
+        let path = std::env::temp_dir().join(format!("{}.db", symbol));
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE ticks (ts INTEGER NOT NULL, symbol TEXT NOT NULL, price REAL, vol REAL,
+                    PRIMARY KEY (symbol, ts)) WITHOUT ROWID;",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO ticks VALUES (1000, ?1, 10.0, 100.0)",
+                [&symbol],
+            )
+            .unwrap();
+        }
+        let storage = match Storage::new(path.to_str().unwrap(), &test_redis_url()) {
+            Ok(storage) => storage,
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                std::fs::remove_file(&path).ok();
+                return;
+            }
+        };
+
+        let old = storage.get_latest_tick(&symbol).await.unwrap().unwrap();
+        assert_eq!(old.price, 10.0);
+        assert_eq!(old.amount, None);
+        // the symbols table is filled from the ticks already there
+        assert_eq!(storage.get_symbols().await.unwrap(), vec![symbol.clone()]);
+
+        let mut new = tick(&symbol, 2000, 10.5);
+        new.amount = Some(1050.0);
+        storage.save_tick(&new).await.unwrap();
+        let latest = storage.get_latest_tick(&symbol).await.unwrap().unwrap();
+        assert_eq!(latest.amount, Some(1050.0));
+
+        drop(storage);
+        // reopening must not try to add the column again
+        assert!(Storage::new(path.to_str().unwrap(), &test_redis_url()).is_ok());
+        for suffix in ["", "-wal", "-shm"] {
+            std::fs::remove_file(format!("{}{}", path.display(), suffix)).ok();
+        }
+    }
+
+    #[test]
+    fn test_sqlite_burst_does_not_need_tokio_blocking_threads() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let storage = Arc::new(Arc::into_inner(storage).unwrap().with_sqlite_workers(2));
+        // the runtime's only blocking thread stays busy for the whole burst
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .max_blocking_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let (release, hold) = std::sync::mpsc::channel::<()>();
+
+        runtime.block_on(async {
+            let blocker = tokio::task::spawn_blocking(move || hold.recv().ok());
+            let symbol = unique_symbol("BURST");
+            let queries = (0..200).map(|i| {
+                let storage = storage.clone();
+                let symbol = symbol.clone();
+                async move {
+                    storage
+                        .insert_ticks_ignore(&[tick(&symbol, i * 1_000, 10.0)])
+                        .await?;
+                    storage.count_ticks(&symbol, 0, i64::MAX).await
+                }
+            });
+            let results = tokio::time::timeout(
+                std::time::Duration::from_secs(10),
+                futures::future::join_all(queries),
+            )
+            .await
+            .expect("queries should not wait for tokio's blocking pool");
+            assert!(results.iter().all(|r| r.is_ok()));
+            assert_eq!(
+                storage.count_ticks(&symbol, 0, i64::MAX).await.unwrap(),
+                200
+            );
+
+            release.send(()).unwrap();
+            blocker.await.unwrap();
+        });
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_survives_a_panicking_job() {
+        let pool = WorkerPool::new("test", 1);
+        assert!(pool.run(|| panic!("job failed")).await.is_err());
+        assert_eq!(pool.run(|| 1 + 1).await.unwrap(), 2);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_parquet_store_reads_a_symbol_and_range() {
+        use crate::parquet_store::ParquetStore;
+        use crate::storage::TickStore;
+        use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+        use parquet::arrow::ArrowWriter;
+        use parquet::file::properties::WriterProperties;
+
+        // sorted by symbol then time, four rows per row group
+        let rows: Vec<(&str, i64, f64)> = (0..8)
+            .map(|i| ("000001.SZ", i * 1_000, 10.0 + i as f64))
+            .chain((0..8).map(|i| ("600733.SH", i * 1_000, 20.0 + i as f64)))
+            .collect();
+        let batch = RecordBatch::try_from_iter([
+            (
+                "symbol",
+                Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.0))) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.1))),
+            ),
+            (
+                "price",
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.2))),
+            ),
+            (
+                "vol",
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|_| 100.0))),
+            ),
+            // not projected
+            (
+                "note",
+                Arc::new(StringArray::from_iter_values(rows.iter().map(|_| "x"))),
+            ),
+        ])
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("{}.parquet", unique_symbol("ticks")));
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(4)
+            .build();
+        let mut writer = ArrowWriter::try_new(
+            std::fs::File::create(&path).unwrap(),
+            batch.schema(),
+            Some(props),
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let store = ParquetStore::open(&path).unwrap();
+        let ticks = store
+            .get_ticks_range("600733.SH", 2_000, 6_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            ticks
+                .iter()
+                .map(|t| (t.ts.as_millis(), t.price))
+                .collect::<Vec<_>>(),
+            [(2_000, 22.0), (3_000, 23.0), (4_000, 24.0), (5_000, 25.0)]
+        );
+        assert!(
+            ticks
+                .iter()
+                .all(|t| t.symbol == "600733.SH" && t.vol == 100.0)
+        );
+        assert!(
+            store
+                .get_ticks_range("600734.SH", 0, i64::MAX)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            store
+                .get_ticks_range("000001.SZ", 8_000, 9_000)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tick_size_rounding_differs_by_board() {
+        let tick_sizes = test_config().trading.tick_sizes;
+        // an ETF trades in 0.001 steps, a main-board stock in 0.01 steps
+        assert_eq!(tick_sizes.round_to_tick(4.2567, "510300.SH"), 4.257);
+        assert_eq!(tick_sizes.round_to_tick(4.2567, "600733.SH"), 4.26);
+
+        let executor = SimExecutor::new(tick_sizes);
+        let etf = executor
+            .buy("510300.SH", 4.2567, 100.0, None)
+            .await
+            .unwrap();
+        let stock = executor
+            .sell("600733.SH", 4.2567, 100.0, None)
+            .await
+            .unwrap();
+        assert_eq!((etf.price, stock.price), (4.257, 4.26));
+        assert_ne!(etf.id, stock.id);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_client_order_id_fills_once() {
+        let executor = SimExecutor::new(test_config().trading.tick_sizes);
+        let first = executor
+            .buy("600733.SH", 10.0, 100.0, Some("order-1"))
+            .await
+            .unwrap();
+        // a retry after a timeout gets the original fill back
+        let retry = executor
+            .buy("600733.SH", 10.0, 100.0, Some("order-1"))
+            .await
+            .unwrap();
+        assert_eq!(retry, first);
+        assert_eq!(executor.position("600733.SH"), 100.0);
+
+        let other = executor
+            .buy("600733.SH", 10.0, 100.0, Some("order-2"))
+            .await
+            .unwrap();
+        assert_ne!(other.id, first.id);
+        executor.sell("600733.SH", 10.0, 50.0, None).await.unwrap();
+        executor.sell("600733.SH", 10.0, 50.0, None).await.unwrap();
+        // without a key every call trades
+        assert_eq!(executor.position("600733.SH"), 100.0);
+
+        // a key reused for a different order is refused, not answered with the old fill
+        for reused in [
+            executor
+                .sell("600733.SH", 10.0, 100.0, Some("order-1"))
+                .await,
+            executor
+                .buy("000001.SZ", 10.0, 100.0, Some("order-1"))
+                .await,
+            executor
+                .buy("600733.SH", 10.0, 200.0, Some("order-1"))
+                .await,
+            executor
+                .buy("600733.SH", 10.5, 100.0, Some("order-1"))
+                .await,
+        ] {
+            let err = reused.unwrap_err();
+            assert!(err.to_string().contains("order-1"), "{}", err);
+        }
+        assert_eq!(executor.position("600733.SH"), 100.0);
+        assert_eq!(executor.position("000001.SZ"), 0.0);
+
+        // the remembered keys are bounded, the oldest going first
+        let executor = SimExecutor::new(test_config().trading.tick_sizes);
+        for i in 0..=10_000 {
+            let key = format!("bulk-{}", i);
+            executor
+                .buy("600733.SH", 10.0, 1.0, Some(&key))
+                .await
+                .unwrap();
+        }
+        executor
+            .buy("600733.SH", 10.0, 1.0, Some("bulk-10000"))
+            .await
+            .unwrap();
+        assert_eq!(executor.position("600733.SH"), 10_001.0);
+        executor
+            .buy("600733.SH", 10.0, 1.0, Some("bulk-0"))
+            .await
+            .unwrap();
+        assert_eq!(executor.position("600733.SH"), 10_002.0);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_json_matches_buffered() {
+        use futures::StreamExt;
+
+        #[derive(serde::Serialize)]
+        struct Envelope {
+            success: bool,
+            data: Data,
+        }
+        #[derive(serde::Serialize)]
+        struct Data {
+            points: Vec<MACDPoint>,
+            symbol: String,
+            count: usize,
+        }
+        let envelope = |points: Vec<MACDPoint>| Envelope {
+            success: true,
+            data: Data {
+                count: points.len(),
+                points,
+                // a string value mentioning the field must not confuse the splice
+                symbol: "\"points\":[]".to_string(),
+            },
+        };
+
+        for len in [0, 1, 600] {
+            let prices: Vec<(TsMillis, f64)> = (0..len)
+                .map(|i| (TsMillis(i), 10.0 + (i as f64).sin()))
+                .collect();
+            let points = compute_macd_series(&prices, 12, 26, 9);
+            let buffered = serde_json::to_vec(&envelope(points.clone())).unwrap();
+
+            let mut skeleton = envelope(Vec::new());
+            skeleton.data.count = points.len();
+            let chunks: Vec<_> = stream_json_with_array(&skeleton, "points", points)
+                .unwrap()
+                .collect()
+                .await;
+            let streamed: Vec<u8> = chunks
+                .into_iter()
+                .flat_map(|chunk| chunk.unwrap().to_vec())
+                .collect();
+            assert_eq!(
+                String::from_utf8(streamed).unwrap(),
+                String::from_utf8(buffered).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_signal_confidence_is_normalized() {
+        // same-shaped histogram at two price scales scores the same
+        let histogram: Vec<f64> = (0..80).map(|i| (i as f64 / 4.0).sin()).collect();
+        let scaled: Vec<f64> = histogram.iter().map(|h| h * 1000.0).collect();
+        assert!((signal_confidence(&histogram, 70) - signal_confidence(&scaled, 70)).abs() < 1e-9);
+
+        // a weak and a strong crossover in the same context
+        let mut histogram = vec![0.0; 60];
+        for (i, h) in histogram.iter_mut().enumerate() {
+            *h = if i % 2 == 0 { -1.0 } else { 1.0 };
+        }
+        histogram.push(-0.5);
+        histogram.push(0.2); // weak bullish cross
+        histogram.push(-0.3);
+        histogram.push(1.8); // strong bullish cross
+        let weak = signal_confidence(&histogram, 61);
+        let strong = signal_confidence(&histogram, 63);
+        assert!(weak > 0.0 && weak < 100.0);
+        assert!(strong > 0.0 && strong < 100.0);
+        assert!(strong > weak + 20.0, "weak={} strong={}", weak, strong);
+
+        assert_eq!(signal_confidence(&[0.0; 10], 9), 0.0);
+    }
+
+    #[test]
+    fn test_analyze_signals_confidence_not_saturated() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let data: Vec<StockData> = (0..200)
+            .map(|i| {
+                let close = 100.0 + 20.0 * (i as f64 / 10.0).sin();
+                StockData {
+                    date: start + chrono::Duration::days(i),
+                    open: close,
+                    close,
+                    high: close,
+                    low: close,
+                    volume: 1000.0,
+                }
+            })
+            .collect();
+        let signals = analyze_signals(&data);
+        assert!(signals.len() >= 2);
+        assert!(
+            signals
+                .iter()
+                .all(|s| s.confidence >= 0.0 && s.confidence < 100.0)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_latest_batch_returns_tick_or_null_per_symbol() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let cached = unique_symbol("LBA");
+        let sqlite_only = unique_symbol("LBB");
+        let missing = unique_symbol("LBC");
+        storage
+            .save_tick(&tick(&cached, 1_709_285_400_000, 10.5))
+            .await
+            .unwrap();
+        storage
+            .insert_ticks_ignore(&[tick(&sqlite_only, 1_709_285_460_000, 20.5)])
+            .await
+            .unwrap();
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/api/latest?symbols={},{},{}",
+                cached, sqlite_only, missing
+            ))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let data = body["data"].as_object().unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[&cached]["price"], 10.5);
+        assert_eq!(data[&sqlite_only]["price"], 20.5);
+        assert!(data[&missing].is_null());
+
+        let too_many: Vec<String> = (0..51).map(|i| format!("S{}", i)).collect();
+        let req = TestRequest::get()
+            .uri(&format!("/api/latest?symbols={}", too_many.join(",")))
+            .to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_per_source_request_timeout() {
+        let mut config = test_config();
+        config.server.timeout = 99;
+        config.data_source.eastmoney.timeout_secs = 20;
+        config.data_source.sina.timeout_secs = 3;
+        let fetcher = DataFetcher::new(Arc::new(config.clone()));
+
+        let timeout_of = |source| {
+            fetcher
+                .source_get(source, "http://127.0.0.1:1/")
+                .build()
+                .unwrap()
+                .timeout()
+                .copied()
+        };
+        assert_eq!(
+            timeout_of(&config.data_source.eastmoney),
+            Some(std::time::Duration::from_secs(20))
+        );
+        assert_eq!(
+            timeout_of(&config.data_source.sina),
+            Some(std::time::Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    fn test_placeholder_fields_are_not_values() {
+        let dash = serde_json::json!("-");
+        let empty = serde_json::json!("");
+        let zero = serde_json::json!(0);
+
+        assert_eq!(
+            parse_field(&dash, "f43", FieldKind::Price, 3).unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_field(&empty, "f46", FieldKind::Price, 3).unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_field(&dash, "f134", FieldKind::Change, 3).unwrap(),
+            None
+        );
+        // zero price means "no data"...
+        assert_eq!(
+            parse_field(&zero, "f43", FieldKind::Price, 3).unwrap(),
+            None
+        );
+        // ...but a zero change or volume is real
+        assert_eq!(
+            parse_field(&zero, "f134", FieldKind::Change, 3).unwrap(),
+            Some(Decimal::ZERO)
+        );
+        assert_eq!(
+            parse_field(&zero, "f48", FieldKind::Volume, 0).unwrap(),
+            Some(Decimal::ZERO)
+        );
+        assert!(parse_field(&serde_json::json!("abc"), "f43", FieldKind::Price, 3).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_primed_day_macd_is_served_from_cache() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let config = Arc::new(test_config());
+        let symbol = unique_symbol("PRM");
+        let today = market_today(config.trading.timezone);
+        let open = today
+            .and_hms_opt(1, 30, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis();
+        for i in 0..30 {
+            storage
+                .save_tick(&tick(&symbol, open + i * 60_000, 10.0 + i as f64 * 0.1))
+                .await
+                .unwrap();
+        }
+        let app = TradingApp::new(storage.clone(), config);
+
+        app.prime_indicator_cache(std::slice::from_ref(&symbol))
+            .await;
+        assert_eq!(app.day_macd_cache_hits(), 0);
+
+        let points = app.get_day_macd(&symbol, today).await.unwrap();
+        assert_eq!(points.len(), 30);
+        assert_eq!(app.day_macd_cache_hits(), 1);
+
+        // a new tick invalidates the cached series
+        storage
+            .save_tick(&tick(&symbol, open + 30 * 60_000, 13.0))
+            .await
+            .unwrap();
+        let points = app.get_day_macd(&symbol, today).await.unwrap();
+        assert_eq!(points.len(), 31);
+        assert_eq!(app.day_macd_cache_hits(), 1);
+
+        // so does a backfilled tick older than the newest one
+        storage
+            .insert_ticks_ignore(&[tick(&symbol, open - 60_000, 9.0)])
+            .await
+            .unwrap();
+        let points = app.get_day_macd(&symbol, today).await.unwrap();
+        assert_eq!(points.len(), 32);
+        assert_eq!(app.day_macd_cache_hits(), 1);
+
+        let err = storage
+            .get_ticks_for_date(&symbol, "2024-13-01")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AppError>().map(AppError::status_code),
+            Some(400)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_incremental_macd_matches_full_recompute() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let config = Arc::new(test_config());
+        let symbol = unique_symbol("INC");
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let open =
+            market_local_to_millis(config.trading.timezone, day.and_hms_opt(9, 30, 0).unwrap());
+        let ticks: Vec<Tick> = (0..60)
+            .map(|i| tick(&symbol, open + i * 60_000, 10.0 + (i as f64 / 4.0).sin()))
+            .collect();
+        // the first 40 are already stored, the rest arrive one by one
+        storage.insert_ticks_ignore(&ticks[..40]).await.unwrap();
+        let app = TradingApp::new(storage.clone(), config);
+
+        let mut streamed = Vec::new();
+        for t in &ticks[40..] {
+            storage.save_tick(t).await.unwrap();
+            streamed.push(app.update_macd(t).await.unwrap());
+        }
+        let full = app.get_day_macd(&symbol, day).await.unwrap();
+        for (update, expected) in streamed.iter().zip(&full[40..]) {
+            assert_eq!(update.ts, expected.ts);
+            assert_eq!(
+                (update.dif, update.dea, update.macd),
+                (expected.dif, expected.dea, expected.macd)
+            );
+        }
+
+        // a tick already applied is answered without counting twice
+        for (t, point) in [(&ticks[59], &streamed[19]), (&ticks[50], &streamed[10])] {
+            let again = app.update_macd(t).await.unwrap();
+            assert_eq!((again.ts, again.macd), (point.ts, point.macd));
+        }
+        // but one that arrives late is refused
+        let late = tick(&symbol, ticks[50].ts.as_millis() + 1, 10.0);
+        assert!(app.update_macd(&late).await.is_err());
+
+        // the next market day starts from scratch
+        let next = tick(&symbol, open + 86_400_000, 11.0);
+        let point = app.update_macd(&next).await.unwrap();
+        assert_eq!((point.dif, point.dea, point.macd), (0.0, 0.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_warm_range_caches_every_symbol_and_day() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let config = Arc::new(test_config());
+        let tz = config.trading.timezone;
+        let symbols = vec![unique_symbol("WARMA"), unique_symbol("WARMB")];
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let days = [monday, monday + chrono::Duration::days(1)];
+        let mut ticks = Vec::new();
+        for symbol in &symbols {
+            for day in days {
+                let open = market_local_to_millis(tz, day.and_hms_opt(9, 30, 0).unwrap());
+                ticks.extend((0..10).map(|i| tick(symbol, open + i * 60_000, 10.0 + i as f64)));
+            }
+        }
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+        let app = TradingApp::new(storage.clone(), config.clone());
+
+        // the weekend before is not a trading day, so there is nothing to warm there
+        let saturday = monday - chrono::Duration::days(2);
+        let summary = app.warm_range(&symbols, saturday, days[1]).await;
+        assert_eq!(
+            summary,
+            WarmSummary {
+                computed: 4,
+                cached: 0,
+                failed: 0
+            }
+        );
+        let misses = app.day_macd_cache_misses();
+        for symbol in &symbols {
+            for day in days {
+                assert_eq!(app.get_day_macd(symbol, day).await.unwrap().len(), 10);
+            }
+        }
+        assert_eq!(app.day_macd_cache_hits(), 4);
+        assert_eq!(app.day_macd_cache_misses(), misses);
+
+        let again = app.warm_range(&symbols, days[0], days[1]).await;
+        assert_eq!((again.computed, again.cached), (0, 4));
+
+        // the same through the admin endpoint
+        let mut state = test_state(storage);
+        let mut admin_config = (*config).clone();
+        admin_config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(admin_config);
+        state.trading_app = Arc::new(app);
+        let server = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let warm = |token: Option<&str>, to: NaiveDate| {
+            let mut req = TestRequest::post()
+                .uri("/api/admin/cache/warm")
+                .set_json(serde_json::json!({ "symbols": symbols, "from": saturday, "to": to }));
+            if let Some(token) = token {
+                req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+            }
+            req.to_request()
+        };
+        let resp = call_service(&server, warm(None, days[1])).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value =
+            call_and_read_body_json(&server, warm(Some("s3cret"), days[1])).await;
+        assert_eq!(
+            body["data"],
+            serde_json::json!({ "computed": 0, "cached": 4, "failed": 0 })
+        );
+        let resp = call_service(
+            &server,
+            warm(Some("s3cret"), saturday - chrono::Duration::days(1)),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_lttb_keeps_endpoints_and_hits_threshold() {
+        let points: Vec<(i64, f64)> = (0..10_000)
+            .map(|i| (i, (i as f64 / 50.0).sin() * 10.0 + 100.0))
+            .collect();
+        let reduced = lttb(&points, 500, |p| (p.0 as f64, p.1));
+        assert_eq!(reduced.len(), 500);
+        assert_eq!(reduced.first(), points.first());
+        assert_eq!(reduced.last(), points.last());
+        assert!(reduced.windows(2).all(|w| w[0].0 < w[1].0));
+
+        // below the threshold everything is returned
+        assert_eq!(lttb(&points[..300], 500, |p| (p.0 as f64, p.1)).len(), 300);
+    }
+
+    #[actix_web::test]
+    async fn test_symbols_endpoint_paginates() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let prefix = unique_symbol("PG");
+        let symbols: Vec<String> = (0..5).map(|i| format!("{}-{}", prefix, i)).collect();
+        for symbol in &symbols {
+            storage
+                .save_tick(&tick(symbol, 1_709_285_400_000, 10.0))
+                .await
+                .unwrap();
+        }
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let mut seen = Vec::new();
+        for offset in [0, 2, 4] {
+            let req = TestRequest::get()
+                .uri(&format!("/api/symbols?limit=2&offset={}", offset))
+                .to_request();
+            let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+            let page = &body["data"];
+            assert_eq!(page["total"], 5);
+            assert_eq!(page["limit"], 2);
+            assert_eq!(page["offset"], offset);
+            let mut items: Vec<String> = page["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["symbol"].as_str().unwrap().to_string())
+                .collect();
+            items.sort();
+            seen.extend(items);
+        }
+        assert_eq!(seen, symbols);
+
+        let req = TestRequest::get().uri("/api/symbols").to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"]["items"].as_array().unwrap().len(), 5);
+        assert_eq!(body["data"]["offset"], 0);
+        assert_eq!(body["data"]["data_status"], "ready");
+    }
+
+    #[actix_web::test]
+    async fn test_symbols_data_status_separates_empty_from_warming() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let mut state = test_state(storage);
+        let mut config = test_config();
+        config.server.prime_cache = true;
+        state.config = Arc::new(config);
+        let trading_app = state.trading_app.clone();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let symbols = || TestRequest::get().uri("/api/symbols").to_request();
+
+        let body: serde_json::Value = call_and_read_body_json(&app, symbols()).await;
+        assert_eq!(body["data"]["data_status"], "warming");
+
+        // priming done and still nothing stored: a genuinely empty result
+        trading_app.prime_indicator_cache(&[]).await;
+        let body: serde_json::Value = call_and_read_body_json(&app, symbols()).await;
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["total"], 0);
+        assert!(body["data"]["items"].as_array().unwrap().is_empty());
+        assert_eq!(body["data"]["data_status"], "empty");
+    }
+
+    #[tokio::test]
+    async fn test_replay_emits_day_in_order_without_persisting() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("RPL");
+        let base = 1_709_285_400_000; // 2024-03-01 09:30 UTC
+        for i in [2, 0, 1, 3] {
+            storage
+                .insert_ticks_ignore(&[tick(&symbol, base + i * 60_000, 10.0 + i as f64)])
+                .await
+                .unwrap();
+        }
+        // a tick on the next day is not part of the replay
+        storage
+            .insert_ticks_ignore(&[tick(&symbol, base + 86_400_000, 50.0)])
+            .await
+            .unwrap();
+
+        let feed = TickFeed::new();
+        let mut rx = feed.subscribe();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let replayed = replay_day(&storage, &feed, &symbol, date, 1_000_000.0)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 4);
+
+        let mut prices = Vec::new();
+        while let Ok(t) = rx.try_recv() {
+            assert!(is_replayed(&t));
+            prices.push(t.price);
+        }
+        assert_eq!(prices, [10.0, 11.0, 12.0, 13.0]);
+
+        let stored = storage
+            .get_ticks_for_date(&symbol, "2024-03-01")
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 4);
+        assert!(
+            replay_day(&storage, &feed, &symbol, date, 0.0)
+                .await
+                .is_err()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_replay_neither_alerts_nor_moves_streamed_macd() {
+        struct Recording(Arc<std::sync::Mutex<Vec<Alert>>>);
+
+        #[async_trait::async_trait]
+        impl Notifier for Recording {
+            fn name(&self) -> &str {
+                "recording"
+            }
+            async fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push(alert.clone());
+                Ok(())
+            }
+        }
+
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("RPLALERT");
+        // flat through the warmup, then up and down a minute per tick from 09:30 Shanghai
+        let start = 1_709_256_600_000;
+        let mut prices = vec![10.0; 60];
+        prices.extend((1..=10).map(|i| 10.0 + i as f64 * 0.1));
+        prices.extend((1..=20).map(|i| 11.0 - i as f64 * 0.1));
+        let ticks: Vec<Tick> = prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| tick(&symbol, start + i as i64 * 60_000, price))
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let trading = test_config().trading;
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut engine = AlertEngine::new(vec![Box::new(Recording(seen.clone()))], &trading);
+        let feed = TickFeed::new();
+        let mut rx = feed.subscribe();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let replayed = replay_day(&storage, &feed, &symbol, date, 1_000_000.0)
+            .await
+            .unwrap();
+        assert_eq!(replayed, ticks.len());
+
+        let state = test_state(storage.clone());
+        let mut delivered = 0;
+        while let Ok(t) = rx.try_recv() {
+            delivered += engine.handle(&t).await;
+            assert!(state.trading_app.update_macd(&t).await.is_err());
+        }
+        assert_eq!(delivered, 0);
+        assert!(seen.lock().unwrap().is_empty());
+        // the same ticks live do cross
+        for t in &ticks {
+            delivered += engine.handle(t).await;
+        }
+        assert_eq!(delivered, 2);
+
+        // the endpoint is admin only and runs one replay at a time
+        let mut state = state;
+        let mut config = test_config();
+        config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(config);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let replay = |token: Option<&str>| {
+            let mut req = TestRequest::post().uri("/api/replay").set_json(
+                serde_json::json!({ "symbol": symbol, "date": "2024-03-01", "speed": 1.0 }),
+            );
+            if let Some(token) = token {
+                req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+            }
+            req.to_request()
+        };
+        assert_eq!(
+            call_service(&app, replay(None)).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        // at real-time speed the first replay is still running for the second request
+        assert_eq!(
+            call_service(&app, replay(Some("s3cret"))).await.status(),
+            StatusCode::ACCEPTED
+        );
+        assert_eq!(
+            call_service(&app, replay(Some("s3cret"))).await.status(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ticks_for_date_use_market_calendar_day() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("TZDAY");
+        // 2024-03-01 00:00 in Shanghai is 2024-02-29 16:00 UTC
+        let cn_midnight = 1_709_222_400_000;
+        let ticks = [
+            tick(&symbol, cn_midnight - 1_000, 9.0),
+            tick(&symbol, cn_midnight, 10.0),
+            tick(&symbol, cn_midnight + 86_400_000 - 1_000, 11.0),
+            tick(&symbol, cn_midnight + 86_400_000, 12.0),
+        ];
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let prices = |ticks: Vec<Tick>| ticks.iter().map(|t| t.price).collect::<Vec<_>>();
+        let day = storage
+            .get_ticks_for_date(&symbol, "2024-03-01")
+            .await
+            .unwrap();
+        assert_eq!(prices(day), [10.0, 11.0]);
+        let before = storage
+            .get_ticks_for_date(&symbol, "2024-02-29")
+            .await
+            .unwrap();
+        assert_eq!(prices(before), [9.0]);
+    }
+
+    #[tokio::test]
+    async fn test_symbol_meta_upsert_and_read_back() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        assert_eq!(storage.get_symbol_meta("600733.SH").await.unwrap(), None);
+
+        let mut meta = SymbolMeta {
+            symbol: "600733.SH".to_string(),
+            name: "北汽蓝谷".to_string(),
+            exchange: "SSE".to_string(),
+            sector: None,
+        };
+        storage.upsert_symbol_meta(&meta).await.unwrap();
+        assert_eq!(
+            storage.get_symbol_meta("600733.SH").await.unwrap(),
+            Some(meta.clone())
+        );
+
+        meta.sector = Some("Automobiles".to_string());
+        storage.upsert_symbol_meta(&meta).await.unwrap();
+        assert_eq!(
+            storage.get_symbol_meta("600733.SH").await.unwrap(),
+            Some(meta)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_search_matches_code_prefix_and_name_substring() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        for (symbol, name) in [
+            ("600733.SH", "北汽蓝谷"),
+            ("600734.SH", "Shanghai Pudong Bank"),
+            ("000001.SZ", "Ping An Bank"),
+        ] {
+            storage
+                .upsert_symbol_meta(&SymbolMeta {
+                    symbol: symbol.to_string(),
+                    name: name.to_string(),
+                    exchange: "SSE".to_string(),
+                    sector: None,
+                })
+                .await
+                .unwrap();
+        }
+        // ticks but no metadata
+        storage
+            .save_tick(&tick("600735.SH", 1_000, 10.0))
+            .await
+            .unwrap();
+
+        let symbols =
+            |matches: Vec<SymbolMatch>| matches.into_iter().map(|m| m.symbol).collect::<Vec<_>>();
+        assert_eq!(
+            symbols(storage.search_symbols("6007", 10).await.unwrap()),
+            ["600733.SH", "600734.SH", "600735.SH"]
+        );
+        // exact code outranks the other prefixes
+        assert_eq!(
+            symbols(storage.search_symbols("600735.sh", 10).await.unwrap()),
+            ["600735.SH"]
+        );
+        assert_eq!(
+            symbols(storage.search_symbols("bank", 10).await.unwrap()),
+            ["000001.SZ", "600734.SH"]
+        );
+        assert_eq!(
+            storage.search_symbols("蓝谷", 10).await.unwrap(),
+            [SymbolMatch {
+                symbol: "600733.SH".to_string(),
+                name: Some("北汽蓝谷".to_string()),
+            }]
+        );
+        assert_eq!(storage.search_symbols("6007", 2).await.unwrap().len(), 2);
+        // wildcards are matched literally
+        assert!(storage.search_symbols("%", 10).await.unwrap().is_empty());
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage.clone())))
+                .configure(configure_api),
+        )
+        .await;
+        let body: serde_json::Value = call_and_read_body_json(
+            &app,
+            TestRequest::get()
+                .uri("/api/search?q=600&limit=1")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(body["data"][0]["symbol"], "600733.SH");
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        let body: serde_json::Value =
+            call_and_read_body_json(&app, TestRequest::get().uri("/api/search?q=").to_request())
+                .await;
+        assert_eq!(body["data"], serde_json::json!([]));
+    }
+
+    /// Quote source that only counts how often each symbol is requested.
+    #[derive(Default)]
+    struct CountingQuotes {
+        calls: std::sync::Mutex<std::collections::HashMap<String, usize>>,
+    }
+
+    impl QuoteSource for CountingQuotes {
+        async fn fetch_quote(&self, symbol: &str) -> Result<Quote, AppError> {
+            *self
+                .calls
+                .lock()
+                .unwrap()
+                .entry(symbol.to_string())
+                .or_default() += 1;
+            Err(AppError::data_not_found("counting only"))
+        }
+    }
+
+    /// Quote source answering every symbol with the same price.
+    struct FixedQuotes;
+
+    impl QuoteSource for FixedQuotes {
+        async fn fetch_quote(&self, symbol: &str) -> Result<Quote, AppError> {
+            Ok(serde_json::from_value(serde_json::json!({
+                "symbol": symbol,
+                "timestamp": chrono::Utc::now().timestamp_millis(),
+                "price": "10.5249",
+            }))
+            .unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_polled_ticks_are_stored_with_default_config() {
+        let config = test_config();
+        let storage = match Storage::new(":memory:", &test_redis_url()) {
+            Ok(storage) => Arc::new(storage.with_run_mode(config.database.run_mode)),
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                return;
+            }
+        };
+        let symbol = unique_symbol("POLLED");
+        let cancel = CancellationToken::new();
+        let pollers = spawn_pollers(
+            Arc::new(FixedQuotes),
+            storage.clone(),
+            TickFeed::with_capacity(16),
+            vec![(symbol.clone(), std::time::Duration::from_secs(60))],
+            config.trading.tick_sizes.clone(),
+            PollGate::always(),
+            cancel.clone(),
+        );
+
+        // the first poll fires immediately
+        let mut stored = None;
+        for _ in 0..100 {
+            stored = storage.get_latest_tick(&symbol).await.unwrap();
+            if stored.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        cancel.cancel();
+        futures::future::join_all(pollers).await;
+        // the quote's price snapped to the 0.01 tick
+        assert_eq!(stored.expect("polled tick was not stored").price, 10.52);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_interval_override_halves_poll_rate() {
+        let mut config = test_config();
+        config.trading.poll_interval_secs = 1;
+        config.trading.watchlist = vec![
+            WatchlistEntry::Symbol("FAST".to_string()),
+            WatchlistEntry::Detailed {
+                symbol: "SLOW".to_string(),
+                poll_interval_secs: Some(2),
+            },
+        ];
+        let source = Arc::new(CountingQuotes::default());
+
+        let handles: Vec<_> = config
+            .trading
+            .poll_schedule()
+            .into_iter()
+            .map(|(symbol, every)| {
+                tokio::spawn(poll_symbol(
+                    source.clone(),
+                    symbol,
+                    every,
+                    PollGate::always(),
+                    CancellationToken::new(),
+                    |_| async {},
+                ))
+            })
+            .collect();
+        // first poll fires immediately, then one per interval
+        tokio::time::sleep(std::time::Duration::from_millis(19_500)).await;
+        for handle in handles {
+            handle.abort();
+        }
+
+        let calls = source.calls.lock().unwrap();
+        assert_eq!(calls["FAST"], 20);
+        assert_eq!(calls["SLOW"], 10);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poller_idles_outside_trading_sessions() {
+        let tz = chrono_tz::Asia::Shanghai;
+        let at = move |date: NaiveDate, h: u32, m: u32| {
+            tz.from_local_datetime(&date.and_hms_opt(h, m, 0).unwrap())
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        let saturday = NaiveDate::from_ymd_opt(2024, 6, 8).unwrap();
+        let holiday = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+
+        let now = Arc::new(std::sync::Mutex::new(at(friday, 9, 0)));
+        let clock = now.clone();
+        let gate = PollGate::sessions(TradingCalendar::new(tz, vec![holiday]))
+            .with_clock(move || *clock.lock().unwrap());
+        let source = Arc::new(CountingQuotes::default());
+        let handle = tokio::spawn(poll_symbol(
+            source.clone(),
+            "GATED".to_string(),
+            std::time::Duration::from_secs(1),
+            gate,
+            CancellationToken::new(),
+            |_| async {},
+        ));
+        let polls = || {
+            source
+                .calls
+                .lock()
+                .unwrap()
+                .get("GATED")
+                .copied()
+                .unwrap_or(0)
+        };
+
+        let cases = [
+            (at(friday, 9, 0), false),   // before the open
+            (at(friday, 10, 0), true),   // morning session
+            (at(friday, 12, 0), false),  // lunch break
+            (at(friday, 14, 0), true),   // afternoon session
+            (at(friday, 15, 30), false), // after the close
+            (at(saturday, 10, 0), false),
+            (at(holiday, 10, 0), false),
+        ];
+        for (time, open) in cases {
+            *now.lock().unwrap() = time;
+            let before = polls();
+            tokio::time::sleep(std::time::Duration::from_millis(5_500)).await;
+            let polled = polls() - before;
+            if open {
+                assert!(polled >= 5, "{} polls at {}", polled, time);
+            } else {
+                assert_eq!(polled, 0, "polled at {}", time);
+            }
+        }
+        handle.abort();
+
+        assert!(
+            PollGate::always()
+                .with_clock(move || at(saturday, 3, 0))
+                .is_open()
+        );
+    }
+
+    /// Registry with a `dummy` task that counts iterations until cancelled, then
+    /// records that it exited.
+    async fn dummy_task_registry() -> (
+        TaskRegistry,
+        Arc<std::sync::atomic::AtomicUsize>,
+        Arc<std::sync::atomic::AtomicBool>,
+    ) {
+        let iterations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let exited = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let registry = TaskRegistry::new();
+        let (count, done) = (iterations.clone(), exited.clone());
+        registry
+            .register("dummy", move |cancel: CancellationToken| {
+                let (count, done) = (count.clone(), done.clone());
+                async move {
+                    while !cancel.is_cancelled() {
+                        count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    }
+                    done.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+            })
+            .await;
+        (registry, iterations, exited)
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_task_stops_and_can_restart() {
+        use std::sync::atomic::Ordering;
+
+        let (registry, iterations, exited) = dummy_task_registry().await;
+        assert_eq!(registry.start("missing").await, None);
+        assert_eq!(registry.start("dummy").await, Some(TaskState::Running));
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert!(iterations.load(Ordering::SeqCst) > 0);
+
+        // stop returns once the running iteration has finished
+        assert_eq!(registry.stop("dummy").await, Some(TaskState::Stopped));
+        assert!(exited.load(Ordering::SeqCst));
+        let after_stop = iterations.load(Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert_eq!(iterations.load(Ordering::SeqCst), after_stop);
+
+        exited.store(false, Ordering::SeqCst);
+        registry.start("dummy").await;
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert!(iterations.load(Ordering::SeqCst) > after_stop);
+        registry.stop("dummy").await;
+        assert!(exited.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_start_while_stopping_does_not_overlap_runs() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let registry = TaskRegistry::new();
+        let (running, most) = (active.clone(), peak.clone());
+        registry
+            .register("slow", move |cancel: CancellationToken| {
+                let (running, most) = (running.clone(), most.clone());
+                async move {
+                    most.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    cancel.cancelled().await;
+                    // a long last iteration
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+            })
+            .await;
+
+        registry.start("slow").await;
+        let stopping = {
+            let registry = registry.clone();
+            tokio::spawn(async move { registry.stop("slow").await })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(registry.start("slow").await, Some(TaskState::Stopping));
+        assert_eq!(registry.stop("slow").await, Some(TaskState::Stopping));
+        assert_eq!(stopping.await.unwrap(), Some(TaskState::Stopped));
+
+        assert_eq!(registry.start("slow").await, Some(TaskState::Running));
+        assert_eq!(registry.stop("slow").await, Some(TaskState::Stopped));
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert_eq!(active.load(Ordering::SeqCst), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_task_endpoints_require_admin_and_known_name() {
+        let (registry, _, exited) = dummy_task_registry().await;
+        let mut state = test_state(storage_without_redis());
+        let mut config = test_config();
+        config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(config);
+        state.tasks = registry;
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let post = |uri: &str| {
+            TestRequest::post()
+                .uri(uri)
+                .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+                .to_request()
+        };
+
+        let req = TestRequest::post()
+            .uri("/api/admin/task/dummy/start")
+            .to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        let resp = call_service(&app, post("/api/admin/task/nope/start")).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let body: serde_json::Value =
+            call_and_read_body_json(&app, post("/api/admin/task/dummy/start")).await;
+        assert_eq!(body["data"]["state"], "running");
+        let body: serde_json::Value =
+            call_and_read_body_json(&app, post("/api/admin/task/dummy/stop")).await;
+        assert_eq!(body["data"]["state"], "stopped");
+        assert!(exited.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_parse_days_clamps_and_rejects() {
+        assert_eq!(parse_days(None, 365), Ok(None));
+        assert_eq!(parse_days(Some("-5"), 365), Ok(Some(1)));
+        assert_eq!(parse_days(Some("0"), 365), Ok(Some(1)));
+        assert_eq!(parse_days(Some("100000"), 365), Ok(Some(365)));
+        assert_eq!(parse_days(Some("30"), 365), Ok(Some(30)));
+        assert!(parse_days(Some("abc"), 365).is_err());
+        assert!(parse_days(Some("1.5"), 365).is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_non_numeric_days_is_bad_request() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        for uri in ["/api/report/X?days=abc", "/api/history/X?days=abc"] {
+            let req = TestRequest::get().uri(uri).to_request();
+            assert_eq!(
+                call_service(&app, req).await.status(),
+                StatusCode::BAD_REQUEST
+            );
+        }
+        let req = TestRequest::get().uri("/api/report/X?days=-3").to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["analysis_period"], "1 days");
+        let req = TestRequest::get()
+            .uri("/api/report/X?days=100000")
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["analysis_period"], "365 days");
+
+        // out-of-range values are clamped on every endpoint, not rejected on some
+        for uri in ["/api/history/X?days=0", "/api/history/X?days=100000"] {
+            let req = TestRequest::get().uri(uri).to_request();
+            assert_eq!(
+                call_service(&app, req).await.status(),
+                StatusCode::OK,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn test_mock_day_ticks_fall_inside_sessions() {
+        let trading = test_config().trading;
+        let calendar = trading.calendar();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let ticks = mock_day_ticks("600733.SH", date, &trading);
+        assert_eq!(ticks.len(), 242);
+        for t in &ticks {
+            let at = t.ts.to_datetime().unwrap();
+            assert!(calendar.is_session_open(at), "{} outside sessions", at);
+            assert_eq!(market_date(trading.timezone, at), date);
+        }
+        // 09:30 Shanghai is 01:30 UTC
+        assert_eq!(ticks[0].ts, TsMillis(1_709_256_600_000));
+        assert!(
+            ticks
+                .iter()
+                .all(|t| t.price == trading.tick_sizes.round_to_tick(t.price, &t.symbol))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_gen_sim_endpoint_stores_requested_day() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let mut state = test_state(storage.clone());
+        let mut config = test_config();
+        config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(config);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let symbol = unique_symbol("SIM");
+        let uri = format!("/api/gen_sim/{}", symbol);
+
+        let req = TestRequest::post().uri(&uri).to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let req = TestRequest::post()
+            .uri(&uri)
+            .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+            .set_json(serde_json::json!({ "date": "2024-03-01" }))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        // 09:30-11:30 and 13:00-15:00 inclusive, one tick per minute
+        assert_eq!(body["data"]["ticks"], 242);
+        assert_eq!(body["data"]["date"], "2024-03-01");
+
+        let stored = storage
+            .get_ticks_for_date(&symbol, "2024-03-01")
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 242);
+        let calendar = test_config().trading.calendar();
+        assert!(
+            stored
+                .iter()
+                .all(|t| calendar.is_session_open(t.ts.to_datetime().unwrap()))
+        );
+
+        // in real mode nothing would be stored, so the call is refused outright
+        *storage.run_mode().write().await = RunMode::Real;
+        let req = TestRequest::post()
+            .uri(&uri)
+            .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+            .to_request();
+        assert_eq!(call_service(&app, req).await.status(), StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn test_gen_sim_over_a_stored_day_is_a_conflict_when_duplicates_error() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let storage = Arc::new(
+            Arc::try_unwrap(storage)
+                .unwrap()
+                .with_on_duplicate(DuplicateTickAction::Error),
+        );
+        let mut state = test_state(storage.clone());
+        let mut config = test_config();
+        config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(config);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let uri = format!("/api/gen_sim/{}", unique_symbol("SIMDUP"));
+        let gen_day = || {
+            TestRequest::post()
+                .uri(&uri)
+                .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+                .set_json(serde_json::json!({ "date": "2024-03-01" }))
+                .to_request()
+        };
+
+        assert_eq!(call_service(&app, gen_day()).await.status(), StatusCode::OK);
+        let resp = call_service(&app, gen_day()).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value = serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert!(
+            body["error"].as_str().unwrap().contains("Duplicate tick"),
+            "{}",
+            body
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_backtest_sweep_evaluates_valid_combos_sorted() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("BT");
+        // 2024-03-01 09:30 Shanghai, a slow oscillation so the MACD crosses a few times
+        let base = 1_709_256_600_000;
+        let ticks: Vec<Tick> = (0..240)
+            .map(|i| {
+                let price = 10.0 + (i as f64 / 15.0).sin() + i as f64 * 0.002;
+                tick(&symbol, base + i * 60_000, price)
+            })
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        // (12, 10) has short >= long and must be skipped
+        let req = TestRequest::post()
+            .uri(&format!("/api/backtest/sweep/{}", symbol))
+            .set_json(serde_json::json!({
+                "grid": { "short": [5, 12], "long": [10, 26], "signal": [9] },
+                "from": "2024-03-01",
+                "to": "2024-03-01",
+            }))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let results = body["data"].as_array().unwrap();
+
+        let mut params: Vec<(u64, u64)> = results
+            .iter()
+            .map(|r| {
+                let p = &r["params"];
+                (p["short"].as_u64().unwrap(), p["long"].as_u64().unwrap())
+            })
+            .collect();
+        params.sort();
+        assert_eq!(params, vec![(5, 10), (5, 26), (12, 26)]);
+
+        let returns: Vec<f64> = results
+            .iter()
+            .map(|r| r["total_return"].as_f64().unwrap())
+            .collect();
+        assert!(returns.windows(2).all(|w| w[0] >= w[1]));
+        assert!(results.iter().any(|r| r["trades"].as_u64().unwrap() > 0));
+    }
+
+    #[actix_web::test]
+    async fn test_market_summary_tallies_symbols_and_signals() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let tz = test_config().trading.timezone;
+        let midnight = market_today(tz).and_time(chrono::NaiveTime::MIN);
+        let today_start = market_local_to_millis(tz, midnight);
+
+        // a slide then a rally ends on a bullish cross; the mirror image on a bearish one
+        let shape = |i: i64| {
+            if i < 40 {
+                -(i as f64)
+            } else {
+                (i - 80) as f64 * 2.0
+            }
+        };
+        let (bull, bear) = (unique_symbol("SUMUP"), unique_symbol("SUMDN"));
+        let mut ticks = Vec::new();
+        for i in 0..60 {
+            ticks.push(tick(&bull, today_start + i * 1000, 100.0 + shape(i) * 0.1));
+            ticks.push(tick(&bear, today_start + i * 1000, 100.0 - shape(i) * 0.1));
+        }
+        let stale = unique_symbol("SUMOLD");
+        ticks.push(tick(&stale, today_start - 3_600_000, 10.0));
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        let req = TestRequest::get().uri("/api/market/summary").to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let data = &body["data"];
+        assert_eq!(data["mode"], "sim");
+        assert_eq!(data["symbol_count"], 3);
+        assert_eq!(data["ticks_today"], 120);
+        assert_eq!(data["bullish_symbols"], 1);
+        assert_eq!(data["bearish_symbols"], 1);
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_range_is_payload_too_large() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let (big, small) = (unique_symbol("BIG"), unique_symbol("SMALL"));
+        // 2024-03-01 and 2024-03-04, UTC days
+        let days = [1_709_251_200_000, 1_709_510_400_000];
+        let mut ticks = Vec::new();
+        for day in days {
+            for i in 0..4 {
+                ticks.push(tick(&big, day + i * 60_000, 10.0));
+            }
+            ticks.push(tick(&small, day, 10.0));
+        }
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let mut state = test_state(storage);
+        let mut config = test_config();
+        config.server.max_response_points = 5;
+        state.config = Arc::new(config);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+
+        let range = "from=2024-03-01&to=2024-03-04&interval=1h";
+        let req = TestRequest::get()
+            .uri(&format!("/api/ohlc/{}?{}", big, range))
+            .to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // a single day of the same symbol fits under the cap
+        let req = TestRequest::get()
+            .uri(&format!("/api/ohlc/{}?date=2024-03-01&interval=1h", big))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/ohlc/{}?{}", small, range))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_history_as_of_truncates_before_computing() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("ASOF");
+        // 2024-03-01 09:30 Shanghai, one tick a minute for four hours
+        let base = 1_709_256_600_000;
+        let ticks: Vec<Tick> = (0..240)
+            .map(|i| tick(&symbol, base + i * 60_000, 10.0 + (i as f64 / 20.0).sin()))
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        let uri = format!("/api/history/{}?date=2024-03-01", symbol);
+        let full: serde_json::Value =
+            call_and_read_body_json(&app, TestRequest::get().uri(&uri).to_request()).await;
+        let req = TestRequest::get()
+            .uri(&format!("{}&as_of=11:00", uri))
+            .to_request();
+        let cut: serde_json::Value = call_and_read_body_json(&app, req).await;
+
+        let (full, cut) = (
+            full["data"]["points"].as_array().unwrap(),
+            cut["data"]["points"].as_array().unwrap(),
+        );
+        assert_eq!(full.len(), 240);
+        // 09:30 through 11:00 inclusive
+        assert_eq!(cut.len(), 91);
+        assert_eq!(cut.last().unwrap()["ts"], base + 90 * 60_000);
+        assert_ne!(cut.last().unwrap()["macd"], full.last().unwrap()["macd"]);
+
+        let req = TestRequest::get()
+            .uri(&format!("{}&as_of=noon", uri))
+            .to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_sim_history_falls_back_to_latest_stored_date() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("LATEST");
+        let tz = test_config().trading.timezone;
+        assert_eq!(storage.get_latest_date(&symbol).await.unwrap(), None);
+
+        // well outside the old 7-day lookback, with an older day before it
+        let day = market_today(tz) - chrono::Duration::days(20);
+        let open = |day: NaiveDate| market_local_to_millis(tz, day.and_hms_opt(10, 0, 0).unwrap());
+        let mut ticks: Vec<Tick> = (0..30)
+            .map(|i| tick(&symbol, open(day) + i * 60_000, 10.0 + i as f64 * 0.01))
+            .collect();
+        ticks.push(tick(&symbol, open(day - chrono::Duration::days(10)), 9.0));
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+        assert_eq!(storage.get_latest_date(&symbol).await.unwrap(), Some(day));
+
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        let body: serde_json::Value = call_and_read_body_json(
+            &app,
+            TestRequest::get()
+                .uri(&format!("/api/history/{}", symbol))
+                .to_request(),
+        )
+        .await;
+        let points = body["data"]["points"].as_array().unwrap();
+        assert_eq!(points.len(), 30);
+        assert_eq!(points[0]["ts"], open(day));
+    }
+
+    #[actix_web::test]
+    async fn test_history_query_is_typed_and_validated() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("HQ");
+        let base = 1_709_256_600_000; // 2024-03-01 09:30 Shanghai
+        let ticks: Vec<Tick> = (0..60)
+            .map(|i| tick(&symbol, base + i * 60_000, 10.0 + (i as f64 / 5.0).sin()))
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        let get = |query: &str| {
+            TestRequest::get()
+                .uri(&format!("/api/history/{}?{}", symbol, query))
+                .to_request()
+        };
+
+        // 60 one-minute ticks in 5-minute bars, with custom periods
+        let body: serde_json::Value = call_and_read_body_json(
+            &app,
+            get("date=2024-03-01&interval=5m&short=3&long=6&signal=2"),
+        )
+        .await;
+        let points = body["data"]["points"].as_array().unwrap();
+        assert_eq!(points.len(), 12);
+        assert_eq!(points[1]["ts"], base + 5 * 60_000);
+
+        // log scale bypasses the cached linear day series
+        let body: serde_json::Value =
+            call_and_read_body_json(&app, get("date=2024-03-01&log_scale=true")).await;
+        let prices: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+        let expected = compute_macd_series_log(&prices, 12, 26, 9).unwrap();
+        let points = body["data"]["points"].as_array().unwrap();
+        assert_eq!(points.len(), expected.len());
+        let dif = points[40]["dif"].as_f64().unwrap();
+        assert!(
+            (dif - expected[40].dif).abs() < 1e-4,
+            "{} vs {}",
+            dif,
+            expected[40].dif
+        );
+
+        // parameters the endpoint does not know are ignored
+        let resp = call_service(&app, get("date=2024-03-01&_=1709280000000")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        for query in [
+            "date=2024-13-01",
+            "date=yesterday",
+            "days=ten",
+            "date=2024-03-01&interval=7x",
+            "date=2024-03-01&short=9&long=6",
+        ] {
+            let resp = call_service(&app, get(query)).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", query);
+            let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+            assert_eq!(body["success"], false, "{}", query);
+        }
+    }
+
+    /// TCP proxy in front of the test Redis on its own runtime thread, so `Storage::new`
+    /// can block on it. Sending on (or dropping) the returned sender shuts the proxy and
+    /// every connection through it down, making Redis unreachable for that storage.
+    #[cfg(feature = "redis")]
+    fn spawn_redis_proxy() -> (String, tokio::sync::oneshot::Sender<()>) {
+        let redis_url = test_redis_url();
+        let rest = redis_url.trim_start_matches("redis://");
+        let (upstream, db) = rest.split_once('/').unwrap_or((rest, ""));
+        let upstream = upstream.to_string();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("redis://{}/{}", listener.local_addr().unwrap(), db);
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let accept = async {
+                    while let Ok((mut client, _)) = listener.accept().await {
+                        let upstream = upstream.clone();
+                        tokio::spawn(async move {
+                            if let Ok(mut server) = tokio::net::TcpStream::connect(&upstream).await
+                            {
+                                let _ =
+                                    tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                            }
+                        });
+                    }
+                };
+                tokio::select! {
+                    _ = accept => {}
+                    _ = stopped => {}
+                }
+            });
+            // dropping the runtime closes the listener and all proxied connections
+        });
+        (url, stop)
+    }
+
+    #[cfg(feature = "redis")]
+    #[actix_web::test]
+    async fn test_error_status_separates_client_from_server_faults() {
+        let (redis_url, stop_redis) = spawn_redis_proxy();
+        let storage = match Storage::new(":memory:", &redis_url) {
+            Ok(storage) => Arc::new(storage),
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                return;
+            }
+        };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage.clone())))
+                .configure(configure_api),
+        )
+        .await;
+        let get = |uri: &str| TestRequest::get().uri(uri).to_request();
+
+        // a date that does not exist is the client's fault
+        let resp = call_service(&app, get("/api/history/600733.SH?date=2024-02-30")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        // as is a symbol the fetcher rejects before going upstream
+        let resp = call_service(&app, get("/api/meta/NOPE")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().contains("Invalid symbol"));
+        // and a series the handler cannot compute from what was asked
+        let symbol = unique_symbol("ZERO");
+        let day = 1_709_256_600_000; // 2024-03-01 09:30 Shanghai
+        storage
+            .insert_ticks_ignore(&[tick(&symbol, day, 10.0), tick(&symbol, day + 60_000, 0.0)])
+            .await
+            .unwrap();
+        let uri = format!("/api/history/{}?date=2024-03-01&log_scale=true", symbol);
+        let resp = call_service(&app, get(&uri)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert!(body["error"].as_str().unwrap().contains("log_scale"));
+
+        // the live window is read from Redis, which is now gone
+        stop_redis.send(()).unwrap();
+        let resp = call_service(&app, get("/api/history/600733.SH?last=10")).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[actix_web::test]
+    async fn test_spa_fallback_serves_index_but_not_for_api() {
+        let dir = std::env::temp_dir().join(unique_symbol("static"));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.html"), "<html>spa</html>").unwrap();
+        std::fs::write(dir.join("app.js"), "console.log(1)").unwrap();
+
+        let app = init_service(
+            App::new()
+                .configure(configure_api)
+                .configure(configure_static(dir.to_str().unwrap())),
+        )
+        .await;
+        let get = |uri: &str| TestRequest::get().uri(uri).to_request();
+
+        let res = call_service(&app, get("/some/spa/route")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "<html>spa</html>");
+
+        let res = call_service(&app, get("/app.js")).await;
+        assert_eq!(read_body(res).await, "console.log(1)");
+
+        let res = call_service(&app, get("/api/unknown")).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = serde_json::from_slice(&read_body(res).await).unwrap();
+        assert_eq!(body["success"], false);
+
+        let res = call_service(
+            &app,
+            TestRequest::post().uri("/some/spa/route").to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "redis")]
+    #[actix_web::test]
+    async fn test_livez_and_readyz_track_dependencies() {
+        let (redis_url, stop_redis) = spawn_redis_proxy();
+        let storage = match Storage::new(":memory:", &redis_url) {
+            Ok(storage) => Arc::new(storage),
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                return;
+            }
+        };
+        let mut state = test_state(storage);
+        let mut config = test_config();
+        config.server.prime_cache = true;
+        state.config = Arc::new(config);
+        let trading_app = state.trading_app.clone();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let probe = |uri: &str| TestRequest::get().uri(uri).to_request();
+
+        assert_eq!(
+            call_service(&app, probe("/api/livez")).await.status(),
+            StatusCode::OK
+        );
+        // dependencies are up but the configured priming has not run yet
+        let resp = call_service(&app, probe("/api/readyz")).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        trading_app.prime_indicator_cache(&[]).await;
+        let body: serde_json::Value = call_and_read_body_json(&app, probe("/api/readyz")).await;
+        assert_eq!(body["data"]["redis"], true);
+        assert_eq!(body["data"]["cache_primed"], true);
+
+        stop_redis.send(()).unwrap();
+        let resp = call_service(&app, probe("/api/readyz")).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(body["data"]["sqlite"], true);
+        assert_eq!(body["data"]["redis"], false);
+        assert_eq!(
+            call_service(&app, probe("/api/livez")).await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_compute_macd_returns_series_for_posted_prices() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+        let post = |body: serde_json::Value| {
+            TestRequest::post()
+                .uri("/api/compute/macd")
+                .set_json(body)
+                .to_request()
+        };
+
+        // short EMA (period 1) tracks the price: 3; long EMA (period 3, k = 0.5):
+        // 1 -> 1.5 -> 2.25; so DIF = 0.75
+        let body: serde_json::Value = call_and_read_body_json(
+            &app,
+            post(serde_json::json!({ "prices": [1.0, 2.0, 3.0], "short": 1, "long": 3, "signal": 1 })),
+        )
+        .await;
+        let points = body["data"].as_array().unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[2]["ts"], 2);
+        assert_eq!(points[2]["dif"], 0.75);
+
+        for bad in [
+            serde_json::json!({ "prices": [] }),
+            serde_json::json!({ "prices": [1.0, 2.0], "short": 5, "long": 3 }),
+            serde_json::json!({ "prices": [1.0, 2.0], "timestamps": [1] }),
+        ] {
+            let resp = call_service(&app, post(bad)).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_json_body_gets_structured_413() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .app_data(json_config(256))
+                .configure(configure_api),
+        )
+        .await;
+        let post = |prices: Vec<f64>| {
+            TestRequest::post()
+                .uri("/api/compute/macd")
+                .set_json(serde_json::json!({ "prices": prices }))
+                .to_request()
+        };
+
+        let resp = call_service(&app, post(vec![10.0; 8])).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = call_service(&app, post(vec![10.25; 500])).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().contains("256"));
+
+        // malformed JSON is still a 400 with the same shape
+        let resp = call_service(
+            &app,
+            TestRequest::post()
+                .uri("/api/compute/macd")
+                .insert_header(header::ContentType::json())
+                .set_payload("{\"prices\": [")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(body["success"], false);
+    }
+
+    #[actix_web::test]
+    async fn test_history_numbers_are_rounded_to_response_decimals() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("ROUND");
+        let base = 1_709_257_000_000; // 2024-03-01 09:36:40 Beijing time
+        for i in 0..30 {
+            let price = 10.0 + (i as f64 / 7.0).sin() / 3.0;
+            storage
+                .save_tick(&tick(&symbol, base + i * 60_000, price))
+                .await
+                .unwrap();
+        }
+        let mut state = test_state(storage);
+        let mut config = test_config();
+        config.server.response_decimals = 2;
+        state.config = Arc::new(config);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/history/{}?date=2024-03-01", symbol))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let points = body["data"]["points"].as_array().unwrap();
+        assert_eq!(points.len(), 30);
+        for point in points {
+            for field in ["price", "dif", "dea", "macd", "ema_short", "ema_long"] {
+                let text = point[field].to_string();
+                let decimals = text.split_once('.').map_or(0, |(_, frac)| frac.len());
+                assert!(
+                    decimals <= 2,
+                    "{} = {} has more than 2 decimals",
+                    field,
+                    text
+                );
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_history_points_carry_ema_lines() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("EMAS");
+        let base = 1_709_257_000_000; // 2024-03-01 09:36:40 Beijing time
+        let ticks: Vec<Tick> = (0..60)
+            .map(|i| tick(&symbol, base + i * 60_000, 10.0 + i as f64 * 0.05))
+            .collect();
+        storage.insert_ticks_ignore(&ticks).await.unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(test_state(storage)))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/history/{}?date=2024-03-01", symbol))
+            .to_request();
+        let body: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let points = body["data"]["points"].as_array().unwrap();
+        assert_eq!(points.len(), 60);
+        assert!(points.iter().all(|p| p["ema_short"].is_number()));
+        let last = points.last().unwrap();
+        let (short, long) = (
+            last["ema_short"].as_f64().unwrap(),
+            last["ema_long"].as_f64().unwrap(),
+        );
+        // on a steady climb the faster EMA leads and both lag the price
+        assert!(short > long);
+        assert!(last["price"].as_f64().unwrap() > short);
+    }
+
+    #[actix_web::test]
+    async fn test_debug_stats_counters_move() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("STATS");
+        storage
+            .save_tick(&tick(&symbol, 1_709_257_000_000, 10.0))
+            .await
+            .unwrap();
+
+        let mut state = test_state(storage);
+        let mut config = test_config();
+        config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(config);
+        state.fetcher = Arc::new(offline_fetcher(false));
+        let fetcher = state.fetcher.clone();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+        let stats = || {
+            TestRequest::get()
+                .uri("/api/debug/stats")
+                .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+                .to_request()
+        };
+
+        let req = TestRequest::get().uri("/api/debug/stats").to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        let before: serde_json::Value = call_and_read_body_json(&app, stats()).await;
+
+        let uri = format!("/api/history/{}?date=2024-03-01", symbol);
+        for _ in 0..2 {
+            let resp = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        assert!(fetcher.get_quote("600733.SH").await.is_err());
+        let stream = call_service(
+            &app,
+            TestRequest::get()
+                .uri(&format!("/api/stream/{}", symbol))
+                .to_request(),
+        )
+        .await;
+
+        let after: serde_json::Value = call_and_read_body_json(&app, stats()).await;
+        let data = &after["data"];
+        assert_eq!(data["day_macd_cache"]["misses"], 1);
+        assert_eq!(data["day_macd_cache"]["hits"], 1);
+        assert_eq!(data["quote_cache"]["misses"], 1);
+        assert!(
+            data["sources"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|s| s["failures"] == 1)
+        );
+        assert_eq!(data["sse_connections"], 1);
+        let backends: &[&str] = if cfg!(feature = "redis") {
+            &["sqlite", "redis"]
+        } else {
+            &["sqlite"]
+        };
+        for backend in backends {
+            let moved = data["storage_queries"][backend].as_u64().unwrap();
+            assert!(moved > before["data"]["storage_queries"][backend].as_u64().unwrap());
+        }
+
+        drop(stream);
+        let closed: serde_json::Value = call_and_read_body_json(&app, stats()).await;
+        assert_eq!(closed["data"]["sse_connections"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_file_backup_runs_alongside_writes() {
+        let symbol = unique_symbol("FILEBAK");
+        let path = std::env::temp_dir().join(format!("{}.db", symbol));
+        let copy_path = std::env::temp_dir().join(format!("{}-copy.db", symbol));
+        let storage = match Storage::new(path.to_str().unwrap(), &test_redis_url()) {
+            Ok(storage) => storage,
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                return;
+            }
+        };
+        let ticks: Vec<Tick> = (0..2_000).map(|i| tick(&symbol, 1_000 + i, 10.0)).collect();
+        storage.save_ticks(&ticks).await.unwrap();
+
+        // the copy reads its own snapshot, so a write issued meanwhile still lands
+        let late = tick(&symbol, 100_000, 11.0);
+        let (backup, write) =
+            futures::join!(storage.backup_to(&copy_path), storage.save_tick(&late));
+        backup.unwrap();
+        assert!(write.unwrap());
+
+        let copy = rusqlite::Connection::open(&copy_path).unwrap();
+        let stored: i64 = copy
+            .query_row(
+                "SELECT COUNT(*) FROM ticks WHERE symbol = ?1",
+                [&symbol],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(stored >= 2_000, "{} rows in the copy", stored);
+        drop(copy);
+        drop(storage);
+        for file in [&path, &copy_path] {
+            for suffix in ["", "-wal", "-shm"] {
+                std::fs::remove_file(format!("{}{}", file.display(), suffix)).ok();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_backup_includes_recent_writes() {
+        let symbol = unique_symbol("WALBAK");
+        let path = std::env::temp_dir().join(format!("{}.db", symbol));
+        let copy_path = std::env::temp_dir().join(format!("{}-copy.db", symbol));
+        let storage = match Storage::new(path.to_str().unwrap(), &test_redis_url()) {
+            Ok(storage) => storage,
+            Err(e) => {
+                eprintln!("skipping storage test, Redis unavailable: {}", e);
+                return;
+            }
+        };
+        // still in the WAL when the backup starts
+        let ticks: Vec<Tick> = (0..50)
+            .map(|i| {
+                tick(
+                    &symbol,
+                    1_709_256_600_000 + i * 60_000,
+                    10.0 + i as f64 * 0.01,
+                )
+            })
+            .collect();
+        storage.save_ticks(&ticks).await.unwrap();
+        storage.backup_to(&copy_path).await.unwrap();
+
+        let copy = rusqlite::Connection::open(&copy_path).unwrap();
+        let mut stmt = copy
+            .prepare("SELECT ts, price FROM ticks WHERE symbol = ?1 ORDER BY ts")
+            .unwrap();
+        let rows: Vec<(i64, f64)> = stmt
+            .query_map([&symbol], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected: Vec<(i64, f64)> = ticks.iter().map(|t| (t.ts.0, t.price)).collect();
+        assert_eq!(rows, expected);
+        drop(stmt);
+        drop(copy);
+        drop(storage);
+        for file in [&path, &copy_path] {
+            for suffix in ["", "-wal", "-shm"] {
+                std::fs::remove_file(format!("{}{}", file.display(), suffix)).ok();
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_admin_backup_is_a_valid_sqlite_copy() {
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("BACKUP");
+        storage
+            .save_tick(&tick(&symbol, 1_709_257_000_000, 10.0))
+            .await
+            .unwrap();
+
+        let mut state = test_state(storage);
+        let mut config = test_config();
+        config.server.admin_token = Some("s3cret".to_string());
+        state.config = Arc::new(config);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/api/admin/backup").to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let req = TestRequest::get()
+            .uri("/api/admin/backup")
+            .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        let body = read_body(resp).await;
+        let path = std::env::temp_dir().join(format!("{}.db", symbol));
+        std::fs::write(&path, &body).unwrap();
+
+        let copy = rusqlite::Connection::open(&path).unwrap();
+        let tables: Vec<String> = copy
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(tables.iter().any(|t| t == "ticks"));
+        let stored: i64 = copy
+            .query_row(
+                "SELECT COUNT(*) FROM ticks WHERE symbol = ?1",
+                [&symbol],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, 1);
+        drop(copy);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_stream_bounds_buffer_for_client_that_never_reads() {
+        use actix_web::body::MessageBody;
+
+        let Some(storage) = test_storage() else {
+            return;
+        };
+        let symbol = unique_symbol("SLOW");
+        for policy in [SlowClientPolicy::DropOldest, SlowClientPolicy::Disconnect] {
+            let mut state = test_state(storage.clone());
+            let mut config = test_config();
+            config.server.slow_client_policy = policy;
+            state.config = Arc::new(config);
+            state.feed = TickFeed::with_capacity(8);
+            let (feed, connections) = (state.feed.clone(), state.sse_connections.clone());
+            let app = init_service(
+                App::new()
+                    .app_data(web::Data::new(state))
+                    .configure(configure_api),
+            )
+            .await;
+
+            let res = call_service(
+                &app,
+                TestRequest::get()
+                    .uri(&format!("/api/stream/{}", symbol))
+                    .to_request(),
+            )
+            .await;
+            // nothing is read while 20 ticks go out
+            for ts in 0..20 {
+                feed.publish(tick(&symbol, ts, 10.0));
+            }
+
+            let mut body = res.into_body();
+            let mut received = Vec::new();
+            while let Ok(Some(chunk)) = tokio::time::timeout(
+                std::time::Duration::from_millis(200),
+                futures::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_next(cx)),
+            )
+            .await
+            {
+                let text = String::from_utf8(chunk.unwrap().to_vec()).unwrap();
+                let json = text.trim().strip_prefix("data: ").unwrap();
+                let tick: Tick = serde_json::from_str(json).unwrap();
+                received.push(tick.ts.as_millis());
+            }
+
+            match policy {
+                // only the newest 8 were held for the client
+                SlowClientPolicy::DropOldest => {
+                    assert_eq!(received, (12..20).collect::<Vec<_>>());
+                    assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+                }
+                SlowClientPolicy::Disconnect => {
+                    assert!(received.is_empty());
+                    assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 0);
+                }
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_stream_with_macd_sends_incremental_points() {
+        use actix_web::body::MessageBody;
+
+        let storage = storage_without_redis();
+        let symbol = unique_symbol("SMACD");
+        let open = 1_709_256_600_000; // 2024-03-01 09:30 Shanghai
+        let ticks: Vec<Tick> = (0..45)
+            .map(|i| tick(&symbol, open + i * 60_000, 10.0 + (i as f64 / 3.0).sin()))
+            .collect();
+        storage.insert_ticks_ignore(&ticks[..40]).await.unwrap();
+        let state = test_state(storage);
+        let feed = state.feed.clone();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .configure(configure_api),
+        )
+        .await;
+
+        // two clients on the same symbol see the same points
+        let mut bodies = Vec::new();
+        for _ in 0..2 {
+            let res = call_service(
+                &app,
+                TestRequest::get()
+                    .uri(&format!("/api/stream/{}?macd=true", symbol))
+                    .to_request(),
+            )
+            .await;
+            bodies.push(res.into_body());
+        }
+        for t in &ticks[40..] {
+            feed.publish(t.clone());
+        }
+
+        let prices: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+        let expected = compute_macd_series(&prices, 12, 26, 9);
+        for mut body in bodies {
+            for (t, point) in ticks[40..].iter().zip(&expected[40..]) {
+                let chunk =
+                    futures::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_next(cx))
+                        .await
+                        .unwrap()
+                        .unwrap();
+                let text = String::from_utf8(chunk.to_vec()).unwrap();
+                let (tick_event, macd_event) = text.split_once("\n\n").unwrap();
+                let streamed: Tick =
+                    serde_json::from_str(tick_event.strip_prefix("data: ").unwrap()).unwrap();
+                assert_eq!(streamed.ts, t.ts);
+                let json = macd_event
+                    .trim()
+                    .strip_prefix("event: macd\ndata: ")
+                    .unwrap();
+                let macd: serde_json::Value = serde_json::from_str(json).unwrap();
+                assert_eq!(macd["ts"], t.ts.as_millis());
+                let dif = macd["dif"].as_f64().unwrap();
+                assert!((dif - point.dif).abs() < 1e-4, "{} vs {}", dif, point.dif);
+            }
+        }
+    }
+
+    fn cross_alert() -> Alert {
+        Alert {
+            symbol: "600733.SH".to_string(),
+            ts: TsMillis(1_709_257_260_000), // 2024-03-01 09:41:00 Beijing time
+            price: 10.52,
+            direction: CrossDirection::Bullish,
+        }
+    }
+
+    #[test]
+    fn test_slack_payload_formats_alert() {
+        let slack = SlackNotifier::new(
+            HttpClient::new(),
+            "http://127.0.0.1:1/hook".to_string(),
+            std::time::Duration::from_secs(1),
+            chrono_tz::Asia::Shanghai,
+        );
+        let payload = slack.payload(&cross_alert());
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "text": ":chart_with_upwards_trend: *600733.SH* bullish MACD cross at 10.52 (2024-03-01 09:41:00 CST)"
+            })
+        );
+
+        let bearish = Alert {
+            direction: CrossDirection::Bearish,
+            ..cross_alert()
+        };
+        let text = slack.payload(&bearish)["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(text.starts_with(":chart_with_downwards_trend: *600733.SH* bearish"));
+    }
+
+    /// Minimal SMTP relay on a random port; each message's DATA is sent on the channel.
+    async fn fake_smtp_relay() -> (u16, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = socket.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    let _ = write.write_all(b"220 fake ESMTP\r\n").await;
+                    let mut data: Option<String> = None;
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if let Some(body) = data.as_mut() {
+                            if line == "." {
+                                let _ = tx.send(data.take().unwrap());
+                                let _ = write.write_all(b"250 queued\r\n").await;
+                            } else {
+                                body.push_str(&line);
+                                body.push('\n');
+                            }
+                            continue;
+                        }
+                        let reply: &[u8] = match line.get(..4).map(str::to_ascii_uppercase) {
+                            Some(cmd) if cmd == "DATA" => {
+                                data = Some(String::new());
+                                b"354 go ahead\r\n"
+                            }
+                            Some(cmd) if cmd == "QUIT" => {
+                                let _ = write.write_all(b"221 bye\r\n").await;
+                                break;
+                            }
+                            _ => b"250 ok\r\n",
+                        };
+                        let _ = write.write_all(reply).await;
+                    }
+                });
+            }
+        });
+        (port, rx)
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_continues_past_failing_notifier() {
+        struct Recording(Arc<std::sync::Mutex<Vec<String>>>);
+        struct Failing;
+
+        #[async_trait::async_trait]
+        impl Notifier for Recording {
+            fn name(&self) -> &str {
+                "recording"
+            }
+            async fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push(alert.summary());
+                Ok(())
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl Notifier for Failing {
+            fn name(&self) -> &str {
+                "failing"
+            }
+            async fn notify(&self, _alert: &Alert) -> anyhow::Result<()> {
+                anyhow::bail!("upstream refused")
+            }
+        }
+
+        let tz = chrono_tz::Asia::Shanghai;
+        let (port, mut mails) = fake_smtp_relay().await;
+        let smtp = SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "MACD alerts <alerts@localhost>".to_string(),
+            to: vec!["trader@localhost".to_string(), "desk@localhost".to_string()],
+        };
+        let timeout = std::time::Duration::from_secs(5);
+        let email = EmailNotifier::new(&smtp, timeout, tz).unwrap();
+        // nothing listens on port 1
+        let dead_relay = AlertsConfig {
+            smtp: Some(SmtpConfig {
+                port: 1,
+                ..smtp.clone()
+            }),
+            ..AlertsConfig::default()
+        };
+        let mut from_config = notifiers_from_config(&dead_relay, tz).unwrap();
+        assert_eq!(from_config.len(), 1);
+        assert_eq!(from_config[0].name(), "email");
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut notifiers: Vec<Box<dyn Notifier>> = vec![
+            Box::new(Recording(seen.clone())),
+            Box::new(Failing),
+            Box::new(email),
+            from_config.remove(0),
+            Box::new(Recording(seen.clone())),
+        ];
+        assert_eq!(dispatch(&notifiers, &cross_alert()).await, 3);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["600733.SH bullish MACD cross at 10.52"; 2]
+        );
+        let mail = mails.recv().await.unwrap();
+        assert!(
+            mail.contains("Subject: 600733.SH bullish MACD cross at 10.52"),
+            "{}",
+            mail
+        );
+        assert!(mail.contains("trader@localhost") && mail.contains("desk@localhost"));
+        assert!(mail.contains("(2024-03-01 09:41:00 CST)"), "{}", mail);
+        assert!(mails.try_recv().is_err());
+
+        notifiers.clear();
+        assert_eq!(dispatch(&notifiers, &cross_alert()).await, 0);
+
+        let bad_sender = SmtpConfig {
+            from: "not a mailbox".to_string(),
+            ..smtp
+        };
+        assert!(EmailNotifier::new(&bad_sender, timeout, tz).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_alert_engine_keeps_up_with_the_feed_while_a_notifier_stalls() {
+        struct Stalled(
+            Arc<tokio::sync::Semaphore>,
+            Arc<std::sync::Mutex<Vec<Alert>>>,
+        );
+
+        #[async_trait::async_trait]
+        impl Notifier for Stalled {
+            fn name(&self) -> &str {
+                "stalled"
+            }
+            async fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+                self.0.acquire().await?.forget();
+                self.1.lock().unwrap().push(alert.clone());
+                Ok(())
+            }
+        }
+
+        let start = 1_709_256_600_000;
+        let mut prices = vec![10.0; 60];
+        prices.extend((1..=10).map(|i| 10.0 + i as f64 * 0.1));
+        prices.extend((1..=20).map(|i| 11.0 - i as f64 * 0.1));
+        prices.extend((1..=20).map(|i| 9.0 + i as f64 * 0.1));
+
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let engine = AlertEngine::new(
+            vec![Box::new(Stalled(gate.clone(), seen.clone()))],
+            &test_config().trading,
+        );
+        // far fewer slots than ticks, so an engine waiting on delivery would lag
+        let (sender, receiver) = tokio::sync::broadcast::channel(4);
+        let running = tokio::spawn(engine.run(receiver));
+        for (i, &price) in prices.iter().enumerate() {
+            sender
+                .send(tick("600733.SH", start + i as i64 * 60_000, price))
+                .unwrap();
+            tokio::task::yield_now().await;
+        }
+        drop(sender);
+        gate.add_permits(prices.len());
+        running.await.unwrap();
+
+        let directions: Vec<CrossDirection> =
+            seen.lock().unwrap().iter().map(|a| a.direction).collect();
+        assert_eq!(
+            directions,
+            vec![
+                CrossDirection::Bullish,
+                CrossDirection::Bearish,
+                CrossDirection::Bullish,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alerts_follow_analysis_signal_rules() {
+        let mut trading = test_config().trading;
+        trading.min_histogram = 0.02;
+        trading.histogram_confirm_bars = 2;
+        trading.cooldown_secs = 60 * 60;
+        trading.signal_windows = serde_json::from_value(serde_json::json!([
+            { "from": "open", "minutes": 120 },
+            { "from": "close", "minutes": 60 },
+        ]))
+        .unwrap();
+        let calendar = trading.calendar();
+
+        // a minute per tick through both sessions of 2024-03-01, with small wiggles on
+        // a slow swing so some crosses fail the histogram test
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let minutes = (0..=120)
+            .map(|m| date.and_hms_opt(9, 30, 0).unwrap() + chrono::Duration::minutes(m))
+            .chain(
+                (0..=120)
+                    .map(|m| date.and_hms_opt(13, 0, 0).unwrap() + chrono::Duration::minutes(m)),
+            );
+        let ticks: Vec<Tick> = minutes
+            .enumerate()
+            .map(|(i, t)| {
+                let x = i as f64;
+                let price = 10.0 + 0.5 * (x / 9.0).sin() + 0.1 * (x / 3.0).sin();
+                tick(
+                    "600733.SH",
+                    market_local_to_millis(calendar.tz, t),
+                    (price * 100.0).round() / 100.0,
+                )
+            })
+            .collect();
+
+        let points: Vec<(TsMillis, f64)> = ticks.iter().map(|t| (t.ts, t.price)).collect();
+        let macd = compute_macd_series(
+            &points,
+            trading.macd_short,
+            trading.macd_long,
+            trading.macd_signal,
+        );
+        let warmup = trading.macd_warmup.unwrap_or(trading.macd_long);
+        let raw = macd_cross_events(&macd, warmup);
+        let confirmed = confirm_crosses(
+            raw.clone(),
+            &macd,
+            trading.min_histogram,
+            trading.histogram_confirm_bars,
+        );
+        let windowed = apply_session_windows(confirmed.clone(), &calendar, &trading.signal_windows);
+        let cooled = apply_cooldown(windowed.clone(), trading.cooldown_secs as i64 * 1000);
+        // every rule drops something here
+        assert!(raw.len() > confirmed.len());
+        assert!(confirmed.len() > windowed.len());
+        assert!(windowed.len() > cooled.len());
+
+        let min_points = trading.min_points.unwrap_or(trading.macd_long * 2);
+        let expected: Vec<(TsMillis, CrossDirection)> = cooled
+            .iter()
+            // the analysis judges min_points on the whole day, live alerts on the ticks
+            // so far; no cross here is confirmed within a few ticks of min_points
+            .filter(|e| e.index >= min_points)
+            .map(|e| (e.ts, e.direction))
+            .collect();
+        assert!(!expected.is_empty() && expected.len() < cooled.len());
+
+        let mut engine = AlertEngine::new(Vec::new(), &trading);
+        let alerts: Vec<(TsMillis, CrossDirection)> = ticks
+            .iter()
+            .filter_map(|t| engine.on_tick(t))
+            .map(|a| (a.ts, a.direction))
+            .collect();
+        assert_eq!(alerts, expected);
+    }
+
+    #[tokio::test]
+    async fn test_alert_engine_dispatches_live_crosses_with_cooldown() {
+        struct Recording(Arc<std::sync::Mutex<Vec<Alert>>>);
+
+        #[async_trait::async_trait]
+        impl Notifier for Recording {
+            fn name(&self) -> &str {
+                "recording"
+            }
+            async fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push(alert.clone());
+                Ok(())
+            }
+        }
+
+        // flat through the warmup, then up, down and up again a minute per tick
+        let start = 1_709_256_600_000;
+        let mut prices = vec![10.0; 60];
+        prices.extend((1..=10).map(|i| 10.0 + i as f64 * 0.1));
+        prices.extend((1..=20).map(|i| 11.0 - i as f64 * 0.1));
+        prices.extend((1..=20).map(|i| 9.0 + i as f64 * 0.1));
+        let ticks: Vec<Tick> = prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| tick("600733.SH", start + i as i64 * 60_000, price))
+            .collect();
+
+        let mut trading = test_config().trading;
+        for (cooldown_secs, expected) in [
+            (
+                0,
+                vec![
+                    CrossDirection::Bullish,
+                    CrossDirection::Bearish,
+                    CrossDirection::Bullish,
+                ],
+            ),
+            // the second bullish cross comes 30 minutes after the first
+            (3600, vec![CrossDirection::Bullish, CrossDirection::Bearish]),
+        ] {
+            trading.cooldown_secs = cooldown_secs;
+            let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let mut engine = AlertEngine::new(vec![Box::new(Recording(seen.clone()))], &trading);
+            let mut delivered = 0;
+            for t in &ticks {
+                delivered += engine.handle(t).await;
+                // another symbol's ticks must not disturb this one's MACD
+                engine.handle(&tick("000001.SZ", t.ts.0, 5.0)).await;
+            }
+            let seen = seen.lock().unwrap();
+            let directions: Vec<CrossDirection> = seen.iter().map(|a| a.direction).collect();
+            assert_eq!(directions, expected, "cooldown {}s", cooldown_secs);
+            assert_eq!(delivered, expected.len());
+            assert!(
+                seen.iter()
+                    .all(|a| a.symbol == "600733.SH" && a.ts.0 >= start + 60 * 60_000)
+            );
+        }
+    }
 }
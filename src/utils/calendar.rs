@@ -0,0 +1,137 @@
+// src/utils/calendar.rs
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+/// Continuous trading sessions of the Shanghai and Shenzhen exchanges in their local
+/// time (the calendar's `tz`): morning 09:30-11:30 and afternoon 13:00-15:00, both ends
+/// inclusive. Markets with other hours are not modelled.
+pub const SESSIONS: [(NaiveTime, NaiveTime); 2] = [
+    (
+        NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+        NaiveTime::from_hms_opt(11, 30, 0).unwrap(),
+    ),
+    (
+        NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(15, 0, 0).unwrap(),
+    ),
+];
+
+/// The session end a `SessionWindow` is measured from.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEdge {
+    Open,
+    Close,
+}
+
+/// What a `SessionWindow` is measured within.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowScope {
+    /// the trading day, from the first session's open to the last one's close
+    #[default]
+    Day,
+    /// each of the `SESSIONS` separately
+    Session,
+}
+
+/// The first (`from = "open"`) or last (`from = "close"`) `minutes` of the trading day,
+/// e.g. 09:30-10:00 for the first 30 minutes, or with `of = "session"` of every one of
+/// the `SESSIONS` (09:30-10:00 and 13:00-13:30). Lunch is never part of a window.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SessionWindow {
+    pub from: SessionEdge,
+    pub minutes: u32,
+    #[serde(default)]
+    pub of: WindowScope,
+}
+
+impl SessionWindow {
+    /// This window within `open..=close`, never reaching past it.
+    fn bounds(&self, open: NaiveTime, close: NaiveTime) -> (NaiveTime, NaiveTime) {
+        let span = chrono::Duration::minutes(self.minutes as i64).min(close - open);
+        match self.from {
+            SessionEdge::Open => (open, open + span),
+            SessionEdge::Close => (close - span, close),
+        }
+    }
+
+    /// Whether the in-session `time` falls inside this window.
+    fn contains(&self, time: NaiveTime) -> bool {
+        let inside = |(open, close): (NaiveTime, NaiveTime)| {
+            let (start, end) = self.bounds(open, close);
+            start <= time && time <= end
+        };
+        match self.of {
+            WindowScope::Day => inside((SESSIONS[0].0, SESSIONS[SESSIONS.len() - 1].1)),
+            WindowScope::Session => SESSIONS.iter().copied().any(inside),
+        }
+    }
+}
+
+/// Exchange trading days: weekdays that are not listed as holidays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradingCalendar {
+    pub tz: Tz,
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for TradingCalendar {
+    fn default() -> Self {
+        Self {
+            tz: chrono_tz::Asia::Shanghai,
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl TradingCalendar {
+    pub fn new(tz: Tz, holidays: Vec<NaiveDate>) -> Self {
+        Self { tz, holidays }
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// Whether `at` falls inside one of the `SESSIONS` of a trading day; false before
+    /// the open, over lunch, after the close and on weekends and holidays.
+    pub fn is_session_open(&self, at: DateTime<Utc>) -> bool {
+        let local = at.with_timezone(&self.tz);
+        let time = local.time();
+        self.is_trading_day(local.date_naive())
+            && SESSIONS
+                .iter()
+                .any(|(open, close)| *open <= time && time <= *close)
+    }
+
+    /// Whether `at` falls inside one of `windows` of a session on a trading day, both
+    /// ends inclusive like `is_session_open`.
+    pub fn in_session_window(&self, at: DateTime<Utc>, windows: &[SessionWindow]) -> bool {
+        let local = at.with_timezone(&self.tz);
+        let time = local.time();
+        self.is_trading_day(local.date_naive())
+            && SESSIONS
+                .iter()
+                .any(|(open, close)| *open <= time && time <= *close)
+            && windows.iter().any(|window| window.contains(time))
+    }
+
+    /// The `n`th trading day before `date` (`date` itself is not counted), so with
+    /// `n = 1` on a Monday this is the previous Friday. `n = 0` returns `date`.
+    pub fn trading_days_before(&self, date: NaiveDate, n: usize) -> NaiveDate {
+        let mut day = date;
+        let mut remaining = n;
+        while remaining > 0 {
+            let Some(prev) = day.pred_opt() else {
+                break;
+            };
+            day = prev;
+            if self.is_trading_day(day) {
+                remaining -= 1;
+            }
+        }
+        day
+    }
+}
@@ -0,0 +1,37 @@
+// src/utils/csv.rs
+
+/// A type that renders as one CSV line under a fixed header.
+pub trait CsvRecord {
+    /// comma-separated column names
+    const HEADER: &'static str;
+
+    /// the record's fields, in `HEADER` order
+    fn fields(&self) -> Vec<String>;
+}
+
+/// `records` as a CSV document with a header line, `\n` line endings.
+pub fn to_csv<T: CsvRecord>(records: &[T]) -> String {
+    let mut out = String::with_capacity(T::HEADER.len() + 1 + records.len() * 64);
+    out.push_str(T::HEADER);
+    out.push('\n');
+    for record in records {
+        let fields: Vec<String> = record.fields().iter().map(|f| escape(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Quote a field containing a comma, quote or line break, doubling inner quotes.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// An optional value as its text, or an empty field.
+pub fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,56 @@
+// src/utils/downsample.rs
+
+/// Largest-Triangle-Three-Buckets downsampling. Returns at most `threshold` points,
+/// always keeping the first and last; points are picked (never interpolated) so the
+/// output is a subset of the input in the same order. `xy` maps a point to its
+/// chart coordinates. Inputs no longer than `threshold` (or thresholds below 3) are
+/// returned unchanged.
+pub fn lttb<T: Clone>(points: &[T], threshold: usize, xy: impl Fn(&T) -> (f64, f64)) -> Vec<T> {
+    let n = points.len();
+    if threshold >= n || threshold < 3 {
+        return points.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(threshold);
+    out.push(points[0].clone());
+
+    // the first and last points take one bucket each, the rest share the remainder
+    let bucket_size = (n - 2) as f64 / (threshold - 2) as f64;
+    let mut a = 0;
+
+    for i in 0..threshold - 2 {
+        let start = (i as f64 * bucket_size) as usize + 1;
+        let end = ((i + 1) as f64 * bucket_size) as usize + 1;
+
+        // average of the next bucket is the third triangle vertex
+        let next_start = end;
+        let next_end = (((i + 2) as f64 * bucket_size) as usize + 1).min(n);
+        let next = &points[next_start..next_end.max(next_start + 1).min(n)];
+        let (mut avg_x, mut avg_y) = (0.0, 0.0);
+        for p in next {
+            let (x, y) = xy(p);
+            avg_x += x;
+            avg_y += y;
+        }
+        avg_x /= next.len() as f64;
+        avg_y /= next.len() as f64;
+
+        let (ax, ay) = xy(&points[a]);
+        let mut best = start;
+        let mut best_area = -1.0;
+        for (j, p) in points.iter().enumerate().take(end).skip(start) {
+            let (x, y) = xy(p);
+            let area = ((ax - avg_x) * (y - ay) - (ax - x) * (avg_y - ay)).abs();
+            if area > best_area {
+                best_area = area;
+                best = j;
+            }
+        }
+
+        out.push(points[best].clone());
+        a = best;
+    }
+
+    out.push(points[n - 1].clone());
+    out
+}
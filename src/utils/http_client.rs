@@ -0,0 +1,31 @@
+// src/utils/http_client.rs
+use reqwest::{Client, RequestBuilder};
+use std::time::Duration;
+
+/// Thin wrapper around a shared `reqwest::Client`. Request timeouts are set per call
+/// since each upstream source has its own.
+const CONNECT_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+            .gzip(true)
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self { client }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+}
@@ -0,0 +1,51 @@
+// src/utils/json_stream.rs
+use actix_web::web::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+
+/// Array elements serialized into each streamed chunk.
+const ITEMS_PER_CHUNK: usize = 256;
+
+/// Stream `envelope` as JSON with `items` written element by element into the array
+/// field `field`, which must serialize as an empty array (`"field":[]`) in `envelope`.
+/// The bytes are identical to serializing the envelope with the items in place, but
+/// the full document is never held in memory at once.
+pub fn stream_json_with_array<E, T>(
+    envelope: &E,
+    field: &str,
+    items: Vec<T>,
+) -> serde_json::Result<impl Stream<Item = serde_json::Result<Bytes>> + use<E, T>>
+where
+    E: Serialize,
+    T: Serialize + 'static,
+{
+    let skeleton = serde_json::to_string(envelope)?;
+    let marker = format!("{}:[]", serde_json::to_string(field)?);
+    let split = skeleton.find(&marker).ok_or_else(|| {
+        <serde_json::Error as serde::ser::Error>::custom(format!(
+            "envelope has no empty `{}` array",
+            field
+        ))
+    })?;
+    // keep the opening bracket in the head and the closing one in the tail
+    let head = Bytes::from(skeleton[..split + marker.len() - 1].to_string());
+    let tail = Bytes::from(skeleton[split + marker.len() - 1..].to_string());
+
+    let body = stream::iter(items)
+        .chunks(ITEMS_PER_CHUNK)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut buf = Vec::new();
+            for (j, item) in chunk.iter().enumerate() {
+                if i > 0 || j > 0 {
+                    buf.push(b',');
+                }
+                serde_json::to_writer(&mut buf, item)?;
+            }
+            Ok(Bytes::from(buf))
+        });
+
+    Ok(stream::once(async { Ok(head) })
+        .chain(body)
+        .chain(stream::once(async { Ok(tail) })))
+}
@@ -0,0 +1,11 @@
+// src/utils/mod.rs
+pub mod calendar;
+pub mod csv;
+pub mod downsample;
+pub mod http_client;
+pub mod json_stream;
+pub mod negotiate;
+pub mod number_format;
+pub mod tick_size;
+pub mod time;
+pub mod worker_pool;
@@ -0,0 +1,47 @@
+// src/utils/negotiate.rs
+use actix_web::HttpRequest;
+use actix_web::http::header::{Accept, Header, Quality};
+use actix_web::mime::{self, Mime};
+
+/// Response body formats an endpoint can offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Representation {
+    Json,
+    Csv,
+}
+
+impl Representation {
+    pub fn mime(&self) -> Mime {
+        match self {
+            Representation::Json => mime::APPLICATION_JSON,
+            Representation::Csv => mime::TEXT_CSV_UTF_8,
+        }
+    }
+
+    fn matches(&self, range: &Mime) -> bool {
+        let own = self.mime();
+        (range.type_() == mime::STAR || range.type_() == own.type_())
+            && (range.subtype() == mime::STAR || range.subtype() == own.subtype())
+    }
+}
+
+/// The first of `offered` the client accepts, trying its `Accept` ranges from most
+/// to least preferred (`q=0` means "not this"). A missing or unparsable header accepts
+/// anything, so `offered[0]` is the default. `None` means 406 Not Acceptable.
+pub fn negotiate(req: &HttpRequest, offered: &[Representation]) -> Option<Representation> {
+    let accept = match Accept::parse(req) {
+        Ok(accept) if !accept.is_empty() => accept,
+        _ => return offered.first().copied(),
+    };
+    let acceptable = Accept(
+        accept
+            .0
+            .into_iter()
+            .filter(|item| item.quality > Quality::ZERO)
+            .collect(),
+    );
+    acceptable
+        .ranked()
+        .iter()
+        .find_map(|range| offered.iter().find(|r| r.matches(range)).copied())
+}
@@ -0,0 +1,186 @@
+// src/utils/number_format.rs
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// How prices, volumes and MACD values appear in JSON (`server.number_format`).
+/// `Decimal` quote/K-line fields and `f64` tick/bar/MACD fields use the same form, so a
+/// client never has to handle both for one kind of value. Timestamps and counts stay
+/// numbers either way.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberFormat {
+    /// JSON numbers; convenient, but decimals past f64 precision are rounded
+    #[default]
+    Number,
+    /// JSON strings such as `"10.62"`, parsed by the client. `Decimal` fields keep every
+    /// digit; `f64` fields are rounded to `FLOAT_STRING_DECIMALS` places
+    String,
+}
+
+/// Decimal places an `f64` keeps as a string: more than any price or indicator needs,
+/// and few enough to drop binary noise such as the tail of `10.520000000000001`.
+const FLOAT_STRING_DECIMALS: usize = 10;
+
+static FORMAT: OnceLock<NumberFormat> = OnceLock::new();
+
+thread_local! {
+    static SCOPED: Cell<Option<NumberFormat>> = const { Cell::new(None) };
+}
+
+/// Set the process-wide format; only the first call has an effect.
+pub fn init(format: NumberFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// The format serializers on this thread use.
+pub fn current() -> NumberFormat {
+    SCOPED
+        .get()
+        .unwrap_or_else(|| FORMAT.get().copied().unwrap_or_default())
+}
+
+impl NumberFormat {
+    /// Run `f` with this format on the current thread only.
+    #[allow(dead_code)] // only tests switch formats mid-process
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let previous = SCOPED.replace(Some(self));
+        let result = f();
+        SCOPED.set(previous);
+        result
+    }
+}
+
+/// Either form is read back, so JSON written under one format (e.g. ticks cached in
+/// Redis) survives a switch to the other.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFloat {
+    Number(f64),
+    Text(String),
+}
+
+impl RawFloat {
+    fn parse<E: serde::de::Error>(self) -> Result<f64, E> {
+        match self {
+            RawFloat::Number(value) => Ok(value),
+            RawFloat::Text(text) => text.trim().parse().map_err(E::custom),
+        }
+    }
+}
+
+/// `value` rounded to `FLOAT_STRING_DECIMALS` places, without trailing zeros.
+fn float_string(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let fixed = format!("{:.*}", FLOAT_STRING_DECIMALS, value);
+    match fixed.trim_end_matches('0').trim_end_matches('.') {
+        "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// `#[serde(with = ...)]` for `f64` fields.
+pub mod float {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        match current() {
+            NumberFormat::Number => serializer.serialize_f64(*value),
+            NumberFormat::String => serializer.serialize_str(&float_string(*value)),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        RawFloat::deserialize(deserializer)?.parse()
+    }
+}
+
+/// `#[serde(default, with = ...)]` for `Option<f64>` fields.
+pub mod option_float {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => float::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<f64>, D::Error> {
+        Option::<RawFloat>::deserialize(deserializer)?
+            .map(RawFloat::parse)
+            .transpose()
+    }
+}
+
+/// `#[serde(with = ...)]` for `Decimal` fields; reads numbers and strings alike.
+pub mod decimal {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal::prelude::ToPrimitive;
+
+    pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        match current() {
+            NumberFormat::Number => match value.to_f64() {
+                Some(float) => serializer.serialize_f64(float),
+                None => serializer.serialize_str(&value.to_string()),
+            },
+            NumberFormat::String => serializer.serialize_str(&value.to_string()),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        <Decimal as Deserialize>::deserialize(deserializer)
+    }
+}
+
+/// `#[serde(default, with = ...)]` for `Option<Decimal>` fields.
+pub mod option_decimal {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Decimal>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => decimal::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Decimal>, D::Error> {
+        Option::<Decimal>::deserialize(deserializer)
+    }
+}
+
+/// An `f64` serialized through `float`, for values inside containers.
+struct Float(f64);
+
+impl Serialize for Float {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        float::serialize(&self.0, serializer)
+    }
+}
+
+/// `#[serde(serialize_with = ...)]` for `(ts, value)` series keyed by name.
+pub fn serialize_series_map<S, T>(
+    map: &BTreeMap<String, Vec<(T, f64)>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    serializer.collect_map(map.iter().map(|(name, series)| {
+        let series: Vec<(&T, Float)> = series.iter().map(|(ts, v)| (ts, Float(*v))).collect();
+        (name, series)
+    }))
+}
@@ -0,0 +1,56 @@
+// src/utils/tick_size.rs
+use serde::Deserialize;
+
+/// A minimum price increment for symbols whose code starts with `prefix`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TickSizeRule {
+    pub prefix: String,
+    pub tick: f64,
+}
+
+/// Minimum price increments by symbol prefix; the longest matching prefix wins and
+/// anything unmatched uses `default` (0.01, the main-board tick).
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TickSizeTable {
+    #[serde(default = "default_tick")]
+    pub default: f64,
+    #[serde(default)]
+    pub rules: Vec<TickSizeRule>,
+}
+
+fn default_tick() -> f64 {
+    0.01
+}
+
+impl Default for TickSizeTable {
+    fn default() -> Self {
+        Self {
+            default: default_tick(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl TickSizeTable {
+    /// Tick size for `symbol`, matched on the code before any `.SH`/`.SZ` suffix.
+    pub fn tick_for(&self, symbol: &str) -> f64 {
+        let code = symbol.split('.').next().unwrap_or(symbol);
+        self.rules
+            .iter()
+            .filter(|rule| code.starts_with(&rule.prefix))
+            .max_by_key(|rule| rule.prefix.len())
+            .map_or(self.default, |rule| rule.tick)
+    }
+
+    /// Round `price` to the nearest valid price for `symbol`.
+    pub fn round_to_tick(&self, price: f64, symbol: &str) -> f64 {
+        let tick = self.tick_for(symbol);
+        if tick <= 0.0 || !tick.is_finite() || !price.is_finite() {
+            return price;
+        }
+        // snap to the tick, then trim the float noise left by the multiplication
+        let decimals = (-tick.log10()).ceil().max(0.0) as i32;
+        let scale = 10f64.powi(decimals);
+        ((price / tick).round() * tick * scale).round() / scale
+    }
+}
@@ -0,0 +1,124 @@
+// src/utils/time.rs
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Current calendar date on the exchange, not in UTC.
+pub fn market_today(tz: Tz) -> NaiveDate {
+    market_date(tz, Utc::now())
+}
+
+/// Calendar date of `instant` in the exchange timezone.
+pub fn market_date(tz: Tz, instant: DateTime<Utc>) -> NaiveDate {
+    instant.with_timezone(&tz).date_naive()
+}
+
+/// Interpret a wall-clock time on the exchange as epoch milliseconds.
+pub fn market_local_to_millis(tz: Tz, local: NaiveDateTime) -> i64 {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or_else(|| local.and_utc().timestamp_millis())
+}
+
+/// `[start, end)` in epoch millis from the exchange's midnight on `from` to its
+/// midnight after `to`, so each date is that market's calendar day rather than UTC's.
+pub fn market_day_range_ms(tz: Tz, from: NaiveDate, to: NaiveDate) -> (i64, i64) {
+    let start = market_local_to_millis(tz, from.and_time(NaiveTime::MIN));
+    let end = match to.succ_opt() {
+        Some(next) => market_local_to_millis(tz, next.and_time(NaiveTime::MIN)),
+        None => i64::MAX,
+    };
+    (start, end)
+}
+
+/// An instant as epoch milliseconds, the unit of every stored and served timestamp.
+/// Serialized as the bare number.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct TsMillis(pub i64);
+
+impl TsMillis {
+    pub fn now() -> Self {
+        Self::from_datetime(&Utc::now())
+    }
+
+    pub fn from_datetime<T: TimeZone>(dt: &DateTime<T>) -> Self {
+        Self(dt.timestamp_millis())
+    }
+
+    /// `None` outside chrono's representable range.
+    pub fn to_datetime(self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.0)
+    }
+
+    pub fn from_secs(secs: i64) -> Self {
+        Self(secs.saturating_mul(1000))
+    }
+
+    /// Whole seconds, rounded towards negative infinity.
+    pub fn as_secs(self) -> i64 {
+        self.0.div_euclid(1000)
+    }
+
+    pub fn as_millis(self) -> i64 {
+        self.0
+    }
+}
+
+impl From<i64> for TsMillis {
+    fn from(millis: i64) -> Self {
+        Self(millis)
+    }
+}
+
+impl From<TsMillis> for i64 {
+    fn from(ts: TsMillis) -> Self {
+        ts.0
+    }
+}
+
+impl std::fmt::Display for TsMillis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Later minus earlier, in milliseconds.
+impl std::ops::Sub for TsMillis {
+    type Output = i64;
+
+    fn sub(self, earlier: Self) -> i64 {
+        self.0 - earlier.0
+    }
+}
+
+impl std::ops::Add<i64> for TsMillis {
+    type Output = Self;
+
+    fn add(self, millis: i64) -> Self {
+        Self(self.0 + millis)
+    }
+}
+
+impl std::ops::Sub<i64> for TsMillis {
+    type Output = Self;
+
+    fn sub(self, millis: i64) -> Self {
+        Self(self.0 - millis)
+    }
+}
+
+impl rusqlite::ToSql for TsMillis {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl rusqlite::types::FromSql for TsMillis {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        i64::column_result(value).map(Self)
+    }
+}
@@ -0,0 +1,63 @@
+// src/utils/worker_pool.rs
+use anyhow::{Result, anyhow};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tokio::sync::oneshot;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed number of OS threads running blocking jobs in submission order. A burst of
+/// jobs queues here instead of taking over tokio's shared blocking pool.
+#[derive(Debug)]
+pub struct WorkerPool {
+    sender: mpsc::Sender<Job>,
+}
+
+impl WorkerPool {
+    /// Start `threads` workers (at least one) named `{name}-{i}`. They exit once the
+    /// pool is dropped and the queued jobs have run.
+    pub fn new(name: &str, threads: usize) -> Self {
+        let threads = threads.max(1);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("{}-{}", name, i))
+                .spawn(move || {
+                    loop {
+                        // the lock is only held while waiting for the next job
+                        let job = match receiver.lock() {
+                            Ok(rx) => rx.recv(),
+                            Err(_) => return,
+                        };
+                        let Ok(job) = job else {
+                            return;
+                        };
+                        // a panicking job fails its own caller, not the worker
+                        let _ = catch_unwind(AssertUnwindSafe(job));
+                    }
+                })
+                .expect("failed to spawn worker thread");
+        }
+        Self { sender }
+    }
+
+    /// Run `f` on a worker and wait for its result without blocking the runtime.
+    pub async fn run<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(Box::new(move || {
+                // the caller may have stopped waiting
+                let _ = tx.send(f());
+            }))
+            .map_err(|_| anyhow!("worker pool has shut down"))?;
+        rx.await.map_err(|_| anyhow!("worker pool job panicked"))
+    }
+}
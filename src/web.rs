@@ -1,45 +1,76 @@
 // src/web.rs
-use crate::app::TradingApp;
-use crate::config::AppConfig;
-use crate::indicators::{MACDPoint, compute_macd_series};
-use actix_web::{App, HttpResponse, HttpServer, Responder, get, post, web};
+use crate::app::{AnalysisOptions, MarketSummary, SymbolInfo, TradingApp, non_positive_price};
+use crate::backtest::{MAX_SWEEP_COMBINATIONS, ParamGrid};
+use crate::config::{AppConfig, SlowClientPolicy, clamp_days};
+use crate::data_fetch::DataFetcher;
+use crate::error::AppError;
+use crate::feed::{TickFeed, is_replayed, replay_day};
+use crate::indicators::{
+    CrossKind, MACDPoint, PriceSource, bar_prices, compute_macd_series,
+    compute_macd_series_flagged, compute_macd_series_log, mark_warmup, rebase_to_common_anchor,
+    return_correlation, select_cross_events,
+};
+use crate::models::{Page, RunMode};
+use crate::report::ReportFormat;
+use crate::sim::generate_mock_day;
+use crate::storage::{Bar, StorageQueryCounts, Tick, aggregate_bars_stamped};
+use crate::tasks::{TaskRegistry, TaskState};
+use crate::utils::csv::to_csv;
+use crate::utils::downsample::lttb;
+use crate::utils::json_stream::stream_json_with_array;
+use crate::utils::negotiate::{Representation, negotiate};
+use crate::utils::time::{
+    TsMillis, market_date, market_day_range_ms, market_local_to_millis, market_today,
+};
+use actix_files::{Files, NamedFile};
+use actix_web::dev::{ServiceRequest, ServiceResponse, fn_service};
+use actix_web::error::JsonPayloadError;
+use actix_web::http::StatusCode;
+use actix_web::http::header::{
+    ContentDisposition, ContentType, DispositionParam, DispositionType, ETag, EntityTag, Header,
+    HttpDate, IfNoneMatch, LastModified, VARY,
+};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, Responder, get, post, web};
 use anyhow::{Context, Result};
-use serde::Serialize;
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, error, info, instrument};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
+use tokio::sync::{RwLock, broadcast};
+use tracing::{debug, error, info, instrument, warn};
 
-#[derive(Debug, Clone, PartialEq, Copy, Serialize)]
-pub enum RunMode {
-    Sim,
-    Real,
-}
-
-impl std::fmt::Display for RunMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            RunMode::Sim => write!(f, "sim"),
-            RunMode::Real => write!(f, "real"),
-        }
-    }
-}
-
-impl std::str::FromStr for RunMode {
-    type Err = String;
+/// How many mode changes `/api/mode_history` remembers.
+const MODE_HISTORY_CAPACITY: usize = 100;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "sim" => Ok(RunMode::Sim),
-            "real" => Ok(RunMode::Real),
-            _ => Err(format!("Invalid run mode: {}", s)),
-        }
-    }
+/// One Sim/Real switch made through `/api/set_mode`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeTransition {
+    pub from: RunMode,
+    pub to: RunMode,
+    pub ts: i64,
 }
 
 #[derive(Clone)]
 pub struct AppState {
     pub mode: Arc<RwLock<RunMode>>,
+    /// newest last, bounded by `MODE_HISTORY_CAPACITY`
+    pub mode_history: Arc<RwLock<VecDeque<ModeTransition>>>,
     pub trading_app: Arc<TradingApp>,
+    pub fetcher: Arc<DataFetcher>,
+    pub feed: TickFeed,
+    /// open `/api/stream` connections
+    pub sse_connections: Arc<AtomicUsize>,
+    /// background tasks controllable through `/api/admin/task/{name}/...`
+    pub tasks: TaskRegistry,
+    /// set while a `/api/replay` runs; only one runs at a time
+    pub replay_running: Arc<AtomicBool>,
     pub config: Arc<AppConfig>,
 }
 
@@ -76,6 +107,95 @@ struct HistoryResponse {
     count: usize,
 }
 
+/// Query string of `/api/history/{symbol}`. Unknown parameters (cache busters and the
+/// like) are ignored; a known one that does not parse is a 400.
+#[derive(Debug, Default, Deserialize, Hash)]
+pub struct HistoryQuery {
+    /// Sim mode: market day to chart; defaults to the last day with ticks
+    pub date: Option<NaiveDate>,
+    /// Real mode: trailing window in days (default 30), clamped with `clamp_days` like
+    /// every other `days` parameter; a non-integer is a 400
+    pub days: Option<i64>,
+    /// Real mode: window of the last N trading days before today plus today so far;
+    /// takes precedence over `days`
+    pub trading_days: Option<usize>,
+    /// the newest N ticks from the Redis cache instead of a day/window (live chart)
+    pub last: Option<usize>,
+    /// drop points after this cut-off: `HH:MM` on the series' last day, epoch ms or
+    /// RFC 3339
+    pub as_of: Option<String>,
+    /// thin the response to at most this many points (LTTB)
+    pub max_points: Option<usize>,
+    /// aggregate ticks into bars of this size (`1m`, `5m`, `1h`, ...) and compute the
+    /// MACD over bar prices
+    pub interval: Option<String>,
+    /// which bar price feeds the MACD with `interval`; defaults to `trading.price_source`
+    pub price_source: Option<PriceSource>,
+    /// MACD periods; default to `trading.macd_short/long/signal`
+    pub short: Option<usize>,
+    pub long: Option<usize>,
+    pub signal: Option<usize>,
+    /// compute the MACD on `ln(price)` so percentage moves weigh equally
+    #[serde(default)]
+    pub log_scale: bool,
+}
+
+impl HistoryQuery {
+    /// Whether the series differs from the cached per-day/per-window default MACD.
+    fn is_custom(&self) -> bool {
+        self.interval.is_some()
+            || self.price_source.is_some()
+            || self.trading_days.is_some()
+            || self.short.is_some()
+            || self.long.is_some()
+            || self.signal.is_some()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OhlcQuery {
+    /// single trading day, shorthand for `from == to`
+    date: Option<NaiveDate>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// bar size such as `1m`, `5m`, `15m`, `1h` (default `1m`)
+    interval: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TicksQuery {
+    /// single trading day, shorthand for `from == to`
+    date: Option<NaiveDate>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// only ticks recorded from this source, e.g. `eastmoney`, `sina`, `sim`, `backfill`
+    source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorrelationQuery {
+    a: String,
+    b: String,
+    /// single trading day, shorthand for `from == to`
+    date: Option<NaiveDate>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// return interval such as `1m`, `5m`, `1h` (default `1m`)
+    interval: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareQuery {
+    /// comma separated, e.g. `600733.SH,000001.SZ`
+    symbols: String,
+    /// single trading day, shorthand for `from == to`
+    date: Option<NaiveDate>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// bar size such as `1m`, `5m`, `1h` (default `1m`)
+    interval: Option<String>,
+}
+
 #[derive(Serialize)]
 struct ModeResponse {
     mode: String,
@@ -87,11 +207,199 @@ struct StatusResponse {
     version: String,
     mode: String,
     symbol_count: usize,
+    day_macd_cache_hits: u64,
+}
+
+/// `Some(rejection)` unless the request carries `Authorization: Bearer <server.admin_token>`.
+fn require_admin(req: &HttpRequest, config: &AppConfig) -> Option<HttpResponse> {
+    let Some(expected) = config.server.admin_token.as_deref() else {
+        return Some(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "Admin endpoints are disabled".into(),
+        )));
+    };
+    let provided = req
+        .headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided == Some(expected) {
+        None
+    } else {
+        Some(HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
+            "Missing or invalid admin token".into(),
+        )))
+    }
+}
+
+/// What `/api/history`, `/api/ohlc` and `/api/ticks` can return; JSON unless `Accept`
+/// asks for CSV.
+const DATA_REPRESENTATIONS: [Representation; 2] = [Representation::Json, Representation::Csv];
+
+/// The representation of `offered` the client accepts, or a 406 naming the offers.
+fn negotiate_or_reject(
+    req: &HttpRequest,
+    offered: &[Representation],
+) -> std::result::Result<Representation, HttpResponse> {
+    negotiate(req, offered).ok_or_else(|| {
+        let supported: Vec<String> = offered.iter().map(|r| r.mime().to_string()).collect();
+        HttpResponse::NotAcceptable().json(ApiResponse::<()>::error(format!(
+            "None of the accepted types is available; supported: {}",
+            supported.join(", ")
+        )))
+    })
+}
+
+/// `Some(413)` when `symbol` has more than `server.max_response_points` ticks in
+/// `[start_ts, end_ts)`. Uses a COUNT query so oversized ranges are never loaded.
+async fn reject_oversized(
+    state: &AppState,
+    symbol: &str,
+    start_ts: i64,
+    end_ts: i64,
+) -> Option<HttpResponse> {
+    let max = state.config.server.max_response_points;
+    match state
+        .trading_app
+        .get_storage()
+        .count_ticks(symbol, start_ts, end_ts)
+        .await
+    {
+        Ok(count) if count > max => Some(HttpResponse::PayloadTooLarge().json(
+            ApiResponse::<()>::error(format!(
+                "Range holds {} ticks, more than the {} allowed; narrow it",
+                count, max
+            )),
+        )),
+        Ok(_) => None,
+        Err(e) => Some(handle_error(e)),
+    }
+}
+
+/// Error response whose status says whose fault it was: an `AppError` anywhere in the
+/// chain decides through `status_code` (bad input 400, unknown data 404, upstream
+/// failures 502) and anything else, storage failures included, is a 500. Input is
+/// checked where it enters, which raises `AppError::invalid_parameter`.
+fn handle_error(err: impl Into<anyhow::Error>) -> HttpResponse {
+    let err = err.into();
+    let status = error_status(&err);
+    if status.is_server_error() {
+        error!("API error: {:#}", err);
+    } else {
+        debug!("Rejected request: {:#}", err);
+    }
+    HttpResponse::build(status).json(ApiResponse::<()>::error(err.to_string()))
+}
+
+fn error_status(err: &anyhow::Error) -> StatusCode {
+    for cause in err.chain() {
+        if let Some(app_error) = cause.downcast_ref::<AppError>() {
+            return StatusCode::from_u16(app_error.status_code())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Weak validator for a history response. It changes whenever a tick is stored in the
+/// range the response covers (`version` is its row count and newest ts, so a backfilled
+/// older tick counts too), the run mode flips, or any query parameter or the negotiated
+/// representation differs.
+fn history_etag(
+    symbol: &str,
+    mode: RunMode,
+    version: (usize, Option<TsMillis>),
+    query: &HistoryQuery,
+    representation: Representation,
+) -> EntityTag {
+    let mut hasher = DefaultHasher::new();
+    symbol.hash(&mut hasher);
+    mode.to_string().hash(&mut hasher);
+    version.hash(&mut hasher);
+    query.hash(&mut hasher);
+    representation.hash(&mut hasher);
+    EntityTag::new_weak(format!("{:016x}", hasher.finish()))
 }
 
-fn handle_error<E: std::fmt::Display>(err: E) -> HttpResponse {
-    error!("API error: {}", err);
-    HttpResponse::InternalServerError().json(ApiResponse::<()>::error(err.to_string()))
+fn etag_matches(req: &HttpRequest, etag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        Err(_) => false,
+    }
+}
+
+/// Parse a bar interval such as `1m`, `5m`, `15m` or `1h` into milliseconds.
+fn parse_interval(interval: &str) -> Option<i64> {
+    let interval = interval.trim().to_lowercase();
+    let (count, unit_ms) = if let Some(n) = interval.strip_suffix("min") {
+        (n, 60_000)
+    } else if let Some(n) = interval.strip_suffix('m') {
+        (n, 60_000)
+    } else if let Some(n) = interval.strip_suffix('h') {
+        (n, 3_600_000)
+    } else {
+        return None;
+    };
+    count
+        .parse::<i64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .map(|n| n * unit_ms)
+}
+
+/// Parser for a raw `days` query parameter: non-numeric values are rejected, numbers
+/// are clamped with `clamp_days`. `None` when the parameter is absent.
+pub fn parse_days(raw: Option<&str>, max_days: i64) -> std::result::Result<Option<i64>, String> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    let days: i64 = raw
+        .trim()
+        .parse()
+        .map_err(|_| format!("`days` must be an integer, got {:?}", raw))?;
+    Ok(Some(clamp_days(days, max_days)))
+}
+
+/// Cut-off for `as_of`: a wall-clock time on the series' last market day, or an instant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsOf {
+    Time(NaiveTime),
+    Millis(TsMillis),
+}
+
+impl AsOf {
+    /// Accepts `HH:MM`, epoch milliseconds or an RFC 3339 timestamp.
+    pub fn parse(raw: &str) -> std::result::Result<Self, String> {
+        let raw = raw.trim();
+        if let Ok(time) = NaiveTime::parse_from_str(raw, "%H:%M") {
+            return Ok(AsOf::Time(time));
+        }
+        if let Ok(ms) = raw.parse::<i64>() {
+            return Ok(AsOf::Millis(TsMillis(ms)));
+        }
+        chrono::DateTime::parse_from_rfc3339(raw)
+            .map(|dt| AsOf::Millis(TsMillis::from_datetime(&dt)))
+            .map_err(|_| {
+                format!(
+                    "`as_of` must be HH:MM, epoch millis or RFC 3339, got {:?}",
+                    raw
+                )
+            })
+    }
+
+    /// The cut-off instant; `Time` is placed on the market date of `last_ts`.
+    fn resolve(self, last_ts: TsMillis, tz: Tz) -> TsMillis {
+        match self {
+            AsOf::Millis(ts) => ts,
+            AsOf::Time(time) => {
+                let last = last_ts.to_datetime().unwrap_or_default();
+                TsMillis(market_local_to_millis(
+                    tz,
+                    market_date(tz, last).and_time(time),
+                ))
+            }
+        }
+    }
 }
 
 #[post("/api/set_mode/{mode}")]
@@ -103,10 +411,23 @@ async fn set_mode(state: web::Data<AppState>, path: web::Path<String>) -> impl R
         Ok(new_mode) => {
             {
                 let mut lock = state.mode.write().await;
-                *lock = new_mode;
+                let old_mode = *lock;
+                // re-selecting the current mode is a no-op and is not logged
+                if old_mode != new_mode {
+                    *lock = new_mode;
+                    let mut transitions = state.mode_history.write().await;
+                    if transitions.len() == MODE_HISTORY_CAPACITY {
+                        transitions.pop_front();
+                    }
+                    transitions.push_back(ModeTransition {
+                        from: old_mode,
+                        to: new_mode,
+                        ts: chrono::Utc::now().timestamp_millis(),
+                    });
+                    info!("Run mode changed from {} to {}", old_mode, new_mode);
+                }
             }
 
-            info!("Run mode changed to: {}", new_mode);
             HttpResponse::Ok().json(ApiResponse::success(ModeResponse {
                 mode: new_mode.to_string(),
             }))
@@ -121,17 +442,25 @@ async fn set_mode(state: web::Data<AppState>, path: web::Path<String>) -> impl R
 #[get("/api/get_mode")]
 #[instrument(skip(state))]
 async fn get_mode(state: web::Data<AppState>) -> impl Responder {
-    let mode = { state.mode.read().await.clone() };
+    let mode = *state.mode.read().await;
 
     HttpResponse::Ok().json(ApiResponse::success(ModeResponse {
         mode: mode.to_string(),
     }))
 }
 
+#[get("/api/mode_history")]
+#[instrument(skip(state))]
+async fn get_mode_history(state: web::Data<AppState>) -> impl Responder {
+    let transitions: Vec<ModeTransition> =
+        state.mode_history.read().await.iter().cloned().collect();
+    HttpResponse::Ok().json(ApiResponse::success(transitions))
+}
+
 #[get("/api/status")]
 #[instrument(skip(state))]
 async fn get_status(state: web::Data<AppState>) -> impl Responder {
-    let mode = { state.mode.read().await.clone() };
+    let mode = *state.mode.read().await;
 
     let symbol_count = match state.trading_app.get_storage().get_symbols().await {
         Ok(symbols) => symbols.len(),
@@ -146,9 +475,300 @@ async fn get_status(state: web::Data<AppState>) -> impl Responder {
         version: state.config.version.clone(),
         mode: mode.to_string(),
         symbol_count,
+        day_macd_cache_hits: state.trading_app.day_macd_cache_hits(),
     }))
 }
 
+#[derive(Serialize)]
+struct MarketSummaryResponse {
+    mode: String,
+    #[serde(flatten)]
+    summary: MarketSummary,
+}
+
+/// Dashboard header stats: symbol/tick counts and bullish vs bearish symbols.
+#[get("/api/market/summary")]
+#[instrument(skip(state))]
+async fn market_summary(state: web::Data<AppState>) -> impl Responder {
+    let mode = *state.mode.read().await;
+    match state.trading_app.market_summary().await {
+        Ok(summary) => HttpResponse::Ok().json(ApiResponse::success(MarketSummaryResponse {
+            mode: mode.to_string(),
+            summary,
+        })),
+        Err(e) => handle_error(e),
+    }
+}
+
+#[derive(Serialize)]
+struct CacheCounts {
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Serialize)]
+struct SourceCounts {
+    name: &'static str,
+    successes: u64,
+    failures: u64,
+}
+
+#[derive(Serialize)]
+struct DebugStats {
+    day_macd_cache: CacheCounts,
+    quote_cache: CacheCounts,
+    sources: Vec<SourceCounts>,
+    sse_connections: usize,
+    storage_queries: StorageQueryCounts,
+}
+
+/// In-process counters for debugging without a metrics stack (admin only).
+#[get("/api/debug/stats")]
+#[instrument(skip(state, req))]
+async fn debug_stats(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Some(rejection) = require_admin(&req, &state.config) {
+        return rejection;
+    }
+    let (quote_hits, quote_misses) = state.fetcher.cache_stats();
+    let sources = state
+        .fetcher
+        .source_statuses()
+        .await
+        .into_iter()
+        .map(|s| SourceCounts {
+            name: s.name,
+            successes: s.successes,
+            failures: s.failures,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ApiResponse::success(DebugStats {
+        day_macd_cache: CacheCounts {
+            hits: state.trading_app.day_macd_cache_hits(),
+            misses: state.trading_app.day_macd_cache_misses(),
+        },
+        quote_cache: CacheCounts {
+            hits: quote_hits,
+            misses: quote_misses,
+        },
+        sources,
+        sse_connections: state.sse_connections.load(Ordering::Relaxed),
+        storage_queries: state.trading_app.get_storage().query_counts(),
+    }))
+}
+
+#[derive(Serialize)]
+struct TaskStatus {
+    name: String,
+    state: TaskState,
+}
+
+async fn control_task(
+    state: &AppState,
+    req: &HttpRequest,
+    name: String,
+    start: bool,
+) -> HttpResponse {
+    if let Some(rejection) = require_admin(req, &state.config) {
+        return rejection;
+    }
+    let result = if start {
+        state.tasks.start(&name).await
+    } else {
+        state.tasks.stop(&name).await
+    };
+    match result {
+        Some(task_state) => HttpResponse::Ok().json(ApiResponse::success(TaskStatus {
+            name,
+            state: task_state,
+        })),
+        None => HttpResponse::NotFound()
+            .json(ApiResponse::<()>::error(format!("Unknown task: {}", name))),
+    }
+}
+
+/// Start a registered background task, e.g. `ingestion` (admin only).
+#[post("/api/admin/task/{name}/start")]
+#[instrument(skip(state, req))]
+async fn start_task(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    control_task(&state, &req, path.into_inner(), true).await
+}
+
+/// Stop a background task once its current iteration is done (admin only).
+#[post("/api/admin/task/{name}/stop")]
+#[instrument(skip(state, req))]
+async fn stop_task(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    control_task(&state, &req, path.into_inner(), false).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WarmRequest {
+    pub symbols: Vec<String>,
+    pub from: NaiveDate,
+    /// inclusive
+    pub to: NaiveDate,
+}
+
+/// Compute and cache the day MACD of each symbol for each trading day in the range
+/// ahead of a batch report (admin only). A range longer than `max_analysis_days` is
+/// clamped with `clamp_days` to its last `max_analysis_days` days.
+#[post("/api/admin/cache/warm")]
+#[instrument(skip(state, req, body))]
+async fn warm_cache(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<WarmRequest>,
+) -> impl Responder {
+    if let Some(rejection) = require_admin(&req, &state.config) {
+        return rejection;
+    }
+    let WarmRequest { symbols, from, to } = body.into_inner();
+    if symbols.is_empty() || symbols.len() > MAX_LATEST_SYMBOLS {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Expected between 1 and {} symbols, got {}",
+            MAX_LATEST_SYMBOLS,
+            symbols.len()
+        )));
+    }
+    if from > to {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "`from` must not be after `to`".into(),
+        ));
+    }
+    let days = clamp_days(
+        (to - from).num_days() + 1,
+        state.config.trading.max_analysis_days,
+    );
+    let from = to - chrono::Duration::days(days - 1);
+
+    let summary = state.trading_app.warm_range(&symbols, from, to).await;
+    HttpResponse::Ok().json(ApiResponse::success(summary))
+}
+
+/// Read size when streaming a backup file back to the client.
+const BACKUP_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Consistent SQLite snapshot as a download (admin only). The copy is taken
+/// into a temp file and unlinked once open, so nothing is left behind.
+#[get("/api/admin/backup")]
+#[instrument(skip(state, req))]
+async fn admin_backup(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Some(rejection) = require_admin(&req, &state.config) {
+        return rejection;
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let tmp = std::env::temp_dir().join(format!("macd-backup-{}-{}.db", std::process::id(), nanos));
+
+    if let Err(e) = state.trading_app.get_storage().backup_to(&tmp).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return handle_error(e);
+    }
+    let file = match tokio::fs::File::open(&tmp).await {
+        Ok(file) => file,
+        Err(e) => return handle_error(e),
+    };
+    if let Err(e) = tokio::fs::remove_file(&tmp).await {
+        warn!("Failed to remove backup temp file {}: {}", tmp.display(), e);
+    }
+
+    let chunks = futures::stream::unfold(file, |mut file| async move {
+        let mut buf = vec![0u8; BACKUP_CHUNK_BYTES];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok::<_, actix_web::Error>(web::Bytes::from(buf)), file))
+            }
+            Err(e) => {
+                // an error ends the response abnormally, so the client cannot
+                // mistake a truncated file for a complete one
+                error!("Backup stream read failed: {}", e);
+                Some((Err(actix_web::error::ErrorInternalServerError(e)), file))
+            }
+        }
+    });
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(format!(
+            "macd-backup-{}.db",
+            market_today(state.config.trading.timezone)
+        ))],
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .insert_header(disposition)
+        .streaming(chunks)
+}
+
+/// Largest page any list endpoint returns; also the default `limit`.
+const MAX_PAGE_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Upper bound on `symbols` accepted by the batch `/api/latest`.
+const MAX_LATEST_SYMBOLS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct LatestBatchQuery {
+    /// comma separated, e.g. `600733.SH,000001.SZ`
+    pub symbols: String,
+}
+
+/// Distinct, sorted symbols of a comma separated list, blanks skipped.
+fn parse_symbol_list(list: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = list
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+#[get("/api/latest")]
+#[instrument(skip(state))]
+async fn latest_batch(
+    state: web::Data<AppState>,
+    query: web::Query<LatestBatchQuery>,
+) -> impl Responder {
+    let symbols = parse_symbol_list(&query.symbols);
+    if symbols.is_empty() || symbols.len() > MAX_LATEST_SYMBOLS {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Expected between 1 and {} symbols, got {}",
+            MAX_LATEST_SYMBOLS,
+            symbols.len()
+        )));
+    }
+
+    match state
+        .trading_app
+        .get_storage()
+        .get_latest_ticks(&symbols)
+        .await
+    {
+        Ok(ticks) => HttpResponse::Ok().json(ApiResponse::success(ticks)),
+        Err(e) => handle_error(e),
+    }
+}
+
 #[get("/api/latest/{symbol}")]
 #[instrument(skip(state))]
 async fn latest(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
@@ -163,143 +783,1244 @@ async fn latest(state: web::Data<AppState>, path: web::Path<String>) -> impl Res
     }
 }
 
+/// Lets a client tell "no symbols yet, still starting" from "no symbols at all".
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DataStatus {
+    Ready,
+    /// configured cache priming has not finished
+    Warming,
+    /// ready, but storage holds no ticks
+    Empty,
+}
+
+#[derive(Serialize)]
+struct SymbolsResponse {
+    #[serde(flatten)]
+    page: Page<SymbolInfo>,
+    data_status: DataStatus,
+}
+
 #[get("/api/symbols")]
 #[instrument(skip(state))]
-async fn get_symbols(state: web::Data<AppState>) -> impl Responder {
-    match state.trading_app.get_all_symbols_info().await {
-        Ok(symbols_info) => {
-            debug!("Retrieved info for {} symbols", symbols_info.len());
-            HttpResponse::Ok().json(ApiResponse::success(symbols_info))
+async fn get_symbols(state: web::Data<AppState>, query: web::Query<PageQuery>) -> impl Responder {
+    let limit = query.limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    match state.trading_app.get_all_symbols_info(limit, offset).await {
+        Ok(page) => {
+            debug!(
+                "Retrieved info for {} of {} symbols",
+                page.items.len(),
+                page.total
+            );
+            let data_status =
+                if state.config.server.prime_cache && !state.trading_app.cache_primed() {
+                    DataStatus::Warming
+                } else if page.total == 0 {
+                    DataStatus::Empty
+                } else {
+                    DataStatus::Ready
+                };
+            HttpResponse::Ok().json(ApiResponse::success(SymbolsResponse { page, data_status }))
         }
         Err(e) => handle_error(e),
     }
 }
 
+/// Default number of `/api/search` results; `limit` may raise it up to `MAX_PAGE_LIMIT`.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    /// code prefix (`6007`) or part of a company name
+    #[serde(default)]
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// Symbols matching `q` by code prefix or by name, best matches first.
+#[get("/api/search")]
+#[instrument(skip(state))]
+async fn search(state: web::Data<AppState>, query: web::Query<SearchQuery>) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .min(MAX_PAGE_LIMIT);
+
+    match state
+        .trading_app
+        .get_storage()
+        .search_symbols(&query.q, limit)
+        .await
+    {
+        Ok(matches) => HttpResponse::Ok().json(ApiResponse::success(matches)),
+        Err(e) => handle_error(e),
+    }
+}
+
 #[get("/api/history/{symbol}")]
-#[instrument(skip(state, query))]
+#[instrument(skip(state, req, query))]
 async fn history(
     state: web::Data<AppState>,
+    req: HttpRequest,
     path: web::Path<String>,
-    query: web::Query<std::collections::HashMap<String, String>>,
+    query: web::Query<HistoryQuery>,
 ) -> impl Responder {
     let symbol = path.into_inner();
-    let mode = { state.mode.read().await.clone() };
+    let mode = *state.mode.read().await;
+    let bad_request = |e: String| HttpResponse::BadRequest().json(ApiResponse::<()>::error(e));
+    let representation = match negotiate_or_reject(&req, &DATA_REPRESENTATIONS) {
+        Ok(representation) => representation,
+        Err(rejection) => return rejection,
+    };
+
+    let trading = &state.config.trading;
+    let days = query
+        .days
+        .map(|days| clamp_days(days, trading.max_analysis_days));
+
+    let as_of = match query.as_of.as_deref().map(AsOf::parse) {
+        None => None,
+        Some(Ok(as_of)) => Some(as_of),
+        Some(Err(e)) => return bad_request(e),
+    };
+
+    let interval_ms = match query.interval.as_deref().map(parse_interval) {
+        None => None,
+        Some(Some(ms)) => Some(ms),
+        Some(None) => {
+            return bad_request(format!(
+                "Invalid interval {:?}, expected e.g. 1m, 5m, 15m, 1h",
+                query.interval.as_deref().unwrap_or_default()
+            ));
+        }
+    };
+
+    let short = query.short.unwrap_or(trading.macd_short);
+    let long = query.long.unwrap_or(trading.macd_long);
+    let signal = query.signal.unwrap_or(trading.macd_signal);
+    if short == 0 || short >= long || signal == 0 {
+        return bad_request("periods must satisfy 0 < short < long and signal > 0".into());
+    }
+
+    // the Real mode window, unless `last` asks for the newest ticks instead
+    let now = chrono::Utc::now().timestamp_millis();
+    let real_start = match query.trading_days {
+        Some(n) => {
+            let first_day = trading
+                .calendar()
+                .trading_days_before(market_today(trading.timezone), n);
+            market_local_to_millis(trading.timezone, first_day.and_time(NaiveTime::MIN))
+        }
+        None => now - chrono::Duration::days(days.unwrap_or(30)).num_milliseconds(),
+    };
+    let (version_start, version_end) = match (mode, query.last, query.date) {
+        (RunMode::Real, None, _) => (real_start, i64::MAX),
+        (RunMode::Sim, None, Some(date)) => market_day_range_ms(trading.timezone, date, date),
+        // the newest ticks or the newest day, which any stored tick can move
+        _ => (i64::MIN, i64::MAX),
+    };
+
+    // Validators are best-effort: if the version can't be read we just serve the full body
+    let version = match state
+        .trading_app
+        .get_storage()
+        .tick_version(&symbol, version_start, version_end)
+        .await
+    {
+        Ok(version) => Some(version),
+        Err(e) => {
+            debug!("Skipping ETag for {}: {}", symbol, e);
+            None
+        }
+    };
+    let etag = version.map(|v| history_etag(&symbol, mode, v, &query, representation));
+
+    if let Some(etag) = &etag
+        && etag_matches(&req, etag)
+    {
+        debug!("History for {} not modified", symbol);
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag.clone()))
+            .insert_header((VARY, "Accept"))
+            .finish();
+    }
+
+    if mode == RunMode::Real
+        && query.last.is_none()
+        && let Some(rejection) = reject_oversized(&state, &symbol, real_start, now).await
+    {
+        return rejection;
+    }
+
+    // MACD over the ticks, or over bar prices when an interval is requested
+    let price_source = query.price_source.unwrap_or(trading.price_source);
+    let macd_of = |ticks: &[Tick]| -> Result<Vec<MACDPoint>> {
+        let points: Vec<(TsMillis, f64)> = match interval_ms {
+            Some(ms) => bar_prices(
+                &aggregate_bars_stamped(ticks, ms, state.config.trading.bar_timestamp),
+                price_source,
+            ),
+            None => ticks.iter().map(|t| (t.ts, t.price)).collect(),
+        };
+        if query.log_scale {
+            return compute_macd_series_log(&points, short, long, signal)
+                .map_err(|i| non_positive_price(points[i].0).into());
+        }
+        Ok(compute_macd_series(&points, short, long, signal))
+    };
+
+    let points_res: Result<Vec<MACDPoint>> = async {
+        let storage = state.trading_app.get_storage();
+        // `?last=N` is the live chart's trailing window, served from the Redis tick list
+        if let Some(n) = query.last {
+            let ticks = storage
+                .get_recent_ticks_cached(&symbol, n)
+                .await
+                .context("Failed to fetch recent ticks")?;
+            return macd_of(&ticks);
+        }
 
-    let points_res: Result<Vec<(i64, f64)>> = async {
         match mode {
             RunMode::Real => {
                 debug!("Fetching real mode history for symbol: {}", symbol);
+                let days = days.unwrap_or(30);
+                if query.is_custom() {
+                    let ticks = match query.trading_days {
+                        Some(n) => storage.get_ticks_recent_trading_days(&symbol, n).await,
+                        None => storage.get_ticks_recent_days(&symbol, days).await,
+                    }
+                    .context("Failed to fetch ticks")?;
+                    return macd_of(&ticks);
+                }
+                let options = AnalysisOptions {
+                    log_scale: query.log_scale,
+                    ..AnalysisOptions::default()
+                };
                 let analysis = state
                     .trading_app
-                    .get_market_analysis(&symbol, Some(30))
+                    .get_market_analysis_with(&symbol, Some(days), options)
                     .await
                     .context("Failed to fetch market analysis")?;
 
-                let price_points: Vec<(i64, f64)> = analysis
-                    .macd_points
-                    .iter()
-                    .map(|point| (point.ts, point.price))
-                    .collect();
-                Ok(price_points)
+                Ok(analysis.macd_points)
             }
             RunMode::Sim => {
                 debug!("Fetching sim mode history for symbol: {}", symbol);
-                if let Some(date) = query.get("date") {
-                    let ticks = state
-                        .trading_app
-                        .get_storage()
-                        .get_ticks_for_date(&symbol, date)
-                        .await
-                        .context("Failed to fetch ticks for date")?;
-
-                    Ok(ticks.iter().map(|t| (t.ts, t.price)).collect())
+                let date = if let Some(date) = query.date {
+                    date
                 } else {
-                    // Fallback: return last full day present in DB
-                    let recent = state
-                        .trading_app
-                        .get_storage()
-                        .get_ticks_recent_days(&symbol, 7)
+                    // Fallback: the latest day present in DB, however old
+                    let Some(date) = storage
+                        .get_latest_date(&symbol)
                         .await
-                        .context("Failed to fetch recent ticks")?;
-
-                    if recent.is_empty() {
+                        .context("Failed to find the latest stored date")?
+                    else {
                         return Ok(vec![]);
-                    }
+                    };
+                    date
+                };
 
-                    // Find last date string
-                    let last_ts = recent.last().unwrap().ts;
-                    let naivedt = chrono::DateTime::from_timestamp(last_ts / 1000, 0)
-                        .map(|dt| dt.naive_utc())
-                        .unwrap_or_else(|| {
-                            chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()
-                        });
-                    let date_str = naivedt.date().format("%Y-%m-%d").to_string();
-
-                    let ticks = state
-                        .trading_app
-                        .get_storage()
-                        .get_ticks_for_date(&symbol, &date_str)
+                // the cached day series is on the linear scale
+                if query.is_custom() || query.log_scale {
+                    let ticks = storage
+                        .get_ticks_for_date(&symbol, &date.format("%Y-%m-%d").to_string())
                         .await
                         .context("Failed to fetch ticks for date")?;
-
-                    Ok(ticks.iter().map(|t| (t.ts, t.price)).collect())
+                    return macd_of(&ticks);
                 }
+                let points = state
+                    .trading_app
+                    .get_day_macd(&symbol, date)
+                    .await
+                    .context("Failed to compute MACD for date")?;
+                Ok(points.as_ref().clone())
             }
         }
     }
     .await;
 
     match points_res {
-        Ok(points) => {
-            let computed_macd_points = compute_macd_series(&points);
+        Ok(computed_macd_points) => {
+            debug!(
+                "Computed MACD for {} data points",
+                computed_macd_points.len()
+            );
+
+            // The MACD is causal, so dropping points after the cut-off gives the same
+            // values as computing over the truncated ticks
+            let mut computed_macd_points = computed_macd_points;
+            if let (Some(as_of), Some(last)) = (as_of, computed_macd_points.last()) {
+                let cutoff = as_of.resolve(last.ts, state.config.trading.timezone);
+                computed_macd_points.retain(|p| p.ts <= cutoff);
+            }
+
+            // MACD is computed at full resolution, only the output is thinned for the chart
+            let computed_macd_points = match query.max_points {
+                Some(max) if computed_macd_points.len() > max => {
+                    lttb(&computed_macd_points, max, |p| {
+                        (p.ts.as_millis() as f64, p.price)
+                    })
+                }
+                _ => computed_macd_points,
+            };
             let count = computed_macd_points.len();
+            let decimals = state.config.server.response_decimals;
+            let computed_macd_points: Vec<MACDPoint> = computed_macd_points
+                .iter()
+                .map(|p| p.rounded(decimals))
+                .collect();
 
-            debug!("Computed MACD for {} data points", count);
+            let mut builder = HttpResponse::Ok();
+            builder.insert_header((VARY, "Accept"));
+            if let Some(etag) = etag {
+                builder.insert_header(ETag(etag));
+            }
+            if let Some((_, Some(ts))) = version {
+                let modified = UNIX_EPOCH + Duration::from_millis(ts.as_millis().max(0) as u64);
+                builder.insert_header(LastModified(HttpDate::from(modified)));
+            }
+            if representation == Representation::Csv {
+                return builder
+                    .content_type(representation.mime())
+                    .body(to_csv(&computed_macd_points));
+            }
 
+            // points are streamed into the envelope rather than buffered with it
             let resp = HistoryResponse {
-                points: computed_macd_points,
+                points: Vec::new(),
                 symbol,
                 mode: mode.to_string(),
                 count,
             };
+            let body = match stream_json_with_array(
+                &ApiResponse::success(resp),
+                "points",
+                computed_macd_points,
+            ) {
+                Ok(body) => body,
+                Err(e) => return handle_error(e),
+            };
+            builder.content_type(ContentType::json()).streaming(body)
+        }
+        Err(e) => handle_error(e),
+    }
+}
+
+/// `[start, end)` in epoch ms for a `date` or `from`/`to` query: the market day
+/// `date`, the days `from..=to` (`to` defaults to `from`) or else the last 24 hours.
+fn date_window(
+    state: &AppState,
+    date: Option<NaiveDate>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<(i64, i64), HttpResponse> {
+    let tz = state.config.trading.timezone;
+    match (date, from, to) {
+        (Some(date), _, _) => Ok(market_day_range_ms(tz, date, date)),
+        (None, Some(from), to) => {
+            let to = to.unwrap_or(from);
+            if to < from {
+                return Err(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "`to` must not be before `from`".into(),
+                )));
+            }
+            Ok(market_day_range_ms(tz, from, to))
+        }
+        (None, None, _) => {
+            let end = chrono::Utc::now();
+            let start = end - chrono::Duration::days(1);
+            Ok((start.timestamp_millis(), end.timestamp_millis()))
+        }
+    }
+}
+
+#[get("/api/ohlc/{symbol}")]
+#[instrument(skip(state, req))]
+async fn ohlc(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<OhlcQuery>,
+) -> impl Responder {
+    let symbol = path.into_inner();
+    let query = query.into_inner();
+    let representation = match negotiate_or_reject(&req, &DATA_REPRESENTATIONS) {
+        Ok(representation) => representation,
+        Err(rejection) => return rejection,
+    };
+
+    let interval = query.interval.as_deref().unwrap_or("1m");
+    let Some(interval_ms) = parse_interval(interval) else {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Invalid interval: {}",
+            interval
+        )));
+    };
+
+    let (start_ts, end_ts) = match date_window(&state, query.date, query.from, query.to) {
+        Ok(window) => window,
+        Err(rejection) => return rejection,
+    };
+
+    if let Some(rejection) = reject_oversized(&state, &symbol, start_ts, end_ts).await {
+        return rejection;
+    }
+
+    match state
+        .trading_app
+        .get_storage()
+        .get_bars(&symbol, start_ts, end_ts, interval_ms)
+        .await
+    {
+        Ok(bars) => {
+            debug!("Built {} {} bars for {}", bars.len(), interval, symbol);
+            if representation == Representation::Csv {
+                return HttpResponse::Ok()
+                    .insert_header((VARY, "Accept"))
+                    .content_type(representation.mime())
+                    .body(to_csv(&bars));
+            }
+            match stream_json_with_array(&ApiResponse::success(Vec::<Bar>::new()), "data", bars) {
+                Ok(body) => HttpResponse::Ok()
+                    .insert_header((VARY, "Accept"))
+                    .content_type(ContentType::json())
+                    .streaming(body),
+                Err(e) => handle_error(e),
+            }
+        }
+        Err(e) => handle_error(e),
+    }
+}
+
+/// Pearson correlation of the per-interval returns of symbols `a` and `b` over a day
+/// or date range (the last 24 hours by default), with the number of aligned points.
+#[get("/api/correlation")]
+#[instrument(skip(state))]
+async fn correlation(
+    state: web::Data<AppState>,
+    query: web::Query<CorrelationQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+
+    let interval = query.interval.as_deref().unwrap_or("1m");
+    let Some(interval_ms) = parse_interval(interval) else {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Invalid interval: {}",
+            interval
+        )));
+    };
+
+    let (start_ts, end_ts) = match date_window(&state, query.date, query.from, query.to) {
+        Ok(window) => window,
+        Err(rejection) => return rejection,
+    };
+
+    for symbol in [&query.a, &query.b] {
+        if let Some(rejection) = reject_oversized(&state, symbol, start_ts, end_ts).await {
+            return rejection;
+        }
+    }
+
+    let storage = state.trading_app.get_storage();
+    let bars_a = match storage
+        .get_bars(&query.a, start_ts, end_ts, interval_ms)
+        .await
+    {
+        Ok(bars) => bars,
+        Err(e) => return handle_error(e),
+    };
+    let bars_b = match storage
+        .get_bars(&query.b, start_ts, end_ts, interval_ms)
+        .await
+    {
+        Ok(bars) => bars,
+        Err(e) => return handle_error(e),
+    };
+    HttpResponse::Ok().json(ApiResponse::success(return_correlation(&bars_a, &bars_b)))
+}
+
+/// Upper bound on `symbols` accepted by `/api/compare`.
+const MAX_COMPARE_SYMBOLS: usize = 10;
+
+/// Bar closes of several symbols rebased to 100 at the latest of their first bars in
+/// the window (the `anchor`), so all start level. Symbols with nothing from the anchor
+/// on are listed under `missing`.
+#[get("/api/compare")]
+#[instrument(skip(state))]
+async fn compare(state: web::Data<AppState>, query: web::Query<CompareQuery>) -> impl Responder {
+    let query = query.into_inner();
+
+    let symbols = parse_symbol_list(&query.symbols);
+    if symbols.is_empty() || symbols.len() > MAX_COMPARE_SYMBOLS {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Expected between 1 and {} symbols, got {}",
+            MAX_COMPARE_SYMBOLS,
+            symbols.len()
+        )));
+    }
+
+    let interval = query.interval.as_deref().unwrap_or("1m");
+    let Some(interval_ms) = parse_interval(interval) else {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Invalid interval: {}",
+            interval
+        )));
+    };
+
+    let (start_ts, end_ts) = match date_window(&state, query.date, query.from, query.to) {
+        Ok(window) => window,
+        Err(rejection) => return rejection,
+    };
+
+    for symbol in &symbols {
+        if let Some(rejection) = reject_oversized(&state, symbol, start_ts, end_ts).await {
+            return rejection;
+        }
+    }
+
+    let storage = state.trading_app.get_storage();
+    let mut bars = Vec::with_capacity(symbols.len());
+    for symbol in symbols {
+        match storage
+            .get_bars(&symbol, start_ts, end_ts, interval_ms)
+            .await
+        {
+            Ok(symbol_bars) => bars.push((symbol, symbol_bars)),
+            Err(e) => return handle_error(e),
+        }
+    }
+    HttpResponse::Ok().json(ApiResponse::success(rebase_to_common_anchor(&bars)))
+}
+
+/// Raw stored ticks for `symbol`, optionally limited to one source.
+#[get("/api/ticks/{symbol}")]
+#[instrument(skip(state, req))]
+async fn get_ticks(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<TicksQuery>,
+) -> impl Responder {
+    let symbol = path.into_inner();
+    let query = query.into_inner();
+    let representation = match negotiate_or_reject(&req, &DATA_REPRESENTATIONS) {
+        Ok(representation) => representation,
+        Err(rejection) => return rejection,
+    };
+
+    let (start_ts, end_ts) = match date_window(&state, query.date, query.from, query.to) {
+        Ok(window) => window,
+        Err(rejection) => return rejection,
+    };
+
+    if let Some(rejection) = reject_oversized(&state, &symbol, start_ts, end_ts).await {
+        return rejection;
+    }
+
+    let storage = state.trading_app.get_storage();
+    let result = match query.source.as_deref() {
+        Some(source) => {
+            storage
+                .get_ticks_range_from_source(&symbol, start_ts, end_ts, source)
+                .await
+        }
+        None => storage.get_ticks_range(&symbol, start_ts, end_ts).await,
+    };
+    match result {
+        Ok(ticks) => {
+            if representation == Representation::Csv {
+                return HttpResponse::Ok()
+                    .insert_header((VARY, "Accept"))
+                    .content_type(representation.mime())
+                    .body(to_csv(&ticks));
+            }
+            match stream_json_with_array(&ApiResponse::success(Vec::<Tick>::new()), "data", ticks) {
+                Ok(body) => HttpResponse::Ok()
+                    .insert_header((VARY, "Accept"))
+                    .content_type(ContentType::json())
+                    .streaming(body),
+                Err(e) => handle_error(e),
+            }
+        }
+        Err(e) => handle_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    pub format: Option<String>,
+    /// trailing window in days (default 30), clamped with `clamp_days` like every
+    /// other `days` parameter; a non-integer is a 400
+    pub days: Option<String>,
+    /// `cross_events` filters; the summary counts still cover the whole window
+    pub since: Option<TsMillis>,
+    #[serde(default)]
+    pub kind: CrossKind,
+    pub limit: Option<usize>,
+}
+
+/// Analysis report for download. `since`, `kind` (`golden`/`death`/`all`) and `limit`
+/// narrow `cross_events`, oldest first.
+#[get("/api/report/{symbol}")]
+#[instrument(skip(state))]
+async fn report(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<ReportQuery>,
+) -> impl Responder {
+    let symbol = path.into_inner();
+    let format = match query
+        .format
+        .as_deref()
+        .unwrap_or("json")
+        .parse::<ReportFormat>()
+    {
+        Ok(format) => format,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+    let days = match parse_days(
+        query.days.as_deref(),
+        state.config.trading.max_analysis_days,
+    ) {
+        Ok(days) => days,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match state.trading_app.build_report(&symbol, days).await {
+        Ok(mut report) => {
+            report.cross_events = select_cross_events(
+                report.cross_events,
+                query.since,
+                query.kind,
+                query.limit.unwrap_or(usize::MAX),
+            );
+            let disposition = ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename(format!(
+                    "{}-report.{}",
+                    symbol,
+                    format.extension()
+                ))],
+            };
+            match format {
+                ReportFormat::Json => HttpResponse::Ok().insert_header(disposition).json(report),
+            }
+        }
+        Err(e) => handle_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayRequest {
+    pub symbol: String,
+    pub date: NaiveDate,
+    /// playback speed multiplier, defaults to real time
+    pub speed: Option<f64>,
+}
+
+/// Clears `AppState::replay_running` when the replay holding it ends.
+struct ReplayGuard(Arc<AtomicBool>);
+
+impl Drop for ReplayGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Start replaying a stored day through the live feed in the background (admin only).
+/// Replayed ticks reach stream clients but not alerts or the streamed MACD; a second
+/// replay while one runs is a 409.
+#[post("/api/replay")]
+#[instrument(skip(state, req))]
+async fn replay(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<ReplayRequest>,
+) -> impl Responder {
+    if let Some(rejection) = require_admin(&req, &state.config) {
+        return rejection;
+    }
+    let ReplayRequest {
+        symbol,
+        date,
+        speed,
+    } = body.into_inner();
+    let speed = speed.unwrap_or(1.0);
+    if !(speed.is_finite() && speed > 0.0) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "`speed` must be a positive number".into(),
+        ));
+    }
+
+    if state
+        .replay_running
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return HttpResponse::Conflict().json(ApiResponse::<()>::error(
+            "A replay is already running".into(),
+        ));
+    }
+    let guard = ReplayGuard(state.replay_running.clone());
+
+    let storage = state.trading_app.get_storage().clone();
+    let feed = state.feed.clone();
+    let started = format!("Replaying {} on {} at {}x", symbol, date, speed);
+    tokio::spawn(async move {
+        let _guard = guard;
+        if let Err(e) = replay_day(&storage, &feed, &symbol, date, speed).await {
+            error!("Replay for {} on {} failed: {}", symbol, date, e);
+        }
+    });
+
+    HttpResponse::Accepted().json(ApiResponse::success(started))
+}
+
+/// Counts a live connection for as long as it is held.
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl ConnectionGuard {
+    fn new(counter: Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamQuery {
+    /// follow each tick with a `macd` event carrying the day MACD point it updates to
+    #[serde(default)]
+    macd: bool,
+}
+
+/// Server-sent events with every tick published for `symbol`, live or replayed.
+#[get("/api/stream/{symbol}")]
+#[instrument(skip(state, query))]
+async fn stream_ticks(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<StreamQuery>,
+) -> impl Responder {
+    let symbol = path.into_inner();
+    let rx = state.feed.subscribe();
+    let policy = state.config.server.slow_client_policy;
+    let macd_app = query.macd.then(|| state.trading_app.clone());
+    // lives in the stream state, so the count drops when the client goes away
+    let guard = ConnectionGuard::new(state.sse_connections.clone());
+
+    let events = futures::stream::unfold((rx, symbol, guard), move |(mut rx, symbol, guard)| {
+        let macd_app = macd_app.clone();
+        async move {
+            loop {
+                match rx.recv().await {
+                    Ok(tick) if tick.symbol == symbol => {
+                        let data = serde_json::to_string(&tick).unwrap_or_default();
+                        let mut event = format!("data: {}\n\n", data);
+                        if let Some(app) = macd_app.as_ref().filter(|_| !is_replayed(&tick)) {
+                            match app.update_macd(&tick).await {
+                                Ok(point) => {
+                                    let data = serde_json::to_string(&point).unwrap_or_default();
+                                    event.push_str(&format!("event: macd\ndata: {}\n\n", data));
+                                }
+                                Err(e) => {
+                                    warn!("No streamed MACD for {} at {}: {}", symbol, tick.ts, e)
+                                }
+                            }
+                        }
+                        let event = web::Bytes::from(event);
+                        return Some((Ok::<_, actix_web::Error>(event), (rx, symbol, guard)));
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => match policy {
+                        SlowClientPolicy::DropOldest => {
+                            warn!("Stream for {} lagged, dropped {} ticks", symbol, skipped);
+                            continue;
+                        }
+                        SlowClientPolicy::Disconnect => {
+                            warn!(
+                                "Stream for {} lagged by {} ticks, disconnecting",
+                                symbol, skipped
+                            );
+                            return None;
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SweepRequest {
+    pub grid: ParamGrid,
+    pub from: NaiveDate,
+    /// inclusive
+    pub to: NaiveDate,
+}
+
+/// Backtest every MACD parameter combination in the grid, best total return first.
+/// Combinations with `short >= long` are skipped.
+#[post("/api/backtest/sweep/{symbol}")]
+#[instrument(skip(state, body))]
+async fn backtest_sweep(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: web::Json<SweepRequest>,
+) -> impl Responder {
+    let symbol = path.into_inner();
+    let SweepRequest { grid, from, to } = body.into_inner();
+    if from > to {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "`from` must not be after `to`".into(),
+        ));
+    }
+    if grid.size() > MAX_SWEEP_COMBINATIONS {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Grid has {} combinations, at most {} allowed",
+            grid.size(),
+            MAX_SWEEP_COMBINATIONS
+        )));
+    }
+    let combos = grid.combinations();
+    if combos.is_empty() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "Grid has no combination with 0 < short < long".into(),
+        ));
+    }
+
+    match state
+        .trading_app
+        .backtest_sweep(&symbol, from, to, combos)
+        .await
+    {
+        Ok(results) => HttpResponse::Ok().json(ApiResponse::success(results)),
+        Err(e) => handle_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComputeMacdRequest {
+    pub prices: Vec<f64>,
+    /// one per price; the price index is used when omitted
+    pub timestamps: Option<Vec<TsMillis>>,
+    /// periods default to `trading.macd_short/long/signal`
+    pub short: Option<usize>,
+    pub long: Option<usize>,
+    pub signal: Option<usize>,
+    /// mark the first `long` points with `"warming_up": true`
+    #[serde(default)]
+    pub flag_warmup: bool,
+    /// compute on `ln(price)` so percentage moves weigh equally; prices must be positive
+    #[serde(default)]
+    pub log_scale: bool,
+}
 
-            HttpResponse::Ok().json(ApiResponse::success(resp))
+/// MACD series over a caller-supplied price array. Stateless: nothing is read or stored.
+#[post("/api/compute/macd")]
+#[instrument(skip(state, body))]
+async fn compute_macd(
+    state: web::Data<AppState>,
+    body: web::Json<ComputeMacdRequest>,
+) -> impl Responder {
+    let ComputeMacdRequest {
+        prices,
+        timestamps,
+        short,
+        long,
+        signal,
+        flag_warmup,
+        log_scale,
+    } = body.into_inner();
+    let trading = &state.config.trading;
+    let short = short.unwrap_or(trading.macd_short);
+    let long = long.unwrap_or(trading.macd_long);
+    let signal = signal.unwrap_or(trading.macd_signal);
+    let max_points = state.config.server.max_response_points;
+
+    let invalid =
+        |message: String| HttpResponse::BadRequest().json(ApiResponse::<()>::error(message));
+    if prices.is_empty() || prices.len() > max_points {
+        return invalid(format!(
+            "`prices` must have between 1 and {} entries, got {}",
+            max_points,
+            prices.len()
+        ));
+    }
+    if let Some(i) = prices.iter().position(|p| !p.is_finite()) {
+        return invalid(format!("`prices[{}]` is not a finite number", i));
+    }
+    if short == 0 || short >= long || signal == 0 {
+        return invalid("periods must satisfy 0 < short < long and signal > 0".into());
+    }
+    let points: Vec<(TsMillis, f64)> = match timestamps {
+        Some(ts) if ts.len() != prices.len() => {
+            return invalid(format!(
+                "got {} timestamps for {} prices",
+                ts.len(),
+                prices.len()
+            ));
+        }
+        Some(ts) => ts.into_iter().zip(prices).collect(),
+        None => prices
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| (TsMillis(i as i64), p))
+            .collect(),
+    };
+
+    let decimals = state.config.server.response_decimals;
+    let series = if log_scale {
+        match compute_macd_series_log(&points, short, long, signal) {
+            Ok(mut series) => {
+                if flag_warmup {
+                    mark_warmup(&mut series, long);
+                }
+                series
+            }
+            Err(i) => {
+                return invalid(format!("`prices[{}]` must be positive for log_scale", i));
+            }
         }
+    } else if flag_warmup {
+        compute_macd_series_flagged(&points, short, long, signal)
+    } else {
+        compute_macd_series(&points, short, long, signal)
+    };
+    let series: Vec<MACDPoint> = series.iter().map(|p| p.rounded(decimals)).collect();
+    HttpResponse::Ok().json(ApiResponse::success(series))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenSimRequest {
+    /// day to generate, defaults to yesterday in the market timezone
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+struct GenSimResponse {
+    symbol: String,
+    date: NaiveDate,
+    ticks: usize,
+}
+
+/// Generate and store a simulated trading day (admin only).
+#[post("/api/gen_sim/{symbol}")]
+#[instrument(skip(state, req, body))]
+async fn gen_sim(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<GenSimRequest>>,
+) -> impl Responder {
+    if let Some(rejection) = require_admin(&req, &state.config) {
+        return rejection;
+    }
+    let mode = *state.mode.read().await;
+    if mode == RunMode::Real {
+        return HttpResponse::Conflict().json(ApiResponse::<()>::error(
+            "Simulated ticks are only stored in sim mode; switch with /api/set_mode/sim".into(),
+        ));
+    }
+    let symbol = path.into_inner();
+    let date = body
+        .and_then(|b| b.date)
+        .unwrap_or_else(|| market_today(state.config.trading.timezone) - chrono::Duration::days(1));
+
+    let trading = &state.config.trading;
+    match generate_mock_day(state.trading_app.get_storage(), &symbol, date, trading).await {
+        Ok(ticks) => HttpResponse::Ok().json(ApiResponse::success(GenSimResponse {
+            symbol,
+            date,
+            ticks,
+        })),
         Err(e) => handle_error(e),
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct QuoteQuery {
+    /// bypass the quote cache; each such call hits the upstream sources and counts
+    /// against their rate limits
+    #[serde(default)]
+    fresh: bool,
+}
+
+/// Real-time quote from the upstream sources, cached for `data_source.cache.quote_ttl`.
+#[get("/api/quote/{symbol}")]
+#[instrument(skip(state))]
+async fn get_quote(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<QuoteQuery>,
+) -> impl Responder {
+    match state
+        .fetcher
+        .get_quote_with(&path.into_inner(), query.fresh)
+        .await
+    {
+        Ok(quote) => HttpResponse::Ok().json(ApiResponse::success(quote)),
+        Err(e) => handle_error(e),
+    }
+}
+
+/// Name/exchange for a symbol; fetched from EastMoney and stored on first lookup.
+#[get("/api/meta/{symbol}")]
+#[instrument(skip(state))]
+async fn get_meta(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
+    // stored under the suffixed form the fetcher returns, so look it up that way too
+    let symbol = state.fetcher.normalize_symbol(&path.into_inner());
+    let storage = state.trading_app.get_storage();
+
+    match storage.get_symbol_meta(&symbol).await {
+        Ok(Some(meta)) => return HttpResponse::Ok().json(ApiResponse::success(meta)),
+        Ok(None) => debug!("No stored metadata for {}, fetching", symbol),
+        Err(e) => return handle_error(e),
+    }
+
+    let meta = match state.fetcher.get_symbol_meta(&symbol).await {
+        Ok(meta) => meta,
+        Err(e) => return handle_error(e),
+    };
+    if let Err(e) = storage.upsert_symbol_meta(&meta).await {
+        return handle_error(e);
+    }
+    HttpResponse::Ok().json(ApiResponse::success(meta))
+}
+
+#[get("/api/sources")]
+#[instrument(skip(state))]
+async fn get_sources(state: web::Data<AppState>) -> impl Responder {
+    let statuses = state.fetcher.source_statuses().await;
+    HttpResponse::Ok().json(ApiResponse::success(statuses))
+}
+
 #[get("/api/health")]
 #[instrument]
 async fn health_check() -> impl Responder {
     HttpResponse::Ok().json(ApiResponse::success("healthy"))
 }
 
-pub async fn start_web(trading_app: Arc<TradingApp>, host: &str, port: u16) -> std::io::Result<()> {
+/// Liveness probe: answering at all means the process is up.
+#[get("/api/livez")]
+async fn livez() -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success("alive"))
+}
+
+/// Longest a readiness probe waits on one dependency.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+struct Readiness {
+    sqlite: bool,
+    redis: bool,
+    /// `None` when `server.prime_cache` is off
+    cache_primed: Option<bool>,
+}
+
+impl Readiness {
+    fn is_ready(&self) -> bool {
+        self.sqlite && self.redis && self.cache_primed != Some(false)
+    }
+}
+
+/// Readiness probe: 200 while SQLite and Redis answer and any configured cache priming
+/// has finished, 503 otherwise, so the instance leaves rotation without a restart.
+#[get("/api/readyz")]
+#[instrument(skip(state))]
+async fn readyz(state: web::Data<AppState>) -> impl Responder {
+    let storage = state.trading_app.get_storage();
+    let check = |name: &'static str, result: Result<Result<()>, tokio::time::error::Elapsed>| {
+        match result {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                warn!("Readiness: {}: {:#}", name, e);
+                false
+            }
+            Err(_) => {
+                warn!("Readiness: {} timed out", name);
+                false
+            }
+        }
+    };
+    let (sqlite, redis) = futures::join!(
+        tokio::time::timeout(READINESS_TIMEOUT, storage.ping_sqlite()),
+        tokio::time::timeout(READINESS_TIMEOUT, storage.ping_redis()),
+    );
+    let readiness = Readiness {
+        sqlite: check("sqlite", sqlite),
+        redis: check("redis", redis),
+        cache_primed: state
+            .config
+            .server
+            .prime_cache
+            .then(|| state.trading_app.cache_primed()),
+    };
+
+    if readiness.is_ready() {
+        HttpResponse::Ok().json(ApiResponse::success(readiness))
+    } else {
+        HttpResponse::ServiceUnavailable().json(ApiResponse {
+            success: false,
+            data: Some(readiness),
+            error: Some("not ready".to_string()),
+        })
+    }
+}
+
+/// Serve the frontend from `static_dir`. Unknown non-API GET paths get `index.html` so
+/// client-side routes survive a refresh; unknown `/api` paths still 404 with a JSON
+/// error. Register after `configure_api`, as it matches every path.
+pub fn configure_static(static_dir: &str) -> impl FnOnce(&mut web::ServiceConfig) + use<> {
+    let static_dir = PathBuf::from(static_dir);
+    move |cfg| {
+        let index = Arc::new(static_dir.join("index.html"));
+        let fallback = fn_service(move |req: ServiceRequest| {
+            let index = index.clone();
+            async move {
+                let (req, _) = req.into_parts();
+                let res = spa_fallback(&req, &index).await;
+                Ok(ServiceResponse::new(req, res))
+            }
+        });
+        cfg.service(
+            Files::new("/", static_dir)
+                .index_file("index.html")
+                .default_handler(fallback),
+        );
+    }
+}
+
+async fn spa_fallback(req: &HttpRequest, index: &Path) -> HttpResponse {
+    let path = req.path();
+    if path == "/api" || path.starts_with("/api/") {
+        return HttpResponse::NotFound().json(ApiResponse::<()>::error(format!(
+            "No such endpoint: {}",
+            path
+        )));
+    }
+    // `Files` has already answered anything but GET/HEAD with a 405
+    match NamedFile::open_async(index).await {
+        Ok(file) => file.into_response(req),
+        Err(e) => {
+            warn!(
+                "SPA fallback for {} has no {}: {}",
+                path,
+                index.display(),
+                e
+            );
+            HttpResponse::NotFound().finish()
+        }
+    }
+}
+
+/// JSON body extractor settings: bodies over `max_payload_bytes` get a 413 and any other
+/// unreadable body a 400, both with the usual `ApiResponse` error instead of plain text.
+pub fn json_config(max_payload_bytes: usize) -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(max_payload_bytes)
+        .error_handler(|err, _req| {
+            let status = match err {
+                JsonPayloadError::Overflow { .. }
+                | JsonPayloadError::OverflowKnownLength { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            let response =
+                HttpResponse::build(status).json(ApiResponse::<()>::error(err.to_string()));
+            actix_web::error::InternalError::from_response(err, response).into()
+        })
+}
+
+/// Register every `/api` route. Shared by `start_web` and the handler tests.
+pub fn configure_api(cfg: &mut web::ServiceConfig) {
+    // malformed or unknown query parameters get the usual JSON error body
+    cfg.app_data(web::QueryConfig::default().error_handler(|err, _req| {
+        let response = HttpResponse::BadRequest().json(ApiResponse::<()>::error(err.to_string()));
+        actix_web::error::InternalError::from_response(err, response).into()
+    }));
+    cfg.service(set_mode)
+        .service(get_mode)
+        .service(get_mode_history)
+        .service(get_status)
+        .service(market_summary)
+        .service(debug_stats)
+        .service(admin_backup)
+        .service(warm_cache)
+        .service(start_task)
+        .service(stop_task)
+        .service(latest_batch)
+        .service(latest)
+        .service(get_symbols)
+        .service(search)
+        .service(history)
+        .service(ohlc)
+        .service(correlation)
+        .service(compare)
+        .service(report)
+        .service(get_ticks)
+        .service(replay)
+        .service(backtest_sweep)
+        .service(compute_macd)
+        .service(stream_ticks)
+        .service(gen_sim)
+        .service(get_quote)
+        .service(get_meta)
+        .service(get_sources)
+        .service(health_check)
+        .service(livez)
+        .service(readyz);
+}
+
+pub async fn start_web(
+    trading_app: Arc<TradingApp>,
+    fetcher: Arc<DataFetcher>,
+    feed: TickFeed,
+    tasks: TaskRegistry,
+    host: &str,
+    port: u16,
+) -> std::io::Result<()> {
     let config = trading_app.get_config().clone();
     let config = Arc::new(config);
 
-    let mode = Arc::new(RwLock::new(RunMode::Sim)); // Default to Sim mode
+    // shared with storage, which drops tick writes from the other mode; starts in
+    // `database.run_mode`
+    let mode = trading_app.get_storage().run_mode();
     let state = AppState {
         mode,
+        mode_history: Arc::new(RwLock::new(VecDeque::new())),
         trading_app,
+        fetcher,
+        feed,
+        sse_connections: Arc::new(AtomicUsize::new(0)),
+        tasks,
+        replay_running: Arc::new(AtomicBool::new(false)),
         config,
     };
 
     info!("Starting web server at {}:{}", host, port);
+    let request_timeout = Duration::from_secs(state.config.server.timeout);
 
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(state.clone()))
-            .service(set_mode)
-            .service(get_mode)
-            .service(get_status)
-            .service(latest)
-            .service(get_symbols)
-            .service(history)
-            .service(health_check)
-            .service(actix_files::Files::new("/", "./static").index_file("index.html"))
+            .app_data(json_config(state.config.server.max_payload_bytes))
+            .configure(configure_api)
+            .configure(configure_static(&state.config.server.static_dir))
     })
+    .client_request_timeout(request_timeout)
     .bind((host, port))?
     .run()
     .await